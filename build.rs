@@ -4,8 +4,22 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
     let out_dir = PathBuf::from(env::var("OUT_DIR").unwrap());
 
     tonic_build::configure()
-        .file_descriptor_set_path(out_dir.join("message_descriptor.bin"))
-        .compile(&["proto/message.proto"], &["proto"])?;
+        .file_descriptor_set_path(out_dir.join("identification_descriptor.bin"))
+        // The legacy `Message` envelope decodes its JSON payloads into these messages.
+        .message_attribute(
+            ".identity.v1",
+            "#[derive(serde::Serialize, serde::Deserialize)] #[serde(default)]",
+        )
+        .compile(
+            &[
+                "proto/message.proto",
+                "proto/identity/v1/user.proto",
+                "proto/identity/v1/auth.proto",
+                "proto/identity/v1/organization.proto",
+                "proto/identity/v1/delegation.proto",
+            ],
+            &["proto"],
+        )?;
 
     Ok(())
 }
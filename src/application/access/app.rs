@@ -0,0 +1,613 @@
+use super::permission::{Permission, PermissionError, RolePermissions};
+use crate::{
+    application::{
+        auth::token::AccessClaims,
+        topic::request::{RequestFindUserError, RequestGetUserByPrimaryKey},
+    },
+    domain::{
+        organization::{entity::OrganizationRole, repository::OrganizationRepository},
+        user::{entity::User, repository::UserRepository},
+    },
+};
+use anyhow::bail;
+use charybdis::types::Timeuuid;
+use std::{future::Future, str::FromStr, sync::Arc};
+use uptop_core::common::result::AppResult;
+
+/// Who is acting: the subject of an access token, or a stored user.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Actor {
+    pub user_id: Timeuuid,
+    /// The global `UserRole`.
+    pub role: String,
+}
+
+impl Actor {
+    /// Whether `user_id`, as sent by a client, is this actor.
+    pub fn is_user(&self, user_id: &str) -> bool {
+        Timeuuid::from_str(user_id).is_ok_and(|user_id| user_id == self.user_id)
+    }
+}
+
+impl TryFrom<&AccessClaims> for Actor {
+    type Error = anyhow::Error;
+
+    fn try_from(claims: &AccessClaims) -> AppResult<Self> {
+        Ok(Self {
+            user_id: Timeuuid::from_str(&claims.user_id)?,
+            role: claims.role.clone(),
+        })
+    }
+}
+
+impl From<&User> for Actor {
+    fn from(user: &User) -> Self {
+        Self {
+            user_id: user.user_id,
+            role: (*user.role).to_string(),
+        }
+    }
+}
+
+/// What an action is done to.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Resource {
+    /// Every user at once, which only a global role can grant.
+    AllUsers,
+    User {
+        user_id: Timeuuid,
+    },
+    Organization {
+        organization_id: Timeuuid,
+    },
+}
+
+/// Fails unless `caller` may do `action` on `resource`, with
+/// [`PermissionError::Unauthenticated`] when there is no caller.
+pub async fn authorize<AA: AccessAppInterface>(
+    access_app: &AA,
+    caller: Option<&Actor>,
+    action: Permission,
+    resource: Resource,
+) -> AppResult<Actor> {
+    let Some(caller) = caller else {
+        bail!(PermissionError::Unauthenticated)
+    };
+    access_app
+        .check_permission(caller, action, &resource)
+        .await?;
+    Ok(caller.clone())
+}
+
+/// Like [`authorize`] on the user `user_id`, as sent by a client.
+pub async fn authorize_user<AA: AccessAppInterface>(
+    access_app: &AA,
+    caller: Option<&Actor>,
+    action: Permission,
+    user_id: &str,
+) -> AppResult<Actor> {
+    let user_id = match Timeuuid::from_str(user_id) {
+        Ok(user_id) => user_id,
+        Err(_) if caller.is_none() => bail!(PermissionError::Unauthenticated),
+        Err(_) => bail!(PermissionError::Denied { permission: action }),
+    };
+    authorize(access_app, caller, action, Resource::User { user_id }).await
+}
+
+/// Like [`authorize`] on the organization `organization_id`, as sent by a
+/// client.
+pub async fn authorize_organization<AA: AccessAppInterface>(
+    access_app: &AA,
+    caller: Option<&Actor>,
+    action: Permission,
+    organization_id: &str,
+) -> AppResult<Actor> {
+    let organization_id = match Timeuuid::from_str(organization_id) {
+        Ok(organization_id) => organization_id,
+        Err(_) if caller.is_none() => bail!(PermissionError::Unauthenticated),
+        Err(_) => bail!(PermissionError::Denied { permission: action }),
+    };
+    let resource = Resource::Organization { organization_id };
+    authorize(access_app, caller, action, resource).await
+}
+
+pub trait AccessAppInterface: Clone + Send + Sync + 'static {
+    /// Fails with [`PermissionError::Denied`] unless a role of `actor` grants
+    /// `action` on `resource`: its global role, itself for its own user, its
+    /// delegated role on a user it manages, or its role in the organization
+    /// (for a user, any organization of that user).
+    fn check_permission(
+        &self,
+        actor: &Actor,
+        action: Permission,
+        resource: &Resource,
+    ) -> impl Future<Output = AppResult<()>> + Send;
+}
+
+#[derive(Clone, Debug)]
+pub struct AccessApp<US, OS>
+where
+    US: UserRepository,
+    OS: OrganizationRepository,
+{
+    user_repo: Arc<US>,
+    organization_repo: Arc<OS>,
+    role_permissions: Arc<RolePermissions>,
+}
+
+impl<US, OS> AccessApp<US, OS>
+where
+    US: UserRepository,
+    OS: OrganizationRepository,
+{
+    pub fn new(
+        user_repo: Arc<US>,
+        organization_repo: Arc<OS>,
+        role_permissions: Arc<RolePermissions>,
+    ) -> Self {
+        Self {
+            user_repo,
+            organization_repo,
+            role_permissions,
+        }
+    }
+
+    /// Whether the role of `actor_id` in the organization grants `action`.
+    async fn organization_grants(
+        &self,
+        organization_id: Timeuuid,
+        actor_id: Timeuuid,
+        action: Permission,
+    ) -> AppResult<bool> {
+        let Some(member) = self
+            .organization_repo
+            .find_member(organization_id, actor_id)
+            .await?
+        else {
+            return Ok(false);
+        };
+        let role = OrganizationRole::parse(Some(&member.role))?;
+        Ok(self.role_permissions.organization_grants(&role, action))
+    }
+}
+
+impl<US, OS> AccessAppInterface for AccessApp<US, OS>
+where
+    US: UserRepository,
+    OS: OrganizationRepository,
+{
+    async fn check_permission(
+        &self,
+        actor: &Actor,
+        action: Permission,
+        resource: &Resource,
+    ) -> AppResult<()> {
+        if self.role_permissions.global_grants(&actor.role, action) {
+            return Ok(());
+        }
+
+        let granted = match resource {
+            Resource::AllUsers => false,
+            Resource::User { user_id } if *user_id == actor.user_id => {
+                self.role_permissions.own_grants(action)
+            }
+            Resource::User { user_id } => {
+                let lookup = RequestGetUserByPrimaryKey::from_user_id(user_id);
+                let user = match self.user_repo.find_user_by_id(&lookup).await {
+                    Ok(user) => user,
+                    Err(err) if err.is::<RequestFindUserError>() => User::default(),
+                    Err(err) => return Err(err),
+                };
+                let delegated = user
+                    .delegation_role(actor.user_id)
+                    .is_some_and(|role| self.role_permissions.delegated_grants(&role, action));
+                if delegated {
+                    return Ok(());
+                }
+
+                let organizations = user.organizations.unwrap_or_default();
+                let mut granted = false;
+                for organization_id in organizations {
+                    if self
+                        .organization_grants(organization_id, actor.user_id, action)
+                        .await?
+                    {
+                        granted = true;
+                        break;
+                    }
+                }
+                granted
+            }
+            Resource::Organization { organization_id } => {
+                self.organization_grants(*organization_id, actor.user_id, action)
+                    .await?
+            }
+        };
+
+        if !granted {
+            bail!(PermissionError::Denied { permission: action })
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        application::topic::request::{
+            RequestGetUser, RequestGetUserByPartitionKey, RequestUpdateUserStatus,
+        },
+        domain::{
+            organization::{
+                entity::{Organization, OrganizationMember},
+                repository::MemberPage,
+            },
+            user::{
+                entity::{UserColumn, UserDeletion, UserStatus},
+                repository::{StatusHistoryPage, UserPage},
+            },
+        },
+    };
+    use anyhow::anyhow;
+    use charybdis::types::Timestamp;
+    use uptop_core::common::utils::now_timeuuid;
+
+    /// What the fakes answer to calls `check_permission` never makes.
+    fn unsupported<T>() -> AppResult<T> {
+        Err(anyhow!("not served by this fake"))
+    }
+
+    /// Serves the lookups `check_permission` makes.
+    #[derive(Clone, Debug, Default)]
+    struct Users(Vec<User>);
+
+    impl UserRepository for Users {
+        async fn create_user<'c>(&self, _: &'c User) -> AppResult<&'c User> {
+            unsupported()
+        }
+
+        async fn remove_user(&self, _: &User) -> AppResult<()> {
+            unsupported()
+        }
+
+        async fn find_user_by_id(&self, query: &RequestGetUserByPrimaryKey) -> AppResult<User> {
+            self.0
+                .iter()
+                .find(|user| user.user_id.to_string() == query.user_id)
+                .cloned()
+                .ok_or_else(|| anyhow!(RequestFindUserError::UserNotFound))
+        }
+
+        async fn find_user(&self, _: &RequestGetUser) -> AppResult<User> {
+            unsupported()
+        }
+
+        async fn find_users(
+            &self,
+            _: &RequestGetUserByPartitionKey,
+            _: i32,
+            _: Option<Vec<u8>>,
+        ) -> AppResult<UserPage> {
+            unsupported()
+        }
+
+        async fn push_new_user_status(
+            &self,
+            _: &RequestUpdateUserStatus,
+            _: &UserStatus,
+        ) -> AppResult<bool> {
+            unsupported()
+        }
+
+        async fn update_user_columns<'u>(
+            &self,
+            _: &'u User,
+            _: &[UserColumn],
+        ) -> AppResult<&'u User> {
+            unsupported()
+        }
+
+        async fn move_user<'u>(&self, _: &User, _: &'u User) -> AppResult<&'u User> {
+            unsupported()
+        }
+
+        async fn claim_email(&self, _: &User) -> AppResult<()> {
+            unsupported()
+        }
+
+        async fn release_email(&self, _: &str, _: Timeuuid) -> AppResult<()> {
+            unsupported()
+        }
+
+        async fn schedule_purge(&self, _: &UserDeletion) -> AppResult<()> {
+            unsupported()
+        }
+
+        async fn cancel_purge(&self, _: Timeuuid) -> AppResult<()> {
+            unsupported()
+        }
+
+        async fn find_due_purges(&self, _: Timestamp) -> AppResult<Vec<UserDeletion>> {
+            unsupported()
+        }
+
+        async fn purge_user<'u>(
+            &self,
+            _: &User,
+            _: &'u User,
+            _: &[UserColumn],
+        ) -> AppResult<&'u User> {
+            unsupported()
+        }
+
+        async fn find_status_history(
+            &self,
+            _: Timeuuid,
+            _: i32,
+            _: Option<Vec<u8>>,
+        ) -> AppResult<StatusHistoryPage> {
+            unsupported()
+        }
+    }
+
+    #[derive(Clone, Debug, Default)]
+    struct Members(Vec<OrganizationMember>);
+
+    impl OrganizationRepository for Members {
+        async fn create_organization(&self, _: &Organization, _: &User) -> AppResult<()> {
+            unsupported()
+        }
+
+        async fn find_organization(&self, _: Timeuuid) -> AppResult<Option<Organization>> {
+            unsupported()
+        }
+
+        async fn update_organization(&self, _: &Organization) -> AppResult<()> {
+            unsupported()
+        }
+
+        async fn delete_organization(&self, _: Timeuuid) -> AppResult<()> {
+            unsupported()
+        }
+
+        async fn find_member(
+            &self,
+            organization_id: Timeuuid,
+            user_id: Timeuuid,
+        ) -> AppResult<Option<OrganizationMember>> {
+            Ok(self
+                .0
+                .iter()
+                .find(|member| {
+                    member.organization_id == organization_id && member.user_id == user_id
+                })
+                .cloned())
+        }
+
+        async fn find_members(
+            &self,
+            _: Timeuuid,
+            _: i32,
+            _: Option<Vec<u8>>,
+        ) -> AppResult<MemberPage> {
+            unsupported()
+        }
+
+        async fn add_member(&self, _: &OrganizationMember, _: &User) -> AppResult<()> {
+            unsupported()
+        }
+
+        async fn remove_member(&self, _: Timeuuid, _: &User) -> AppResult<()> {
+            unsupported()
+        }
+
+        async fn set_active_organization(&self, _: &User, _: Option<Timeuuid>) -> AppResult<()> {
+            unsupported()
+        }
+    }
+
+    fn access_app(users: Vec<User>, members: Vec<OrganizationMember>) -> AccessApp<Users, Members> {
+        let role_permissions = RolePermissions::parse(include_str!("role_permissions.json"));
+        AccessApp::new(
+            Arc::new(Users(users)),
+            Arc::new(Members(members)),
+            Arc::new(role_permissions.unwrap()),
+        )
+    }
+
+    fn actor(role: &str) -> Actor {
+        Actor {
+            user_id: now_timeuuid(),
+            role: role.to_owned(),
+        }
+    }
+
+    fn user(user_id: Timeuuid) -> Resource {
+        Resource::User { user_id }
+    }
+
+    fn is_denied(result: AppResult<()>) -> bool {
+        matches!(
+            result.unwrap_err().downcast_ref(),
+            Some(PermissionError::Denied { .. })
+        )
+    }
+
+    #[tokio::test]
+    async fn global_roles_are_checked_before_any_lookup() {
+        // Empty repositories: a lookup would deny, so only the role can grant.
+        let app = access_app(vec![], vec![]);
+        let admin = actor("Admin");
+
+        for resource in [Resource::AllUsers, user(now_timeuuid())] {
+            let result = app
+                .check_permission(&admin, Permission::UserPurge, &resource)
+                .await;
+            assert!(result.is_ok());
+        }
+        let result = app
+            .check_permission(&actor("Member"), Permission::UserRead, &Resource::AllUsers)
+            .await;
+        assert!(is_denied(result));
+    }
+
+    #[tokio::test]
+    async fn users_get_the_self_permissions_on_themselves_only() {
+        let app = access_app(vec![], vec![]);
+        let member = actor("Member");
+        let own = user(member.user_id);
+
+        assert!(app
+            .check_permission(&member, Permission::UserUpdate, &own)
+            .await
+            .is_ok());
+        assert!(is_denied(
+            app.check_permission(&member, Permission::UserUpdateStatus, &own)
+                .await
+        ));
+        assert!(is_denied(
+            app.check_permission(&member, Permission::UserUpdate, &user(now_timeuuid()))
+                .await
+        ));
+    }
+
+    #[tokio::test]
+    async fn delegates_get_their_role_on_the_managed_user() {
+        let owner = actor("Member");
+        let admin = actor("Member");
+        let managed = User {
+            user_id: now_timeuuid(),
+            owners: Some(vec![owner.user_id]),
+            admins: Some(vec![admin.user_id]),
+            ..Default::default()
+        };
+        let app = access_app(vec![managed.clone()], vec![]);
+        let resource = user(managed.user_id);
+
+        assert!(app
+            .check_permission(&owner, Permission::UserDelegate, &resource)
+            .await
+            .is_ok());
+        assert!(app
+            .check_permission(&admin, Permission::UserResetPassword, &resource)
+            .await
+            .is_ok());
+        assert!(is_denied(
+            app.check_permission(&admin, Permission::UserDelegate, &resource)
+                .await
+        ));
+    }
+
+    #[tokio::test]
+    async fn delegates_get_nothing_beyond_the_delegated_permissions() {
+        let owner = actor("Member");
+        let admin = actor("Member");
+        let mut managed = User {
+            user_id: now_timeuuid(),
+            owners: Some(vec![owner.user_id]),
+            admins: Some(vec![admin.user_id]),
+            ..Default::default()
+        };
+        let other = User {
+            user_id: now_timeuuid(),
+            ..Default::default()
+        };
+        let app = access_app(vec![managed.clone(), other.clone()], vec![]);
+        let resource = user(managed.user_id);
+
+        for permission in [
+            Permission::UserUpdate,
+            Permission::UserUpdateStatus,
+            Permission::UserDelete,
+            Permission::UserPurge,
+        ] {
+            assert!(is_denied(
+                app.check_permission(&owner, permission, &resource).await
+            ));
+            assert!(is_denied(
+                app.check_permission(&admin, permission, &resource).await
+            ));
+        }
+        assert!(is_denied(
+            app.check_permission(&owner, Permission::UserDisable, &user(other.user_id))
+                .await
+        ));
+
+        // Revoking takes effect on the next check.
+        managed.set_delegation(admin.user_id, None);
+        let app = access_app(vec![managed], vec![]);
+        assert!(is_denied(
+            app.check_permission(&admin, Permission::UserDisable, &resource)
+                .await
+        ));
+    }
+
+    #[tokio::test]
+    async fn organization_roles_apply_to_the_organization_and_its_users() {
+        let organization_id = now_timeuuid();
+        let org_admin = actor("Member");
+        let colleague = User {
+            user_id: now_timeuuid(),
+            organizations: Some(vec![organization_id]),
+            ..Default::default()
+        };
+        let member = |user_id, role: &str| OrganizationMember {
+            organization_id,
+            user_id,
+            role: role.to_owned(),
+            ..Default::default()
+        };
+        let app = access_app(
+            vec![colleague.clone()],
+            vec![
+                member(org_admin.user_id, "Admin"),
+                member(colleague.user_id, "Member"),
+            ],
+        );
+        let organization = Resource::Organization { organization_id };
+
+        assert!(app
+            .check_permission(&org_admin, Permission::OrgInvite, &organization)
+            .await
+            .is_ok());
+        assert!(is_denied(
+            app.check_permission(&org_admin, Permission::OrgDelete, &organization)
+                .await
+        ));
+        assert!(app
+            .check_permission(&org_admin, Permission::UserRead, &user(colleague.user_id))
+            .await
+            .is_ok());
+        assert!(is_denied(
+            app.check_permission(&org_admin, Permission::UserUpdate, &user(colleague.user_id))
+                .await
+        ));
+        assert!(is_denied(
+            app.check_permission(&actor("Member"), Permission::OrgRead, &organization)
+                .await
+        ));
+    }
+
+    #[tokio::test]
+    async fn unknown_users_grant_nothing_but_global_roles() {
+        let app = access_app(vec![], vec![]);
+
+        assert!(is_denied(
+            app.check_permission(
+                &actor("Manager"),
+                Permission::UserUpdate,
+                &user(now_timeuuid())
+            )
+            .await
+        ));
+        assert!(app
+            .check_permission(
+                &actor("Manager"),
+                Permission::UserRead,
+                &user(now_timeuuid())
+            )
+            .await
+            .is_ok());
+    }
+}
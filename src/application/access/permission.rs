@@ -0,0 +1,243 @@
+use crate::domain::{
+    organization::entity::OrganizationRole,
+    user::entity::{DelegationRole, UserRole},
+};
+use anyhow::{anyhow, Context};
+use serde::Deserialize;
+use std::{
+    collections::{HashMap, HashSet},
+    fmt::{Display, Formatter},
+    path::Path,
+};
+use thiserror::Error;
+use uptop_core::common::result::AppResult;
+
+static DEFAULT_ROLE_PERMISSIONS: &str = include_str!("role_permissions.json");
+
+/// The catalog of actions a role can be allowed.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Permission {
+    UserRead,
+    UserUpdate,
+    UserUpdateStatus,
+    UserDelete,
+    UserPurge,
+    UserDelegate,
+    UserResetPassword,
+    UserDisable,
+    OrgRead,
+    OrgUpdate,
+    OrgDelete,
+    OrgInvite,
+    OrgManageMembers,
+}
+
+impl Permission {
+    pub const ALL: [Permission; 13] = [
+        Permission::UserRead,
+        Permission::UserUpdate,
+        Permission::UserUpdateStatus,
+        Permission::UserDelete,
+        Permission::UserPurge,
+        Permission::UserDelegate,
+        Permission::UserResetPassword,
+        Permission::UserDisable,
+        Permission::OrgRead,
+        Permission::OrgUpdate,
+        Permission::OrgDelete,
+        Permission::OrgInvite,
+        Permission::OrgManageMembers,
+    ];
+
+    /// The name used in the role mapping file, e.g. `user.update_status`.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Permission::UserRead => "user.read",
+            Permission::UserUpdate => "user.update",
+            Permission::UserUpdateStatus => "user.update_status",
+            Permission::UserDelete => "user.delete",
+            Permission::UserPurge => "user.purge",
+            Permission::UserDelegate => "user.delegate",
+            Permission::UserResetPassword => "user.reset_password",
+            Permission::UserDisable => "user.disable",
+            Permission::OrgRead => "org.read",
+            Permission::OrgUpdate => "org.update",
+            Permission::OrgDelete => "org.delete",
+            Permission::OrgInvite => "org.invite",
+            Permission::OrgManageMembers => "org.manage_members",
+        }
+    }
+
+    pub fn parse(input: &str) -> AppResult<Permission> {
+        Permission::ALL
+            .into_iter()
+            .find(|permission| permission.as_str() == input)
+            .ok_or_else(|| {
+                anyhow!(PermissionError::Unknown {
+                    name: input.to_owned()
+                })
+            })
+    }
+}
+
+impl Display for Permission {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+#[derive(Debug, Error)]
+pub enum PermissionError {
+    #[error("Sign in required")]
+    Unauthenticated,
+    #[error("Permission {name} not found!")]
+    Unknown { name: String },
+    #[error("Missing permission {permission}")]
+    Denied { permission: Permission },
+}
+
+/// Which permissions each role grants. `own` applies to a user acting on
+/// itself, `global` to the `UserRole` of the actor, `organization` to its
+/// `OrganizationRole` in the organization a resource belongs to and
+/// `delegated` to its `DelegationRole` on a user it manages. Read from the
+/// JSON file at `ROLE_PERMISSIONS_PATH`, or the built-in
+/// `role_permissions.json`.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct RolePermissions {
+    own: HashSet<Permission>,
+    global: HashMap<String, HashSet<Permission>>,
+    organization: HashMap<String, HashSet<Permission>>,
+    delegated: HashMap<String, HashSet<Permission>>,
+}
+
+#[derive(Deserialize)]
+struct RolePermissionsFile {
+    #[serde(rename = "self", default)]
+    own: Vec<String>,
+    #[serde(default)]
+    global: HashMap<String, Vec<String>>,
+    #[serde(default)]
+    organization: HashMap<String, Vec<String>>,
+    #[serde(default)]
+    delegated: HashMap<String, Vec<String>>,
+}
+
+impl RolePermissions {
+    pub fn from_env() -> AppResult<Self> {
+        match std::env::var("ROLE_PERMISSIONS_PATH") {
+            Ok(path) => Self::from_file(path.trim()),
+            Err(_) => Self::parse(DEFAULT_ROLE_PERMISSIONS),
+        }
+    }
+
+    pub fn from_file(path: impl AsRef<Path>) -> AppResult<Self> {
+        let path = path.as_ref();
+        let json = std::fs::read_to_string(path)
+            .with_context(|| format!("Can not read role permissions {}", path.display()))?;
+        Self::parse(&json).with_context(|| format!("{} is not valid", path.display()))
+    }
+
+    /// Parses a mapping file. Unknown roles and permissions are rejected, so a
+    /// typo can not silently take a permission away.
+    pub fn parse(json: &str) -> AppResult<Self> {
+        let file: RolePermissionsFile = serde_json::from_str(json)?;
+
+        let mut global = HashMap::new();
+        for (role, permissions) in file.global {
+            let role = UserRole::matching(Some(&role))?;
+            global.insert(role, parse_all(&permissions)?);
+        }
+        let mut organization = HashMap::new();
+        for (role, permissions) in file.organization {
+            let role = OrganizationRole::parse(Some(&role))?;
+            organization.insert(role.to_string(), parse_all(&permissions)?);
+        }
+        let mut delegated = HashMap::new();
+        for (role, permissions) in file.delegated {
+            let role = DelegationRole::parse(Some(&role))?;
+            delegated.insert(role.to_string(), parse_all(&permissions)?);
+        }
+
+        Ok(Self {
+            own: parse_all(&file.own)?,
+            global,
+            organization,
+            delegated,
+        })
+    }
+
+    /// Whether a user may do `permission` on itself.
+    pub fn own_grants(&self, permission: Permission) -> bool {
+        self.own.contains(&permission)
+    }
+
+    /// Whether the global `role` grants `permission` on any resource.
+    pub fn global_grants(&self, role: &str, permission: Permission) -> bool {
+        self.global
+            .get(role)
+            .is_some_and(|permissions| permissions.contains(&permission))
+    }
+
+    /// Whether `role` grants `permission` inside its organization.
+    pub fn organization_grants(&self, role: &OrganizationRole, permission: Permission) -> bool {
+        self.organization
+            .get(&role.to_string())
+            .is_some_and(|permissions| permissions.contains(&permission))
+    }
+
+    /// Whether `role` grants `permission` on the user it is listed on.
+    pub fn delegated_grants(&self, role: &DelegationRole, permission: Permission) -> bool {
+        self.delegated
+            .get(&role.to_string())
+            .is_some_and(|permissions| permissions.contains(&permission))
+    }
+}
+
+fn parse_all(names: &[String]) -> AppResult<HashSet<Permission>> {
+    names.iter().map(|name| Permission::parse(name)).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn permission_names_round_trip() {
+        for permission in Permission::ALL {
+            assert_eq!(Permission::parse(permission.as_str()).unwrap(), permission);
+        }
+        assert!(Permission::parse("user.fly").is_err());
+    }
+
+    #[test]
+    fn default_mapping_grants_by_scope() {
+        let permissions = RolePermissions::parse(DEFAULT_ROLE_PERMISSIONS).unwrap();
+
+        assert!(permissions.own_grants(Permission::UserUpdate));
+        assert!(!permissions.own_grants(Permission::UserUpdateStatus));
+        assert!(permissions.global_grants("Admin", Permission::UserPurge));
+        assert!(!permissions.global_grants("Manager", Permission::UserUpdate));
+        assert!(!permissions.global_grants("Unknown", Permission::UserRead));
+        assert!(permissions.organization_grants(&OrganizationRole::Owner, Permission::OrgDelete));
+        assert!(!permissions.organization_grants(&OrganizationRole::Admin, Permission::OrgDelete));
+        assert!(permissions.delegated_grants(&DelegationRole::Owner, Permission::UserDelegate));
+        assert!(!permissions.delegated_grants(&DelegationRole::Admin, Permission::UserDelegate));
+    }
+
+    #[test]
+    fn missing_scopes_grant_nothing() {
+        let permissions = RolePermissions::parse(r#"{ "self": ["user.read"] }"#).unwrap();
+
+        assert!(permissions.own_grants(Permission::UserRead));
+        assert!(!permissions.global_grants("Admin", Permission::UserRead));
+        assert!(!permissions.organization_grants(&OrganizationRole::Owner, Permission::OrgRead));
+    }
+
+    #[test]
+    fn parse_rejects_unknown_roles_and_permissions() {
+        assert!(RolePermissions::parse(r#"{ "self": ["user.fly"] }"#).is_err());
+        assert!(RolePermissions::parse(r#"{ "global": { "Root": [] } }"#).is_err());
+        assert!(RolePermissions::parse(r#"{ "organization": { "Guest": [] } }"#).is_err());
+        assert!(RolePermissions::parse(r#"{ "delegated": { "Member": [] } }"#).is_err());
+    }
+}
@@ -0,0 +1,1658 @@
+use super::{
+    cipher::SecretCipher,
+    lockout::LockoutPolicy,
+    password::{verify_dummy_password, verify_password},
+    policy::PasswordPolicy,
+    request::{
+        RequestConfirmPasswordReset, RequestConfirmTotp, RequestEnrollTotp, RequestLogin,
+        RequestLoginError, RequestMfaError, RequestPasswordReset, RequestPasswordResetError,
+        RequestRefreshToken, RequestRefreshTokenError, RequestResetMfa, RequestUnlockUser,
+        RequestVerifyMfa,
+    },
+    response::{AuthenticatedUser, LoginOutcome, PendingMfa, TotpEnrollment},
+    secret::{generate_secret, hash_secret, secret_matches},
+    token::{AccessClaims, TokenIssuer, TokenVerifier},
+    totp::{
+        generate_backup_codes, generate_totp_secret, is_totp_code, matching_step, new_totp,
+        normalize_backup_code,
+    },
+};
+use crate::{
+    application::topic::{
+        app::change_status,
+        request::{RequestFindUserError, RequestGetUser, RequestGetUserByPrimaryKey},
+    },
+    domain::{
+        auth::{
+            entity::{LoginAttempt, MfaChallenge, MfaFactor, RefreshToken},
+            repository::{LoginAttemptRepository, MfaRepository, RefreshTokenRepository},
+        },
+        mail::{Mail, MailSender},
+        user::{
+            entity::{ReasonOfStatus, StatusChangedBy, User, UserColumn, UserStatus},
+            repository::UserRepository,
+        },
+    },
+};
+use anyhow::{anyhow, bail};
+use charybdis::types::Timeuuid;
+use chrono::{DateTime, Duration, Utc};
+use std::{future::Future, sync::Arc};
+use uptop_core::common::{
+    result::AppResult,
+    utils::{new_password, now_timeuuid},
+};
+
+const PASSWORD_RECOVERY_CODE_TTL_MINUTES: i64 = 30;
+const MFA_CHALLENGE_TTL_MINUTES: i64 = 5;
+/// How often counting a failed login is retried after losing a race.
+const FAILURE_RETRIES: usize = 5;
+
+pub trait AuthAppInterface: Clone + Send + Sync + 'static {
+    fn login(&self, req: RequestLogin) -> impl Future<Output = AppResult<LoginOutcome>> + Send;
+
+    fn refresh(
+        &self,
+        req: RequestRefreshToken,
+    ) -> impl Future<Output = AppResult<AuthenticatedUser>> + Send;
+
+    fn logout(&self, req: RequestRefreshToken) -> impl Future<Output = AppResult<bool>> + Send;
+
+    fn validate_token(&self, token: &str) -> AppResult<AccessClaims>;
+
+    /// Mails a recovery code if `req.email` belongs to an account. The result
+    /// is the same whether or not it does.
+    fn request_password_reset(
+        &self,
+        req: RequestPasswordReset,
+    ) -> impl Future<Output = AppResult<()>> + Send;
+
+    fn confirm_password_reset(
+        &self,
+        req: RequestConfirmPasswordReset,
+    ) -> impl Future<Output = AppResult<()>> + Send;
+
+    /// Starts (or restarts) TOTP enrollment. The factor is not required at
+    /// login until it is confirmed.
+    fn enroll_totp(
+        &self,
+        req: RequestEnrollTotp,
+    ) -> impl Future<Output = AppResult<TotpEnrollment>> + Send;
+
+    /// Confirms enrollment with a first code and returns the backup codes.
+    fn confirm_totp(
+        &self,
+        req: RequestConfirmTotp,
+    ) -> impl Future<Output = AppResult<Vec<String>>> + Send;
+
+    /// Redeems the challenge returned by `login` with a second factor.
+    fn verify_mfa(
+        &self,
+        req: RequestVerifyMfa,
+    ) -> impl Future<Output = AppResult<AuthenticatedUser>> + Send;
+
+    /// Removes the second factor of a user, who then signs in with its
+    /// password alone until it enrolls again. Returns `false` when none was
+    /// enrolled.
+    fn reset_mfa(&self, req: RequestResetMfa) -> impl Future<Output = AppResult<bool>> + Send;
+
+    /// Lifts a lockout from failed logins. Returns `false` when the user was
+    /// not locked.
+    fn unlock_user(&self, req: RequestUnlockUser) -> impl Future<Output = AppResult<bool>> + Send;
+}
+
+#[derive(Clone, Debug)]
+pub struct AuthApp<US, RS, FS, LS, MS>
+where
+    US: UserRepository,
+    RS: RefreshTokenRepository,
+    FS: MfaRepository,
+    LS: LoginAttemptRepository,
+    MS: MailSender,
+{
+    user_repo: Arc<US>,
+    refresh_token_repo: Arc<RS>,
+    mfa_repo: Arc<FS>,
+    login_attempt_repo: Arc<LS>,
+    mail_sender: Arc<MS>,
+    secret_cipher: SecretCipher,
+    lockout: LockoutPolicy,
+    password_policy: PasswordPolicy,
+    token_issuer: TokenIssuer,
+    token_verifier: TokenVerifier,
+    refresh_token_ttl: Duration,
+}
+
+impl<US, RS, FS, LS, MS> AuthApp<US, RS, FS, LS, MS>
+where
+    US: UserRepository,
+    RS: RefreshTokenRepository,
+    FS: MfaRepository,
+    LS: LoginAttemptRepository,
+    MS: MailSender,
+{
+    #[allow(clippy::too_many_arguments)]
+    pub fn new(
+        user_repo: Arc<US>,
+        refresh_token_repo: Arc<RS>,
+        mfa_repo: Arc<FS>,
+        login_attempt_repo: Arc<LS>,
+        mail_sender: Arc<MS>,
+        secret_cipher: SecretCipher,
+        lockout: LockoutPolicy,
+        password_policy: PasswordPolicy,
+        token_issuer: TokenIssuer,
+        token_verifier: TokenVerifier,
+        refresh_token_ttl: Duration,
+    ) -> Self {
+        Self {
+            user_repo,
+            refresh_token_repo,
+            mfa_repo,
+            login_attempt_repo,
+            mail_sender,
+            secret_cipher,
+            lockout,
+            password_policy,
+            token_issuer,
+            token_verifier,
+            refresh_token_ttl,
+        }
+    }
+
+    /// Resolves the user behind `req` and checks the password and status. Every
+    /// credential failure is reported as the same error. Failures count towards
+    /// the lockout of the user and of the client IP.
+    async fn verify_credentials(&self, req: &RequestLogin) -> AppResult<User> {
+        if let Some(ip) = req.client_ip.as_deref() {
+            if let Some(until) = self.locked_until(LoginAttempt::IP_SCOPE, ip).await? {
+                bail!(RequestLoginError::TooManyAttempts {
+                    retry_after_seconds: retry_after_seconds(until)
+                })
+            }
+        }
+
+        let mut user = match self.user_repo.find_user(&req.lookup()).await {
+            Ok(user) => user,
+            Err(err) if err.downcast_ref::<RequestFindUserError>().is_some() => {
+                verify_dummy_password(&req.password);
+                self.record_ip_failure(req).await?;
+                bail!(RequestLoginError::InvalidCredentials)
+            }
+            Err(err) => return Err(err),
+        };
+
+        let user_key = user.user_id.to_string();
+        if let Some(until) = self
+            .locked_until(LoginAttempt::USER_SCOPE, &user_key)
+            .await?
+        {
+            bail!(RequestLoginError::AccountLocked {
+                retry_after_seconds: retry_after_seconds(until)
+            })
+        }
+        if !verify_password(&req.password, &user.password) {
+            self.record_ip_failure(req).await?;
+            let locked_until = self
+                .record_failure(
+                    LoginAttempt::USER_SCOPE,
+                    &user_key,
+                    self.lockout.user_threshold,
+                )
+                .await?;
+            if let Some(until) = locked_until {
+                tracing::warn!(user_id = %user.user_id, %until, "Locking user after failed logins");
+                // Disabled and deleted users are refused anyway.
+                if matches!(
+                    user.current_status()?,
+                    UserStatus::Active(_) | UserStatus::Inactive(_)
+                ) {
+                    self.push_status(
+                        &mut user,
+                        UserStatus::Disable(ReasonOfStatus::TooManyFailedLogins),
+                    )
+                    .await?;
+                }
+            }
+            bail!(RequestLoginError::InvalidCredentials)
+        }
+
+        self.login_attempt_repo
+            .clear_login_attempt(LoginAttempt::USER_SCOPE, &user_key)
+            .await?;
+        // The lock ran out and the password is right, so the lockout is over.
+        if user.current_status()? == UserStatus::Disable(ReasonOfStatus::TooManyFailedLogins) {
+            self.push_status(
+                &mut user,
+                UserStatus::Active(ReasonOfStatus::ComeBackAccess),
+            )
+            .await?;
+        }
+        ensure_can_sign_in(&user)?;
+        Ok(user)
+    }
+
+    /// End of the lock on `subject`, if it is currently locked.
+    async fn locked_until(&self, scope: &str, subject: &str) -> AppResult<Option<DateTime<Utc>>> {
+        let attempt = self
+            .login_attempt_repo
+            .find_login_attempt(scope, subject)
+            .await?;
+
+        Ok(attempt
+            .and_then(|attempt| attempt.locked_until)
+            .filter(|until| *until > Utc::now()))
+    }
+
+    /// Forgets the failed logins of `user` and lifts a status of
+    /// `Disable(TooManyFailedLogins)`. Returns whether `user` was locked.
+    async fn lift_lockout(&self, user: &mut User) -> AppResult<bool> {
+        let user_key = user.user_id.to_string();
+        let was_locked = self
+            .locked_until(LoginAttempt::USER_SCOPE, &user_key)
+            .await?
+            .is_some();
+        self.login_attempt_repo
+            .clear_login_attempt(LoginAttempt::USER_SCOPE, &user_key)
+            .await?;
+
+        let disabled =
+            user.current_status()? == UserStatus::Disable(ReasonOfStatus::TooManyFailedLogins);
+        if disabled {
+            self.push_status(user, UserStatus::Active(ReasonOfStatus::ComeBackAccess))
+                .await?;
+        }
+
+        Ok(was_locked || disabled)
+    }
+
+    /// Counts a failed login of `subject` and returns the end of the lock it
+    /// triggers, if any. The count is compare-and-set, so concurrent failures
+    /// are all counted.
+    async fn record_failure(
+        &self,
+        scope: &str,
+        subject: &str,
+        threshold: i32,
+    ) -> AppResult<Option<DateTime<Utc>>> {
+        for _ in 0..FAILURE_RETRIES {
+            let previous = self
+                .login_attempt_repo
+                .find_login_attempt(scope, subject)
+                .await?;
+
+            let now = Utc::now();
+            let failures = previous.as_ref().map_or(0, |attempt| attempt.failures) + 1;
+            let lock = self.lockout.lock_duration(failures, threshold);
+            let attempt = LoginAttempt {
+                scope: scope.to_owned(),
+                subject: subject.to_owned(),
+                failures,
+                last_failed_at: now,
+                locked_until: lock.map(|lock| now + lock),
+            };
+
+            let ttl = self.lockout.window + lock.unwrap_or_else(Duration::zero);
+            if self
+                .login_attempt_repo
+                .save_login_attempt(previous.as_ref(), &attempt, ttl.num_seconds() as i32)
+                .await?
+            {
+                return Ok(attempt.locked_until);
+            }
+        }
+
+        // Other failures keep winning the race and are counted instead.
+        self.locked_until(scope, subject).await
+    }
+
+    async fn record_ip_failure(&self, req: &RequestLogin) -> AppResult<()> {
+        if let Some(ip) = req.client_ip.as_deref() {
+            let locked_until = self
+                .record_failure(LoginAttempt::IP_SCOPE, ip, self.lockout.ip_threshold)
+                .await?;
+            if let Some(until) = locked_until {
+                tracing::warn!(%ip, %until, "Throttling client after failed logins");
+            }
+        }
+        Ok(())
+    }
+
+    /// Checks a client refresh token and returns its stored row.
+    async fn verify_refresh_token(&self, value: &str) -> AppResult<RefreshToken> {
+        let (user_id, token_id, secret) = RefreshToken::parse_client_value(value)
+            .ok_or_else(|| anyhow!(RequestRefreshTokenError::InvalidRefreshToken))?;
+
+        let token = self
+            .refresh_token_repo
+            .find_refresh_token(user_id, token_id)
+            .await?
+            .filter(|token| token.expires_at > Utc::now())
+            .filter(|token| secret_matches(secret, &token.token_hash))
+            .ok_or_else(|| anyhow!(RequestRefreshTokenError::InvalidRefreshToken))?;
+
+        Ok(token)
+    }
+
+    /// Issues an access token and a refresh token in `family_id`.
+    async fn authenticate(&self, user: &User, family_id: Timeuuid) -> AppResult<AuthenticatedUser> {
+        let access_token = self.token_issuer.issue(AccessClaims {
+            user_id: user.user_id.to_string(),
+            role: (*user.role).to_string(),
+            country: (*user.country).to_string(),
+            region: (*user.region).to_string(),
+            city: (*user.city).to_string(),
+            active_organization: user.active_organization.map(|org| org.to_string()),
+            ..Default::default()
+        })?;
+
+        let secret = generate_secret();
+        let now = Utc::now();
+        let refresh_token = RefreshToken {
+            user_id: user.user_id,
+            token_id: now_timeuuid(),
+            family_id,
+            token_hash: hash_secret(&secret),
+            country: (*user.country).to_string(),
+            region: (*user.region).to_string(),
+            city: (*user.city).to_string(),
+            rotated_at: None,
+            created_at: now,
+            expires_at: now + self.refresh_token_ttl,
+        };
+        self.refresh_token_repo
+            .create_refresh_token(&refresh_token)
+            .await?;
+
+        Ok(AuthenticatedUser {
+            user: user.try_into()?,
+            access_token: access_token.token,
+            access_token_expires_at: access_token.expires_at.to_rfc3339(),
+            refresh_token: refresh_token.to_client_value(&secret),
+            refresh_token_expires_at: refresh_token.expires_at.to_rfc3339(),
+        })
+    }
+
+    /// Marks the user active and issues the session tokens.
+    async fn complete_login(&self, user: &mut User) -> AppResult<AuthenticatedUser> {
+        self.push_status(user, UserStatus::Active(ReasonOfStatus::LoginAgain))
+            .await?;
+        self.authenticate(user, now_timeuuid()).await
+    }
+
+    async fn issue_mfa_challenge(&self, user: &User) -> AppResult<PendingMfa> {
+        let secret = generate_secret();
+        let now = Utc::now();
+        let challenge = MfaChallenge {
+            user_id: user.user_id,
+            challenge_id: now_timeuuid(),
+            challenge_hash: hash_secret(&secret),
+            country: (*user.country).to_string(),
+            region: (*user.region).to_string(),
+            city: (*user.city).to_string(),
+            created_at: now,
+            expires_at: now + Duration::minutes(MFA_CHALLENGE_TTL_MINUTES),
+        };
+        self.mfa_repo.create_mfa_challenge(&challenge).await?;
+
+        Ok(PendingMfa {
+            mfa_token: challenge.to_client_value(&secret),
+            mfa_token_expires_at: challenge.expires_at.to_rfc3339(),
+        })
+    }
+
+    fn decrypt_totp_secret(&self, factor: &MfaFactor) -> AppResult<Vec<u8>> {
+        self.secret_cipher
+            .decrypt(&factor.secret_ciphertext, factor.user_id.as_bytes())
+    }
+
+    /// Accepts a TOTP code once per time step.
+    async fn verify_totp_code(&self, factor: &MfaFactor, user: &User, code: &str) -> AppResult<()> {
+        let totp = new_totp(self.decrypt_totp_secret(factor)?, &user.email)?;
+        let step = matching_step(&totp, code, Utc::now().timestamp() as u64)
+            .map(|step| step as i64)
+            .filter(|step| factor.last_used_step.is_none_or(|last| *step > last))
+            .ok_or_else(|| anyhow!(RequestMfaError::InvalidCode))?;
+
+        match self.mfa_repo.mark_totp_step_used(factor, step).await? {
+            true => Ok(()),
+            false => bail!(RequestMfaError::InvalidCode),
+        }
+    }
+
+    /// Accepts each backup code once.
+    async fn verify_backup_code(&self, factor: &MfaFactor, code: &str) -> AppResult<()> {
+        let code = normalize_backup_code(code);
+        let hashes = factor.backup_code_hashes.clone().unwrap_or_default();
+        let position = hashes
+            .iter()
+            .position(|hash| secret_matches(&code, hash))
+            .ok_or_else(|| anyhow!(RequestMfaError::InvalidCode))?;
+
+        let mut remaining = hashes;
+        remaining.remove(position);
+        match self
+            .mfa_repo
+            .replace_backup_codes(factor, remaining)
+            .await?
+        {
+            true => Ok(()),
+            false => bail!(RequestMfaError::InvalidCode),
+        }
+    }
+
+    async fn find_user_by_email(&self, email: &str) -> AppResult<Option<User>> {
+        let lookup = RequestGetUser {
+            email: Some(email.to_owned()),
+            ..Default::default()
+        };
+        match self.user_repo.find_user(&lookup).await {
+            Ok(user) => Ok(Some(user)),
+            Err(err) if err.downcast_ref::<RequestFindUserError>().is_some() => Ok(None),
+            Err(err) => Err(err),
+        }
+    }
+
+    /// Appends `status` to the stored history and to `user`, if the state
+    /// machine allows it and nobody changed the status since `user` was read.
+    async fn push_status(&self, user: &mut User, status: UserStatus) -> AppResult<()> {
+        change_status(
+            self.user_repo.as_ref(),
+            user,
+            status,
+            StatusChangedBy::System,
+            None,
+            None,
+        )
+        .await
+    }
+}
+
+impl<US, RS, FS, LS, MS> AuthAppInterface for AuthApp<US, RS, FS, LS, MS>
+where
+    US: UserRepository,
+    RS: RefreshTokenRepository,
+    FS: MfaRepository,
+    LS: LoginAttemptRepository,
+    MS: MailSender,
+{
+    async fn login(&self, req: RequestLogin) -> AppResult<LoginOutcome> {
+        let mut user = self.verify_credentials(&req).await?;
+
+        let factor = self.mfa_repo.find_mfa_factor(user.user_id).await?;
+        if factor.is_some_and(|factor| factor.is_confirmed()) {
+            return self
+                .issue_mfa_challenge(&user)
+                .await
+                .map(LoginOutcome::MfaRequired);
+        }
+
+        self.complete_login(&mut user)
+            .await
+            .map(|authenticated| LoginOutcome::Authenticated(Box::new(authenticated)))
+    }
+
+    async fn refresh(&self, req: RequestRefreshToken) -> AppResult<AuthenticatedUser> {
+        let token = self.verify_refresh_token(&req.refresh_token).await?;
+
+        let rotated = token.rotated_at.is_none()
+            && self
+                .refresh_token_repo
+                .mark_refresh_token_rotated(&token)
+                .await?;
+
+        if !rotated {
+            tracing::warn!(
+                user_id = %token.user_id,
+                family_id = %token.family_id,
+                "Refresh token reused, revoking its family"
+            );
+            self.refresh_token_repo
+                .revoke_refresh_token_family(token.user_id, token.family_id)
+                .await?;
+            bail!(RequestRefreshTokenError::RefreshTokenReused)
+        }
+
+        let user = self
+            .user_repo
+            .find_user_by_id(&RequestGetUserByPrimaryKey::new(
+                &token.country,
+                &token.region,
+                &token.city,
+                token.user_id,
+            ))
+            .await?;
+
+        if let Err(err) = ensure_can_sign_in(&user) {
+            self.refresh_token_repo
+                .revoke_refresh_tokens(user.user_id)
+                .await?;
+            return Err(err);
+        }
+
+        self.authenticate(&user, token.family_id).await
+    }
+
+    async fn logout(&self, req: RequestRefreshToken) -> AppResult<bool> {
+        let token = self.verify_refresh_token(&req.refresh_token).await?;
+        self.refresh_token_repo
+            .revoke_refresh_token_family(token.user_id, token.family_id)
+            .await?;
+
+        let mut user = self
+            .user_repo
+            .find_user_by_id(&RequestGetUserByPrimaryKey::new(
+                &token.country,
+                &token.region,
+                &token.city,
+                token.user_id,
+            ))
+            .await?;
+        // Disabled and deleted users keep their status when signing out.
+        if matches!(user.current_status()?, UserStatus::Active(_)) {
+            self.push_status(&mut user, UserStatus::Inactive(ReasonOfStatus::Logout))
+                .await?;
+        }
+
+        Ok(true)
+    }
+
+    fn validate_token(&self, token: &str) -> AppResult<AccessClaims> {
+        self.token_verifier.verify(token)
+    }
+
+    async fn reset_mfa(&self, req: RequestResetMfa) -> AppResult<bool> {
+        let user = self.user_repo.find_user_by_id(&req.primary_key()).await?;
+        if self.mfa_repo.find_mfa_factor(user.user_id).await?.is_none() {
+            return Ok(false);
+        }
+
+        self.mfa_repo.delete_mfa_factor(user.user_id).await?;
+        Ok(true)
+    }
+
+    async fn unlock_user(&self, req: RequestUnlockUser) -> AppResult<bool> {
+        let mut user = self.user_repo.find_user_by_id(&req.primary_key()).await?;
+        self.lift_lockout(&mut user).await
+    }
+
+    async fn request_password_reset(&self, req: RequestPasswordReset) -> AppResult<()> {
+        let mut user = match self.find_user_by_email(&req.email).await? {
+            Some(user) if ensure_can_sign_in(&user).is_ok() => user,
+            _ => return Ok(()),
+        };
+
+        let code = generate_secret();
+        user.password_recovery_code = Some(hash_secret(&code));
+        user.password_recovery_code_expires_at =
+            Some(Utc::now() + Duration::minutes(PASSWORD_RECOVERY_CODE_TTL_MINUTES));
+        self.user_repo
+            .update_user_columns(
+                &user,
+                &[
+                    UserColumn::PasswordRecoveryCode,
+                    UserColumn::PasswordRecoveryCodeExpiresAt,
+                ],
+            )
+            .await?;
+
+        let mail = Mail {
+            to: (*user.email).to_string(),
+            subject: "Reset your password".to_owned(),
+            body: format!(
+                "Your password reset code is {code}. It expires in {PASSWORD_RECOVERY_CODE_TTL_MINUTES} minutes."
+            ),
+        };
+        if let Err(err) = self.mail_sender.send(mail).await {
+            tracing::error!("Can not send password reset mail: {err:?}");
+        }
+
+        Ok(())
+    }
+
+    async fn confirm_password_reset(&self, req: RequestConfirmPasswordReset) -> AppResult<()> {
+        let mut user = self
+            .find_user_by_email(&req.email)
+            .await?
+            .ok_or_else(|| anyhow!(RequestPasswordResetError::InvalidCode))?;
+
+        let code_matches = user
+            .password_recovery_code
+            .as_deref()
+            .is_some_and(|hash| secret_matches(&req.code, hash));
+        if !code_matches {
+            bail!(RequestPasswordResetError::InvalidCode)
+        }
+
+        let now = Utc::now();
+        if user
+            .password_recovery_code_expires_at
+            .is_none_or(|expires_at| expires_at <= now)
+        {
+            bail!(RequestPasswordResetError::CodeExpired)
+        }
+        self.password_policy.check(
+            "new_password",
+            &req.new_password,
+            &user.user_name,
+            &user.email,
+        )?;
+
+        user.password = new_password(&req.new_password)?;
+        user.password_recovery_code = None;
+        user.password_recovery_code_expires_at = None;
+        user.password_recovered_at = Some(now);
+        user.updated_at = now;
+        self.user_repo
+            .update_user_columns(
+                &user,
+                &[
+                    UserColumn::Password,
+                    UserColumn::PasswordRecoveryCode,
+                    UserColumn::PasswordRecoveryCodeExpiresAt,
+                    UserColumn::PasswordRecoveredAt,
+                    UserColumn::UpdatedAt,
+                ],
+            )
+            .await?;
+
+        self.refresh_token_repo
+            .revoke_refresh_tokens(user.user_id)
+            .await?;
+        // Proving access to the email is as good as an unlock by an admin.
+        self.lift_lockout(&mut user).await?;
+
+        Ok(())
+    }
+
+    async fn enroll_totp(&self, req: RequestEnrollTotp) -> AppResult<TotpEnrollment> {
+        let user = self.user_repo.find_user_by_id(&req.primary_key()).await?;
+
+        let existing = self.mfa_repo.find_mfa_factor(user.user_id).await?;
+        if existing.is_some_and(|factor| factor.is_confirmed()) {
+            bail!(RequestMfaError::AlreadyEnrolled)
+        }
+
+        let secret = generate_totp_secret();
+        let now = Utc::now();
+        let factor = MfaFactor {
+            user_id: user.user_id,
+            secret_ciphertext: self
+                .secret_cipher
+                .encrypt(&secret, user.user_id.as_bytes())?,
+            backup_code_hashes: None,
+            last_used_step: None,
+            confirmed_at: None,
+            created_at: now,
+            updated_at: now,
+        };
+        self.mfa_repo.save_mfa_factor(&factor).await?;
+
+        let totp = new_totp(secret, &user.email)?;
+        Ok(TotpEnrollment {
+            secret: totp.get_secret_base32(),
+            otpauth_uri: totp.get_url(),
+        })
+    }
+
+    async fn confirm_totp(&self, req: RequestConfirmTotp) -> AppResult<Vec<String>> {
+        let user = self.user_repo.find_user_by_id(&req.primary_key()).await?;
+
+        let mut factor = self
+            .mfa_repo
+            .find_mfa_factor(user.user_id)
+            .await?
+            .ok_or_else(|| anyhow!(RequestMfaError::NotEnrolled))?;
+        if factor.is_confirmed() {
+            bail!(RequestMfaError::AlreadyEnrolled)
+        }
+        if !is_totp_code(&req.code) {
+            bail!(RequestMfaError::InvalidCode)
+        }
+
+        let totp = new_totp(self.decrypt_totp_secret(&factor)?, &user.email)?;
+        let step = matching_step(&totp, &req.code, Utc::now().timestamp() as u64)
+            .ok_or_else(|| anyhow!(RequestMfaError::InvalidCode))?;
+
+        let backup_codes = generate_backup_codes();
+        let now = Utc::now();
+        factor.backup_code_hashes = Some(
+            backup_codes
+                .iter()
+                .map(|code| hash_secret(&normalize_backup_code(code)))
+                .collect(),
+        );
+        factor.last_used_step = Some(step as i64);
+        factor.confirmed_at = Some(now);
+        factor.updated_at = now;
+        self.mfa_repo.save_mfa_factor(&factor).await?;
+
+        Ok(backup_codes)
+    }
+
+    async fn verify_mfa(&self, req: RequestVerifyMfa) -> AppResult<AuthenticatedUser> {
+        let (user_id, challenge_id, secret) = MfaChallenge::parse_client_value(&req.mfa_token)
+            .ok_or_else(|| anyhow!(RequestMfaError::InvalidChallenge))?;
+
+        // Taking the challenge consumes it, so every password login allows a
+        // single second factor attempt.
+        let challenge = self
+            .mfa_repo
+            .take_mfa_challenge(user_id, challenge_id)
+            .await?
+            .filter(|challenge| challenge.expires_at > Utc::now())
+            .filter(|challenge| secret_matches(secret, &challenge.challenge_hash))
+            .ok_or_else(|| anyhow!(RequestMfaError::InvalidChallenge))?;
+
+        let mut user = self
+            .user_repo
+            .find_user_by_id(&RequestGetUserByPrimaryKey::new(
+                &challenge.country,
+                &challenge.region,
+                &challenge.city,
+                challenge.user_id,
+            ))
+            .await?;
+        ensure_can_sign_in(&user)?;
+
+        let factor = self
+            .mfa_repo
+            .find_mfa_factor(user.user_id)
+            .await?
+            .filter(MfaFactor::is_confirmed)
+            .ok_or_else(|| anyhow!(RequestMfaError::NotEnrolled))?;
+
+        match is_totp_code(&req.code) {
+            true => self.verify_totp_code(&factor, &user, &req.code).await?,
+            false => self.verify_backup_code(&factor, &req.code).await?,
+        }
+
+        self.complete_login(&mut user).await
+    }
+}
+
+/// Whole seconds until `until`, at least one.
+fn retry_after_seconds(until: DateTime<Utc>) -> i64 {
+    (until - Utc::now()).num_seconds().max(1)
+}
+
+fn ensure_can_sign_in(user: &User) -> AppResult<()> {
+    match user.current_status()? {
+        // A lockout only guards the password; sessions opened before it stay
+        // valid, so failed guesses can not sign the owner out.
+        UserStatus::Disable(ReasonOfStatus::TooManyFailedLogins) => Ok(()),
+        UserStatus::Disable(_) => bail!(RequestLoginError::AccountDisabled),
+        UserStatus::Deleted(_) => bail!(RequestLoginError::AccountDeleted),
+        UserStatus::Active(_) | UserStatus::Inactive(_) => Ok(()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        application::{
+            auth::{
+                lockout::{DEFAULT_BASE_LOCK_SECONDS, DEFAULT_USER_THRESHOLD},
+                token::{
+                    tests::{issuer, verifier, PUBLIC_KEY},
+                    DEFAULT_ISSUER,
+                },
+                totp::{BACKUP_CODE_COUNT, TOTP_STEP_SECONDS},
+            },
+            topic::request::{RequestGetUserByPartitionKey, RequestUpdateUserStatus},
+        },
+        domain::user::{
+            entity::{UserDeletion, UserStatusError},
+            repository::{StatusHistoryPage, UserPage},
+        },
+    };
+    use argon2::password_hash::{PasswordHasher, SaltString};
+    use charybdis::types::Timestamp;
+    use std::sync::Mutex;
+    use totp_rs::{Secret, TOTP};
+
+    /// What the fakes answer to calls the sign-in flows never make.
+    fn unsupported<T>() -> AppResult<T> {
+        Err(anyhow!("not served by this fake"))
+    }
+
+    /// Stores users in memory, with the compare-and-set of the status.
+    #[derive(Clone, Debug, Default)]
+    struct Users(Arc<Mutex<Vec<User>>>);
+
+    impl Users {
+        fn get(&self, user_id: Timeuuid) -> User {
+            let users = self.0.lock().unwrap();
+            let user = users.iter().find(|user| user.user_id == user_id);
+            user.cloned().unwrap()
+        }
+    }
+
+    impl UserRepository for Users {
+        async fn create_user<'c>(&self, _: &'c User) -> AppResult<&'c User> {
+            unsupported()
+        }
+
+        async fn remove_user(&self, _: &User) -> AppResult<()> {
+            unsupported()
+        }
+
+        async fn find_user_by_id(&self, query: &RequestGetUserByPrimaryKey) -> AppResult<User> {
+            let users = self.0.lock().unwrap();
+            users
+                .iter()
+                .find(|user| user.user_id.to_string() == query.user_id)
+                .cloned()
+                .ok_or_else(|| anyhow!(RequestFindUserError::UserNotFound))
+        }
+
+        async fn find_user(&self, query: &RequestGetUser) -> AppResult<User> {
+            let users = self.0.lock().unwrap();
+            users
+                .iter()
+                .find(|user| match &query.email {
+                    Some(email) => user.email == *email,
+                    None => user.user_name == query.user_name,
+                })
+                .cloned()
+                .ok_or_else(|| anyhow!(RequestFindUserError::UserNotFound))
+        }
+
+        async fn find_users(
+            &self,
+            _: &RequestGetUserByPartitionKey,
+            _: i32,
+            _: Option<Vec<u8>>,
+        ) -> AppResult<UserPage> {
+            unsupported()
+        }
+
+        async fn push_new_user_status(
+            &self,
+            payload: &RequestUpdateUserStatus,
+            from: &UserStatus,
+        ) -> AppResult<bool> {
+            let status = UserStatus::parse(Some(&payload.status))?;
+            let mut users = self.0.lock().unwrap();
+            let Some(user) = users
+                .iter_mut()
+                .find(|user| user.user_id.to_string() == payload.user_id)
+            else {
+                return Ok(false);
+            };
+            if user.current_status()? != *from {
+                return Ok(false);
+            }
+            user.set_status(&status, Utc::now());
+            Ok(true)
+        }
+
+        async fn update_user_columns<'u>(
+            &self,
+            user: &'u User,
+            _: &[UserColumn],
+        ) -> AppResult<&'u User> {
+            let mut users = self.0.lock().unwrap();
+            for stored in users
+                .iter_mut()
+                .filter(|stored| stored.user_id == user.user_id)
+            {
+                *stored = user.clone();
+            }
+            Ok(user)
+        }
+
+        async fn move_user<'u>(&self, _: &User, _: &'u User) -> AppResult<&'u User> {
+            unsupported()
+        }
+
+        async fn claim_email(&self, _: &User) -> AppResult<()> {
+            unsupported()
+        }
+
+        async fn release_email(&self, _: &str, _: Timeuuid) -> AppResult<()> {
+            unsupported()
+        }
+
+        async fn schedule_purge(&self, _: &UserDeletion) -> AppResult<()> {
+            unsupported()
+        }
+
+        async fn cancel_purge(&self, _: Timeuuid) -> AppResult<()> {
+            unsupported()
+        }
+
+        async fn find_due_purges(&self, _: Timestamp) -> AppResult<Vec<UserDeletion>> {
+            unsupported()
+        }
+
+        async fn purge_user<'u>(
+            &self,
+            _: &User,
+            _: &'u User,
+            _: &[UserColumn],
+        ) -> AppResult<&'u User> {
+            unsupported()
+        }
+
+        async fn find_status_history(
+            &self,
+            _: Timeuuid,
+            _: i32,
+            _: Option<Vec<u8>>,
+        ) -> AppResult<StatusHistoryPage> {
+            unsupported()
+        }
+    }
+
+    #[derive(Clone, Debug, Default)]
+    struct RefreshTokens(Arc<Mutex<Vec<RefreshToken>>>);
+
+    impl RefreshTokens {
+        fn families(&self, user_id: Timeuuid) -> Vec<Timeuuid> {
+            let tokens = self.0.lock().unwrap();
+            let mut families: Vec<_> = tokens
+                .iter()
+                .filter(|token| token.user_id == user_id)
+                .map(|token| token.family_id)
+                .collect();
+            families.dedup();
+            families
+        }
+    }
+
+    impl RefreshTokenRepository for RefreshTokens {
+        async fn create_refresh_token(&self, token: &RefreshToken) -> AppResult<()> {
+            self.0.lock().unwrap().push(token.clone());
+            Ok(())
+        }
+
+        async fn find_refresh_token(
+            &self,
+            user_id: Timeuuid,
+            token_id: Timeuuid,
+        ) -> AppResult<Option<RefreshToken>> {
+            let tokens = self.0.lock().unwrap();
+            Ok(tokens
+                .iter()
+                .find(|token| token.user_id == user_id && token.token_id == token_id)
+                .cloned())
+        }
+
+        async fn mark_refresh_token_rotated(&self, token: &RefreshToken) -> AppResult<bool> {
+            let mut tokens = self.0.lock().unwrap();
+            match tokens
+                .iter_mut()
+                .find(|stored| stored.token_id == token.token_id && stored.rotated_at.is_none())
+            {
+                Some(stored) => {
+                    stored.rotated_at = Some(Utc::now());
+                    Ok(true)
+                }
+                None => Ok(false),
+            }
+        }
+
+        async fn revoke_refresh_token_family(
+            &self,
+            user_id: Timeuuid,
+            family_id: Timeuuid,
+        ) -> AppResult<()> {
+            let mut tokens = self.0.lock().unwrap();
+            tokens.retain(|token| token.user_id != user_id || token.family_id != family_id);
+            Ok(())
+        }
+
+        async fn revoke_other_refresh_token_families(
+            &self,
+            _: Timeuuid,
+            _: Timeuuid,
+        ) -> AppResult<()> {
+            unsupported()
+        }
+
+        async fn revoke_refresh_tokens(&self, user_id: Timeuuid) -> AppResult<()> {
+            let mut tokens = self.0.lock().unwrap();
+            tokens.retain(|token| token.user_id != user_id);
+            Ok(())
+        }
+    }
+
+    /// Stores factors and challenges in memory, with the compare-and-set of
+    /// the used step and the backup codes.
+    #[derive(Clone, Debug, Default)]
+    struct MfaFactors {
+        factors: Arc<Mutex<Vec<MfaFactor>>>,
+        challenges: Arc<Mutex<Vec<MfaChallenge>>>,
+    }
+
+    impl MfaFactors {
+        /// Applies `change` to the stored factor of `factor.user_id` if
+        /// `unchanged` still holds for it.
+        fn compare_and_set(
+            &self,
+            factor: &MfaFactor,
+            unchanged: impl Fn(&MfaFactor) -> bool,
+            change: impl FnOnce(&mut MfaFactor),
+        ) -> bool {
+            let mut factors = self.factors.lock().unwrap();
+            match factors
+                .iter_mut()
+                .find(|stored| stored.user_id == factor.user_id)
+            {
+                Some(stored) if unchanged(stored) => {
+                    change(stored);
+                    true
+                }
+                _ => false,
+            }
+        }
+    }
+
+    impl MfaRepository for MfaFactors {
+        async fn save_mfa_factor(&self, factor: &MfaFactor) -> AppResult<()> {
+            let mut factors = self.factors.lock().unwrap();
+            factors.retain(|stored| stored.user_id != factor.user_id);
+            factors.push(factor.clone());
+            Ok(())
+        }
+
+        async fn find_mfa_factor(&self, user_id: Timeuuid) -> AppResult<Option<MfaFactor>> {
+            let factors = self.factors.lock().unwrap();
+            Ok(factors
+                .iter()
+                .find(|factor| factor.user_id == user_id)
+                .cloned())
+        }
+
+        async fn delete_mfa_factor(&self, user_id: Timeuuid) -> AppResult<()> {
+            let mut factors = self.factors.lock().unwrap();
+            factors.retain(|factor| factor.user_id != user_id);
+            Ok(())
+        }
+
+        async fn mark_totp_step_used(&self, factor: &MfaFactor, step: i64) -> AppResult<bool> {
+            Ok(self.compare_and_set(
+                factor,
+                |stored| stored.last_used_step == factor.last_used_step,
+                |stored| stored.last_used_step = Some(step),
+            ))
+        }
+
+        async fn replace_backup_codes(
+            &self,
+            factor: &MfaFactor,
+            backup_code_hashes: Vec<String>,
+        ) -> AppResult<bool> {
+            Ok(self.compare_and_set(
+                factor,
+                |stored| stored.backup_code_hashes == factor.backup_code_hashes,
+                |stored| stored.backup_code_hashes = Some(backup_code_hashes),
+            ))
+        }
+
+        async fn create_mfa_challenge(&self, challenge: &MfaChallenge) -> AppResult<()> {
+            self.challenges.lock().unwrap().push(challenge.clone());
+            Ok(())
+        }
+
+        async fn take_mfa_challenge(
+            &self,
+            user_id: Timeuuid,
+            challenge_id: Timeuuid,
+        ) -> AppResult<Option<MfaChallenge>> {
+            let mut challenges = self.challenges.lock().unwrap();
+            let position = challenges.iter().position(|challenge| {
+                challenge.user_id == user_id && challenge.challenge_id == challenge_id
+            });
+            Ok(position.map(|position| challenges.remove(position)))
+        }
+    }
+
+    /// Stores failed logins in memory, with the compare-and-set of the count.
+    #[derive(Clone, Debug, Default)]
+    struct LoginAttempts(Arc<Mutex<Vec<LoginAttempt>>>);
+
+    impl LoginAttempts {
+        fn get(&self, scope: &str, subject: &str) -> Option<LoginAttempt> {
+            let attempts = self.0.lock().unwrap();
+            attempts
+                .iter()
+                .find(|attempt| attempt.scope == scope && attempt.subject == subject)
+                .cloned()
+        }
+
+        /// Lets the lock of `subject` run out.
+        fn expire_lock(&self, scope: &str, subject: &str) {
+            let mut attempts = self.0.lock().unwrap();
+            for attempt in attempts
+                .iter_mut()
+                .filter(|attempt| attempt.scope == scope && attempt.subject == subject)
+            {
+                attempt.locked_until = Some(Utc::now() - Duration::seconds(1));
+            }
+        }
+    }
+
+    impl LoginAttemptRepository for LoginAttempts {
+        async fn find_login_attempt(
+            &self,
+            scope: &str,
+            subject: &str,
+        ) -> AppResult<Option<LoginAttempt>> {
+            Ok(self.get(scope, subject))
+        }
+
+        async fn save_login_attempt(
+            &self,
+            previous: Option<&LoginAttempt>,
+            attempt: &LoginAttempt,
+            _: i32,
+        ) -> AppResult<bool> {
+            let stored = self.get(&attempt.scope, &attempt.subject);
+            if stored.map(|stored| stored.failures) != previous.map(|previous| previous.failures) {
+                return Ok(false);
+            }
+
+            let mut attempts = self.0.lock().unwrap();
+            attempts.retain(|stored| {
+                stored.scope != attempt.scope || stored.subject != attempt.subject
+            });
+            attempts.push(attempt.clone());
+            Ok(true)
+        }
+
+        async fn clear_login_attempt(&self, scope: &str, subject: &str) -> AppResult<()> {
+            let mut attempts = self.0.lock().unwrap();
+            attempts.retain(|attempt| attempt.scope != scope || attempt.subject != subject);
+            Ok(())
+        }
+    }
+
+    #[derive(Clone, Debug, Default)]
+    struct NoMail;
+
+    impl MailSender for NoMail {
+        async fn send(&self, _: Mail) -> AppResult<()> {
+            Ok(())
+        }
+    }
+
+    type TestApp = AuthApp<Users, RefreshTokens, MfaFactors, LoginAttempts, NoMail>;
+
+    #[derive(Default)]
+    struct Fakes {
+        users: Users,
+        refresh_tokens: RefreshTokens,
+        mfa_factors: MfaFactors,
+        login_attempts: LoginAttempts,
+        lockout: LockoutPolicy,
+    }
+
+    impl Fakes {
+        fn app(&self) -> TestApp {
+            AuthApp::new(
+                Arc::new(self.users.clone()),
+                Arc::new(self.refresh_tokens.clone()),
+                Arc::new(self.mfa_factors.clone()),
+                Arc::new(self.login_attempts.clone()),
+                Arc::new(NoMail),
+                SecretCipher::new(&[7; 32]).unwrap(),
+                self.lockout.clone(),
+                PasswordPolicy::default(),
+                issuer(DEFAULT_ISSUER, Duration::minutes(15)),
+                verifier(PUBLIC_KEY, DEFAULT_ISSUER),
+                Duration::days(30),
+            )
+        }
+
+        fn add_user(&self, status: UserStatus) -> User {
+            let mut user = User {
+                user_id: now_timeuuid(),
+                user_name: "alice".to_owned(),
+                email: "alice@example.com".to_owned(),
+                role: "User".to_owned(),
+                country: "VN".to_owned(),
+                region: "HN".to_owned(),
+                city: "Ba Dinh".to_owned(),
+                password: password_hash(PASSWORD),
+                ..Default::default()
+            };
+            user.set_status(&status, Utc::now());
+            self.users.0.lock().unwrap().push(user.clone());
+            user
+        }
+    }
+
+    const PASSWORD: &str = "Tr0ub4dor&Horse";
+
+    /// A PHC hash with the cheapest Argon2 parameters, which
+    /// `verify_password` reads back from the hash.
+    fn password_hash(password: &str) -> String {
+        let params = argon2::Params::new(8, 1, 1, None).unwrap();
+        let argon2 =
+            argon2::Argon2::new(argon2::Algorithm::Argon2id, argon2::Version::V0x13, params);
+        let salt = SaltString::from_b64("dGVzdHNhbHR0ZXN0c2FsdA").unwrap();
+        argon2
+            .hash_password(password.as_bytes(), &salt)
+            .unwrap()
+            .to_string()
+    }
+
+    fn active() -> UserStatus {
+        UserStatus::Active(ReasonOfStatus::LoginAgain)
+    }
+
+    fn refresh_request(refresh_token: &str) -> RequestRefreshToken {
+        RequestRefreshToken {
+            refresh_token: refresh_token.to_owned(),
+        }
+    }
+
+    fn enroll_request(user: &User) -> RequestEnrollTotp {
+        RequestEnrollTotp {
+            country: user.country.clone(),
+            region: user.region.clone(),
+            city: user.city.clone(),
+            user_id: user.user_id.to_string(),
+        }
+    }
+
+    /// Enrolls `user` with a code of the current step and returns the
+    /// authenticator app's view of the secret and the backup codes.
+    async fn enroll(app: &TestApp, user: &User) -> (TOTP, Vec<String>) {
+        let enrollment = app.enroll_totp(enroll_request(user)).await.unwrap();
+        let secret = Secret::Encoded(enrollment.secret).to_bytes().unwrap();
+        let totp = new_totp(secret, &user.email).unwrap();
+
+        let backup_codes = app
+            .confirm_totp(RequestConfirmTotp {
+                country: user.country.clone(),
+                region: user.region.clone(),
+                city: user.city.clone(),
+                user_id: user.user_id.to_string(),
+                code: code_at(&totp, current_step()),
+            })
+            .await
+            .unwrap();
+        (totp, backup_codes)
+    }
+
+    fn current_step() -> u64 {
+        Utc::now().timestamp() as u64 / TOTP_STEP_SECONDS
+    }
+
+    fn code_at(totp: &TOTP, step: u64) -> String {
+        totp.generate(step * TOTP_STEP_SECONDS)
+    }
+
+    /// Signs in with a fresh challenge and `code` as the second factor.
+    async fn verify_mfa(app: &TestApp, user: &User, code: &str) -> AppResult<AuthenticatedUser> {
+        let challenge = app.issue_mfa_challenge(user).await.unwrap();
+        app.verify_mfa(RequestVerifyMfa {
+            mfa_token: challenge.mfa_token,
+            code: code.to_owned(),
+        })
+        .await
+    }
+
+    fn is_invalid_code(result: AppResult<AuthenticatedUser>) -> bool {
+        matches!(
+            result.unwrap_err().downcast_ref(),
+            Some(RequestMfaError::InvalidCode)
+        )
+    }
+
+    async fn login(app: &TestApp, password: &str) -> AppResult<LoginOutcome> {
+        app.login(RequestLogin {
+            login: "alice".to_owned(),
+            password: password.to_owned(),
+            client_ip: Some("203.0.113.7".to_owned()),
+        })
+        .await
+    }
+
+    fn login_error(result: AppResult<LoginOutcome>) -> RequestLoginError {
+        result.unwrap_err().downcast().unwrap()
+    }
+
+    fn refresh_error(result: AppResult<AuthenticatedUser>) -> RequestRefreshTokenError {
+        result.unwrap_err().downcast().unwrap()
+    }
+
+    #[tokio::test]
+    async fn refresh_rotates_the_token_within_its_family() {
+        let fakes = Fakes::default();
+        let app = fakes.app();
+        let user = fakes.add_user(active());
+        let family_id = now_timeuuid();
+        let first = app.authenticate(&user, family_id).await.unwrap();
+
+        let second = app
+            .refresh(refresh_request(&first.refresh_token))
+            .await
+            .unwrap();
+        let third = app
+            .refresh(refresh_request(&second.refresh_token))
+            .await
+            .unwrap();
+
+        assert_ne!(first.refresh_token, second.refresh_token);
+        assert_ne!(second.refresh_token, third.refresh_token);
+        assert_eq!(fakes.refresh_tokens.families(user.user_id), [family_id]);
+        let claims = app.validate_token(&third.access_token).unwrap();
+        assert_eq!(claims.user_id, user.user_id.to_string());
+    }
+
+    #[tokio::test]
+    async fn reusing_a_rotated_token_revokes_its_family_only() {
+        let fakes = Fakes::default();
+        let app = fakes.app();
+        let user = fakes.add_user(active());
+        let stolen = app.authenticate(&user, now_timeuuid()).await.unwrap();
+        let other_device = app.authenticate(&user, now_timeuuid()).await.unwrap();
+        let rotated = app
+            .refresh(refresh_request(&stolen.refresh_token))
+            .await
+            .unwrap();
+
+        let reuse = app.refresh(refresh_request(&stolen.refresh_token)).await;
+
+        assert!(matches!(
+            refresh_error(reuse),
+            RequestRefreshTokenError::RefreshTokenReused
+        ));
+        assert!(matches!(
+            refresh_error(app.refresh(refresh_request(&rotated.refresh_token)).await),
+            RequestRefreshTokenError::InvalidRefreshToken
+        ));
+        assert!(app
+            .refresh(refresh_request(&other_device.refresh_token))
+            .await
+            .is_ok());
+    }
+
+    #[tokio::test]
+    async fn refresh_rejects_forged_and_expired_tokens() {
+        let fakes = Fakes::default();
+        let app = fakes.app();
+        let user = fakes.add_user(active());
+        let session = app.authenticate(&user, now_timeuuid()).await.unwrap();
+        let (user_id, token_id, _) =
+            RefreshToken::parse_client_value(&session.refresh_token).unwrap();
+
+        let forged = format!("{user_id}.{token_id}.{}", generate_secret());
+        assert!(matches!(
+            refresh_error(app.refresh(refresh_request(&forged)).await),
+            RequestRefreshTokenError::InvalidRefreshToken
+        ));
+        assert!(matches!(
+            refresh_error(app.refresh(refresh_request("garbage")).await),
+            RequestRefreshTokenError::InvalidRefreshToken
+        ));
+
+        for token in fakes.refresh_tokens.0.lock().unwrap().iter_mut() {
+            token.expires_at = Utc::now() - Duration::seconds(1);
+        }
+        assert!(matches!(
+            refresh_error(app.refresh(refresh_request(&session.refresh_token)).await),
+            RequestRefreshTokenError::InvalidRefreshToken
+        ));
+    }
+
+    #[tokio::test]
+    async fn refresh_revokes_every_session_of_a_disabled_user() {
+        let fakes = Fakes::default();
+        let app = fakes.app();
+        let user = fakes.add_user(active());
+        let session = app.authenticate(&user, now_timeuuid()).await.unwrap();
+        app.authenticate(&user, now_timeuuid()).await.unwrap();
+        let mut disabled = fakes.users.get(user.user_id);
+        disabled.set_status(&UserStatus::Disable(ReasonOfStatus::Scammer), Utc::now());
+        app.user_repo
+            .update_user_columns(&disabled, &[])
+            .await
+            .unwrap();
+
+        let result = app.refresh(refresh_request(&session.refresh_token)).await;
+
+        assert!(matches!(
+            result.unwrap_err().downcast().unwrap(),
+            RequestLoginError::AccountDisabled
+        ));
+        assert!(fakes.refresh_tokens.families(user.user_id).is_empty());
+    }
+
+    #[tokio::test]
+    async fn logout_revokes_the_family_and_marks_the_user_inactive() {
+        let fakes = Fakes::default();
+        let app = fakes.app();
+        let user = fakes.add_user(active());
+        let session = app.authenticate(&user, now_timeuuid()).await.unwrap();
+        let other_family = now_timeuuid();
+        app.authenticate(&user, other_family).await.unwrap();
+
+        assert!(app
+            .logout(refresh_request(&session.refresh_token))
+            .await
+            .unwrap());
+
+        assert_eq!(fakes.refresh_tokens.families(user.user_id), [other_family]);
+        assert_eq!(
+            fakes.users.get(user.user_id).current_status().unwrap(),
+            UserStatus::Inactive(ReasonOfStatus::Logout)
+        );
+    }
+
+    #[tokio::test]
+    async fn a_lost_status_write_refreshes_the_user() {
+        let fakes = Fakes::default();
+        let app = fakes.app();
+        let mut stale = fakes.add_user(active());
+        let mut stored = stale.clone();
+        stored.set_status(
+            &UserStatus::Disable(ReasonOfStatus::TooManyFailedLogins),
+            Utc::now(),
+        );
+        fakes.users.0.lock().unwrap()[0] = stored;
+
+        let result = app
+            .push_status(&mut stale, UserStatus::Inactive(ReasonOfStatus::Logout))
+            .await;
+
+        assert!(matches!(
+            result.unwrap_err().downcast().unwrap(),
+            UserStatusError::Changed
+        ));
+        assert_eq!(
+            stale.current_status().unwrap(),
+            UserStatus::Disable(ReasonOfStatus::TooManyFailedLogins)
+        );
+    }
+
+    #[tokio::test]
+    async fn totp_codes_are_accepted_once_per_step() {
+        let fakes = Fakes::default();
+        let app = fakes.app();
+        let user = fakes.add_user(active());
+        let (totp, _) = enroll(&app, &user).await;
+        let stale_factor = app.mfa_repo.find_mfa_factor(user.user_id).await.unwrap();
+        // Confirming used this step, the next one is still unused.
+        let step = stale_factor.as_ref().unwrap().last_used_step.unwrap() as u64;
+
+        assert!(is_invalid_code(
+            verify_mfa(&app, &user, &code_at(&totp, step)).await
+        ));
+        assert!(verify_mfa(&app, &user, &code_at(&totp, step + 1))
+            .await
+            .is_ok());
+        assert!(is_invalid_code(
+            verify_mfa(&app, &user, &code_at(&totp, step + 1)).await
+        ));
+        // A request that read the factor before the step was used loses the
+        // compare-and-set.
+        let replay = app
+            .verify_totp_code(&stale_factor.unwrap(), &user, &code_at(&totp, step + 1))
+            .await;
+        assert!(matches!(
+            replay.unwrap_err().downcast_ref(),
+            Some(RequestMfaError::InvalidCode)
+        ));
+    }
+
+    #[tokio::test]
+    async fn backup_codes_are_accepted_once() {
+        let fakes = Fakes::default();
+        let app = fakes.app();
+        let user = fakes.add_user(active());
+        let (_, backup_codes) = enroll(&app, &user).await;
+        let stale_factor = app.mfa_repo.find_mfa_factor(user.user_id).await.unwrap();
+
+        assert!(verify_mfa(&app, &user, &backup_codes[0].to_uppercase())
+            .await
+            .is_ok());
+        assert!(is_invalid_code(
+            verify_mfa(&app, &user, &backup_codes[0]).await
+        ));
+        // The same code raced on a factor read before it was used.
+        let replay = app
+            .verify_backup_code(&stale_factor.unwrap(), &backup_codes[0])
+            .await;
+        assert!(matches!(
+            replay.unwrap_err().downcast_ref(),
+            Some(RequestMfaError::InvalidCode)
+        ));
+        assert!(verify_mfa(&app, &user, &backup_codes[1]).await.is_ok());
+        let factor = app.mfa_repo.find_mfa_factor(user.user_id).await.unwrap();
+        assert_eq!(
+            factor.unwrap().backup_code_hashes.unwrap().len(),
+            BACKUP_CODE_COUNT - 2
+        );
+    }
+
+    #[tokio::test]
+    async fn a_challenge_allows_a_single_attempt() {
+        let fakes = Fakes::default();
+        let app = fakes.app();
+        let user = fakes.add_user(active());
+        let (_, backup_codes) = enroll(&app, &user).await;
+        let challenge = app.issue_mfa_challenge(&user).await.unwrap();
+        let attempt = |code: &str| RequestVerifyMfa {
+            mfa_token: challenge.mfa_token.clone(),
+            code: code.to_owned(),
+        };
+
+        let wrong = app.verify_mfa(attempt("0000-0000-0000-0000")).await;
+        let retry = app.verify_mfa(attempt(&backup_codes[0])).await;
+
+        assert!(is_invalid_code(wrong));
+        assert!(matches!(
+            retry.unwrap_err().downcast_ref(),
+            Some(RequestMfaError::InvalidChallenge)
+        ));
+    }
+
+    #[tokio::test]
+    async fn failed_logins_lock_the_user_at_the_threshold_with_backoff() {
+        let fakes = Fakes::default();
+        let app = fakes.app();
+        let user = fakes.add_user(active());
+        let user_key = user.user_id.to_string();
+
+        for _ in 1..DEFAULT_USER_THRESHOLD {
+            assert!(matches!(
+                login_error(login(&app, "wrong").await),
+                RequestLoginError::InvalidCredentials
+            ));
+        }
+        let attempt = fakes
+            .login_attempts
+            .get(LoginAttempt::USER_SCOPE, &user_key);
+        assert_eq!(attempt.unwrap().locked_until, None);
+
+        assert!(matches!(
+            login_error(login(&app, "wrong").await),
+            RequestLoginError::InvalidCredentials
+        ));
+        assert!(matches!(
+            login_error(login(&app, PASSWORD).await),
+            RequestLoginError::AccountLocked { retry_after_seconds } if retry_after_seconds <= 60
+        ));
+        assert_eq!(
+            fakes.users.get(user.user_id).current_status().unwrap(),
+            UserStatus::Disable(ReasonOfStatus::TooManyFailedLogins)
+        );
+
+        // Another failure once the lock ran out locks twice as long.
+        fakes
+            .login_attempts
+            .expire_lock(LoginAttempt::USER_SCOPE, &user_key);
+        login(&app, "wrong").await.unwrap_err();
+        let attempt = fakes
+            .login_attempts
+            .get(LoginAttempt::USER_SCOPE, &user_key)
+            .unwrap();
+        assert_eq!(attempt.failures, DEFAULT_USER_THRESHOLD + 1);
+        assert_eq!(
+            attempt.locked_until.unwrap() - attempt.last_failed_at,
+            Duration::seconds(2 * DEFAULT_BASE_LOCK_SECONDS)
+        );
+    }
+
+    #[tokio::test]
+    async fn the_right_password_after_the_lock_restores_the_user() {
+        let fakes = Fakes::default();
+        let app = fakes.app();
+        let user = fakes.add_user(active());
+        let user_key = user.user_id.to_string();
+        for _ in 0..DEFAULT_USER_THRESHOLD {
+            login(&app, "wrong").await.unwrap_err();
+        }
+
+        fakes
+            .login_attempts
+            .expire_lock(LoginAttempt::USER_SCOPE, &user_key);
+        let outcome = login(&app, PASSWORD).await.unwrap();
+
+        assert!(matches!(outcome, LoginOutcome::Authenticated(_)));
+        assert!(fakes
+            .login_attempts
+            .get(LoginAttempt::USER_SCOPE, &user_key)
+            .is_none());
+        assert_eq!(
+            fakes.users.get(user.user_id).current_status().unwrap(),
+            UserStatus::Active(ReasonOfStatus::LoginAgain)
+        );
+    }
+
+    #[tokio::test]
+    async fn failed_logins_throttle_the_client_ip() {
+        let fakes = Fakes {
+            lockout: LockoutPolicy {
+                ip_threshold: 2,
+                user_threshold: 100,
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+        let app = fakes.app();
+        fakes.add_user(active());
+
+        login(&app, "wrong").await.unwrap_err();
+        login(&app, "wrong").await.unwrap_err();
+
+        assert!(matches!(
+            login_error(login(&app, PASSWORD).await),
+            RequestLoginError::TooManyAttempts { .. }
+        ));
+        let other_client = app
+            .login(RequestLogin {
+                login: "alice".to_owned(),
+                password: PASSWORD.to_owned(),
+                client_ip: Some("198.51.100.1".to_owned()),
+            })
+            .await;
+        assert!(other_client.is_ok());
+    }
+}
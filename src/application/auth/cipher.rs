@@ -0,0 +1,86 @@
+use aes_gcm::{
+    aead::{Aead, AeadCore, KeyInit, OsRng, Payload},
+    Aes256Gcm, Nonce,
+};
+use anyhow::{anyhow, bail, Context};
+use base64::{
+    engine::general_purpose::{STANDARD, URL_SAFE_NO_PAD},
+    Engine,
+};
+use std::fmt::{Debug, Formatter};
+use uptop_core::common::result::AppResult;
+
+const KEY_LEN: usize = 32;
+const NONCE_LEN: usize = 12;
+
+/// Encrypts secrets at rest with AES-256-GCM. The stored form is
+/// base64url(nonce || ciphertext); `context` is bound as associated data so a
+/// value copied into another row does not decrypt.
+#[derive(Clone)]
+pub struct SecretCipher {
+    cipher: Aes256Gcm,
+}
+
+impl SecretCipher {
+    pub fn new(key: &[u8]) -> AppResult<Self> {
+        if key.len() != KEY_LEN {
+            bail!("Encryption key must be {KEY_LEN} bytes");
+        }
+        let cipher =
+            Aes256Gcm::new_from_slice(key).map_err(|_| anyhow!("Invalid encryption key"))?;
+        Ok(Self { cipher })
+    }
+
+    /// Reads the base64 key in `MFA_ENCRYPTION_KEY`.
+    pub fn from_env() -> AppResult<Self> {
+        let key = std::env::var("MFA_ENCRYPTION_KEY").context("MFA_ENCRYPTION_KEY is not set")?;
+        let key = STANDARD
+            .decode(key.trim())
+            .context("MFA_ENCRYPTION_KEY is not valid base64")?;
+        Self::new(&key)
+    }
+
+    pub fn encrypt(&self, plaintext: &[u8], context: &[u8]) -> AppResult<String> {
+        let nonce = Aes256Gcm::generate_nonce(&mut OsRng);
+        let ciphertext = self
+            .cipher
+            .encrypt(
+                &nonce,
+                Payload {
+                    msg: plaintext,
+                    aad: context,
+                },
+            )
+            .map_err(|_| anyhow!("Can not encrypt secret"))?;
+
+        let mut stored = nonce.to_vec();
+        stored.extend_from_slice(&ciphertext);
+        Ok(URL_SAFE_NO_PAD.encode(stored))
+    }
+
+    pub fn decrypt(&self, stored: &str, context: &[u8]) -> AppResult<Vec<u8>> {
+        let bytes = URL_SAFE_NO_PAD
+            .decode(stored)
+            .context("Encrypted secret is not valid base64")?;
+        if bytes.len() <= NONCE_LEN {
+            bail!("Encrypted secret is too short");
+        }
+
+        let (nonce, ciphertext) = bytes.split_at(NONCE_LEN);
+        self.cipher
+            .decrypt(
+                Nonce::from_slice(nonce),
+                Payload {
+                    msg: ciphertext,
+                    aad: context,
+                },
+            )
+            .map_err(|_| anyhow!("Can not decrypt secret"))
+    }
+}
+
+impl Debug for SecretCipher {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("SecretCipher").finish_non_exhaustive()
+    }
+}
@@ -0,0 +1,111 @@
+use anyhow::Context;
+use chrono::Duration;
+use uptop_core::common::result::AppResult;
+
+pub const DEFAULT_USER_THRESHOLD: i32 = 5;
+pub const DEFAULT_IP_THRESHOLD: i32 = 20;
+pub const DEFAULT_BASE_LOCK_SECONDS: i64 = 60;
+pub const DEFAULT_MAX_LOCK_SECONDS: i64 = 60 * 60;
+pub const DEFAULT_WINDOW_SECONDS: i64 = 15 * 60;
+
+/// When failed logins lock a user or a client IP. Once `threshold` failures
+/// are reached every further failure locks for `base_lock * 2^(failures -
+/// threshold)`, capped at `max_lock`. Failures are forgotten `window` after
+/// the last one (or the end of the lock). Read from the environment:
+/// `LOCKOUT_USER_THRESHOLD`, `LOCKOUT_IP_THRESHOLD`, `LOCKOUT_BASE_SECONDS`,
+/// `LOCKOUT_MAX_SECONDS` and `LOCKOUT_WINDOW_SECONDS`.
+#[derive(Clone, Debug, PartialEq)]
+pub struct LockoutPolicy {
+    pub user_threshold: i32,
+    pub ip_threshold: i32,
+    pub base_lock: Duration,
+    pub max_lock: Duration,
+    pub window: Duration,
+}
+
+impl Default for LockoutPolicy {
+    fn default() -> Self {
+        Self {
+            user_threshold: DEFAULT_USER_THRESHOLD,
+            ip_threshold: DEFAULT_IP_THRESHOLD,
+            base_lock: Duration::seconds(DEFAULT_BASE_LOCK_SECONDS),
+            max_lock: Duration::seconds(DEFAULT_MAX_LOCK_SECONDS),
+            window: Duration::seconds(DEFAULT_WINDOW_SECONDS),
+        }
+    }
+}
+
+impl LockoutPolicy {
+    pub fn from_env() -> AppResult<Self> {
+        Ok(Self {
+            user_threshold: env_or("LOCKOUT_USER_THRESHOLD", DEFAULT_USER_THRESHOLD)?,
+            ip_threshold: env_or("LOCKOUT_IP_THRESHOLD", DEFAULT_IP_THRESHOLD)?,
+            base_lock: Duration::seconds(env_or(
+                "LOCKOUT_BASE_SECONDS",
+                DEFAULT_BASE_LOCK_SECONDS,
+            )?),
+            max_lock: Duration::seconds(env_or("LOCKOUT_MAX_SECONDS", DEFAULT_MAX_LOCK_SECONDS)?),
+            window: Duration::seconds(env_or("LOCKOUT_WINDOW_SECONDS", DEFAULT_WINDOW_SECONDS)?),
+        })
+    }
+
+    /// How long `failures` consecutive failures lock for, if at all.
+    pub fn lock_duration(&self, failures: i32, threshold: i32) -> Option<Duration> {
+        if failures < threshold {
+            return None;
+        }
+
+        let exponent = (failures - threshold).min(30) as u32;
+        let lock = self
+            .base_lock
+            .checked_mul(2_i32.saturating_pow(exponent))
+            .unwrap_or(self.max_lock);
+        Some(lock.min(self.max_lock))
+    }
+}
+
+fn env_or<T>(key: &str, default: T) -> AppResult<T>
+where
+    T: std::str::FromStr,
+    T::Err: std::error::Error + Send + Sync + 'static,
+{
+    match std::env::var(key) {
+        Ok(value) => value
+            .trim()
+            .parse()
+            .with_context(|| format!("{key} is not valid")),
+        Err(_) => Ok(default),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn locks_from_the_threshold_and_doubles_up_to_the_cap() {
+        let policy = LockoutPolicy {
+            base_lock: Duration::seconds(60),
+            max_lock: Duration::seconds(300),
+            ..Default::default()
+        };
+        let lock = |failures| policy.lock_duration(failures, 3);
+
+        assert_eq!(lock(1), None);
+        assert_eq!(lock(2), None);
+        assert_eq!(lock(3), Some(Duration::seconds(60)));
+        assert_eq!(lock(4), Some(Duration::seconds(120)));
+        assert_eq!(lock(5), Some(Duration::seconds(240)));
+        assert_eq!(lock(6), Some(Duration::seconds(300)));
+    }
+
+    #[test]
+    fn huge_failure_counts_stay_at_the_cap() {
+        let policy = LockoutPolicy::default();
+
+        assert_eq!(
+            policy.lock_duration(i32::MAX, DEFAULT_USER_THRESHOLD),
+            Some(policy.max_lock)
+        );
+    }
+}
@@ -0,0 +1,10 @@
+pub mod app;
+pub mod cipher;
+pub mod lockout;
+pub mod password;
+pub mod policy;
+pub mod request;
+pub mod response;
+pub mod secret;
+pub mod token;
+pub mod totp;
@@ -0,0 +1,28 @@
+use argon2::{
+    password_hash::{PasswordHash, PasswordVerifier},
+    Argon2,
+};
+use std::sync::OnceLock;
+use uptop_core::common::utils::new_password;
+
+/// Checks `password` against a PHC hash produced by `new_password`. The
+/// comparison itself is constant time.
+pub fn verify_password(password: &str, hash: &str) -> bool {
+    match PasswordHash::new(hash) {
+        Ok(parsed) => Argon2::default()
+            .verify_password(password.as_bytes(), &parsed)
+            .is_ok(),
+        Err(err) => {
+            tracing::error!("Stored password hash is malformed: {err:?}");
+            false
+        }
+    }
+}
+
+/// Spends the same work as a real verification when there is no user to check
+/// against, so response times don't reveal which accounts exist.
+pub fn verify_dummy_password(password: &str) {
+    static DUMMY_HASH: OnceLock<String> = OnceLock::new();
+    let hash = DUMMY_HASH.get_or_init(|| new_password("uptop-dummy-password").unwrap_or_default());
+    let _ = verify_password(password, hash);
+}
@@ -0,0 +1,317 @@
+use anyhow::{anyhow, Context};
+use std::{borrow::Cow, collections::HashSet, fmt, sync::Arc};
+use uptop_core::common::result::AppResult;
+use validator::{ValidationError, ValidationErrors};
+
+pub const DEFAULT_MIN_LENGTH: usize = 10;
+pub const DEFAULT_MAX_LENGTH: usize = 128;
+
+/// Shortest user name or email local part that is checked as a substring of
+/// the password; anything shorter matches too many unrelated passwords.
+const MIN_IDENTITY_LENGTH: usize = 3;
+
+static COMMON_PASSWORDS: &str = include_str!("common_passwords.txt");
+
+/// Rules every new password has to satisfy. Read from the environment:
+/// `PASSWORD_MIN_LENGTH`, `PASSWORD_MAX_LENGTH`, `PASSWORD_REQUIRE_LOWERCASE`,
+/// `PASSWORD_REQUIRE_UPPERCASE`, `PASSWORD_REQUIRE_DIGIT`,
+/// `PASSWORD_REQUIRE_SYMBOL`, `PASSWORD_REJECT_COMMON` and
+/// `PASSWORD_COMMON_LIST_FILE`, a file of extra common passwords, one per line.
+#[derive(Clone, Debug, PartialEq)]
+pub struct PasswordPolicy {
+    pub min_length: usize,
+    pub max_length: usize,
+    pub require_lowercase: bool,
+    pub require_uppercase: bool,
+    pub require_digit: bool,
+    pub require_symbol: bool,
+    pub reject_common: bool,
+    pub common_passwords: CommonPasswords,
+}
+
+impl Default for PasswordPolicy {
+    fn default() -> Self {
+        Self {
+            min_length: DEFAULT_MIN_LENGTH,
+            max_length: DEFAULT_MAX_LENGTH,
+            require_lowercase: true,
+            require_uppercase: true,
+            require_digit: true,
+            require_symbol: false,
+            reject_common: true,
+            common_passwords: CommonPasswords::bundled(),
+        }
+    }
+}
+
+impl PasswordPolicy {
+    pub fn from_env() -> AppResult<Self> {
+        let default = Self::default();
+        let policy = Self {
+            min_length: env_or("PASSWORD_MIN_LENGTH", default.min_length)?,
+            max_length: env_or("PASSWORD_MAX_LENGTH", default.max_length)?,
+            require_lowercase: env_or("PASSWORD_REQUIRE_LOWERCASE", default.require_lowercase)?,
+            require_uppercase: env_or("PASSWORD_REQUIRE_UPPERCASE", default.require_uppercase)?,
+            require_digit: env_or("PASSWORD_REQUIRE_DIGIT", default.require_digit)?,
+            require_symbol: env_or("PASSWORD_REQUIRE_SYMBOL", default.require_symbol)?,
+            reject_common: env_or("PASSWORD_REJECT_COMMON", default.reject_common)?,
+            common_passwords: match std::env::var("PASSWORD_COMMON_LIST_FILE") {
+                Ok(path) => CommonPasswords::load(path.trim())?,
+                Err(_) => default.common_passwords,
+            },
+        };
+
+        if policy.min_length == 0 || policy.min_length > policy.max_length {
+            return Err(anyhow!(
+                "PASSWORD_MIN_LENGTH must be between 1 and PASSWORD_MAX_LENGTH"
+            ));
+        }
+        Ok(policy)
+    }
+
+    /// Checks `password` for the account identified by `user_name` and
+    /// `email`. Violations are reported under `field`, one entry per broken
+    /// rule.
+    pub fn check(
+        &self,
+        field: &'static str,
+        password: &str,
+        user_name: &str,
+        email: &str,
+    ) -> Result<(), ValidationErrors> {
+        let mut errors = ValidationErrors::new();
+        let mut violation = |code: &'static str, message: String| {
+            errors.add(
+                field,
+                ValidationError::new(code).with_message(Cow::Owned(message)),
+            );
+        };
+
+        let length = password.chars().count();
+        if length < self.min_length {
+            violation(
+                "password_too_short",
+                format!("Must be at least {} characters", self.min_length),
+            );
+        }
+        if length > self.max_length {
+            violation(
+                "password_too_long",
+                format!("Must be at most {} characters", self.max_length),
+            );
+        }
+        if self.require_lowercase && !password.chars().any(char::is_lowercase) {
+            violation(
+                "password_missing_lowercase",
+                "Must contain a lowercase letter".to_owned(),
+            );
+        }
+        if self.require_uppercase && !password.chars().any(char::is_uppercase) {
+            violation(
+                "password_missing_uppercase",
+                "Must contain an uppercase letter".to_owned(),
+            );
+        }
+        if self.require_digit && !password.chars().any(|c| c.is_ascii_digit()) {
+            violation("password_missing_digit", "Must contain a digit".to_owned());
+        }
+        if self.require_symbol && password.chars().all(char::is_alphanumeric) {
+            violation(
+                "password_missing_symbol",
+                "Must contain a symbol".to_owned(),
+            );
+        }
+
+        let lowered = password.to_lowercase();
+        let local_part = email.split('@').next().unwrap_or_default();
+        let contains_identity = [user_name, email, local_part]
+            .iter()
+            .map(|identity| identity.trim().to_lowercase())
+            .filter(|identity| identity.chars().count() >= MIN_IDENTITY_LENGTH)
+            .any(|identity| lowered.contains(&identity));
+        if contains_identity {
+            violation(
+                "password_contains_identity",
+                "Must not contain the user name or email".to_owned(),
+            );
+        }
+
+        if self.reject_common && self.common_passwords.contains(&lowered) {
+            violation(
+                "password_too_common",
+                "Is too common, choose a less guessable password".to_owned(),
+            );
+        }
+
+        match errors.is_empty() {
+            true => Ok(()),
+            false => Err(errors),
+        }
+    }
+}
+
+/// Lowercased passwords rejected when [`PasswordPolicy::reject_common`] is
+/// set: the bundled list, plus the lines of an optional file.
+#[derive(Clone, PartialEq)]
+pub struct CommonPasswords(Arc<HashSet<String>>);
+
+impl CommonPasswords {
+    pub fn bundled() -> Self {
+        Self(Arc::new(parse_lines(COMMON_PASSWORDS).collect()))
+    }
+
+    /// The bundled list extended with the file at `path`.
+    pub fn load(path: &str) -> AppResult<Self> {
+        let extra = std::fs::read_to_string(path)
+            .with_context(|| format!("PASSWORD_COMMON_LIST_FILE {path} cannot be read"))?;
+        Ok(Self(Arc::new(
+            parse_lines(COMMON_PASSWORDS)
+                .chain(parse_lines(&extra))
+                .collect(),
+        )))
+    }
+
+    /// Matches the list, also after dropping the trailing digits and symbols
+    /// people tend to append ("Password123!").
+    fn contains(&self, lowered: &str) -> bool {
+        let stem = lowered.trim_end_matches(|c: char| !c.is_alphabetic());
+        self.0.contains(lowered) || (!stem.is_empty() && self.0.contains(stem))
+    }
+}
+
+impl fmt::Debug for CommonPasswords {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "CommonPasswords({} entries)", self.0.len())
+    }
+}
+
+fn parse_lines(list: &str) -> impl Iterator<Item = String> + '_ {
+    list.lines()
+        .map(|line| line.trim().to_lowercase())
+        .filter(|line| !line.is_empty())
+}
+
+fn env_or<T>(key: &str, default: T) -> AppResult<T>
+where
+    T: std::str::FromStr,
+    T::Err: std::error::Error + Send + Sync + 'static,
+{
+    match std::env::var(key) {
+        Ok(value) => value
+            .trim()
+            .parse()
+            .with_context(|| format!("{key} is not valid")),
+        Err(_) => Ok(default),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn violations(policy: &PasswordPolicy, password: &str) -> Vec<String> {
+        match policy.check("password", password, "alice", "alice.nguyen@example.com") {
+            Ok(()) => vec![],
+            Err(errors) => errors.field_errors()["password"]
+                .iter()
+                .map(|error| error.code.to_string())
+                .collect(),
+        }
+    }
+
+    #[test]
+    fn default_policy_accepts_a_strong_password() {
+        assert!(violations(&PasswordPolicy::default(), "Tr0ub4dor&Horse").is_empty());
+    }
+
+    #[test]
+    fn reports_every_broken_rule() {
+        let policy = PasswordPolicy {
+            require_symbol: true,
+            ..Default::default()
+        };
+
+        assert_eq!(
+            violations(&policy, "short"),
+            [
+                "password_too_short",
+                "password_missing_uppercase",
+                "password_missing_digit",
+                "password_missing_symbol",
+            ]
+        );
+        assert_eq!(
+            violations(&policy, "NOLOWERCASE1!"),
+            ["password_missing_lowercase"]
+        );
+    }
+
+    #[test]
+    fn counts_characters_not_bytes() {
+        let policy = PasswordPolicy {
+            min_length: 4,
+            max_length: 4,
+            ..Default::default()
+        };
+
+        assert!(violations(&policy, "Ää1ü").is_empty());
+        assert_eq!(violations(&policy, "Ää1üö"), ["password_too_long"]);
+    }
+
+    #[test]
+    fn rejects_the_user_name_and_email() {
+        let policy = PasswordPolicy::default();
+
+        assert_eq!(
+            violations(&policy, "MyNameIsAlice1"),
+            ["password_contains_identity"]
+        );
+        assert_eq!(
+            violations(&policy, "Alice.Nguyen2024"),
+            ["password_contains_identity"]
+        );
+    }
+
+    #[test]
+    fn ignores_identities_shorter_than_the_minimum() {
+        let result =
+            PasswordPolicy::default().check("password", "Always4Tr0ub4dor", "al", "o@x.io");
+
+        assert!(result.is_ok());
+    }
+
+    #[test]
+    fn rejects_common_passwords_with_appended_digits_and_symbols() {
+        let policy = PasswordPolicy {
+            min_length: 1,
+            require_uppercase: false,
+            require_digit: false,
+            ..Default::default()
+        };
+
+        assert_eq!(violations(&policy, "password"), ["password_too_common"]);
+        assert_eq!(violations(&policy, "Password123!"), ["password_too_common"]);
+        assert!(violations(
+            &PasswordPolicy {
+                reject_common: false,
+                ..policy
+            },
+            "password"
+        )
+        .is_empty());
+    }
+
+    #[test]
+    fn extends_the_bundled_list_with_a_file() {
+        let path = std::env::temp_dir().join(format!("common-{}.txt", std::process::id()));
+        std::fs::write(&path, "Correcthorse\n\n  batterystaple  \n").unwrap();
+        let common_passwords = CommonPasswords::load(path.to_str().unwrap()).unwrap();
+        std::fs::remove_file(&path).unwrap();
+
+        assert!(common_passwords.contains("correcthorse"));
+        assert!(common_passwords.contains("batterystaple99"));
+        assert!(common_passwords.contains("password"));
+        assert!(!CommonPasswords::bundled().contains("correcthorse"));
+        assert!(CommonPasswords::load("/nonexistent/common.txt").is_err());
+    }
+}
@@ -0,0 +1,246 @@
+use crate::application::{
+    id::validate_timeuuid,
+    topic::request::{RequestGetUser, RequestGetUserByPrimaryKey},
+};
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+use uptop_core::common::result::AppResult;
+use validator::Validate;
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize, Validate)]
+pub struct RequestLogin {
+    /// User name or email.
+    #[validate(length(min = 1))]
+    pub login: String,
+    #[validate(length(min = 1))]
+    pub password: String,
+    /// Address the request came from, for per-IP throttling.
+    pub client_ip: Option<String>,
+}
+
+impl RequestLogin {
+    pub fn try_into_domain(self) -> AppResult<Self> {
+        self.validate()?;
+
+        Ok(Self {
+            login: self.login.trim().to_string(),
+            password: self.password,
+            client_ip: self.client_ip,
+        })
+    }
+
+    pub fn lookup(&self) -> RequestGetUser {
+        match self.login.contains('@') {
+            true => RequestGetUser {
+                email: Some((*self.login).to_string()),
+                ..Default::default()
+            },
+            false => RequestGetUser {
+                user_name: (*self.login).to_string(),
+                ..Default::default()
+            },
+        }
+    }
+}
+
+#[derive(Debug, Error)]
+pub enum RequestLoginError {
+    #[error("Invalid user name, email or password")]
+    InvalidCredentials,
+    #[error("Account is disabled")]
+    AccountDisabled,
+    #[error("Account is deleted")]
+    AccountDeleted,
+    #[error("Account is locked after too many failed logins")]
+    AccountLocked { retry_after_seconds: i64 },
+    #[error("Too many failed logins from this address")]
+    TooManyAttempts { retry_after_seconds: i64 },
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize, Validate)]
+pub struct RequestRefreshToken {
+    #[validate(length(min = 1))]
+    pub refresh_token: String,
+}
+
+impl RequestRefreshToken {
+    pub fn try_into_domain(self) -> AppResult<Self> {
+        self.validate()?;
+
+        Ok(Self {
+            refresh_token: self.refresh_token.trim().to_string(),
+        })
+    }
+}
+
+#[derive(Debug, Error)]
+pub enum RequestRefreshTokenError {
+    #[error("Refresh token is invalid or expired")]
+    InvalidRefreshToken,
+    #[error("Refresh token was already used, the session has been revoked")]
+    RefreshTokenReused,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize, Validate)]
+pub struct RequestPasswordReset {
+    #[validate(email)]
+    pub email: String,
+}
+
+impl RequestPasswordReset {
+    pub fn try_into_domain(self) -> AppResult<Self> {
+        self.validate()?;
+
+        Ok(Self {
+            email: self.email.trim().to_string(),
+        })
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize, Validate)]
+pub struct RequestConfirmPasswordReset {
+    #[validate(email)]
+    pub email: String,
+    #[validate(length(min = 1))]
+    pub code: String,
+    #[validate(length(min = 1))]
+    pub new_password: String,
+}
+
+impl RequestConfirmPasswordReset {
+    pub fn try_into_domain(self) -> AppResult<Self> {
+        self.validate()?;
+
+        Ok(Self {
+            email: self.email.trim().to_string(),
+            code: self.code.trim().to_string(),
+            new_password: self.new_password,
+        })
+    }
+}
+
+#[derive(Debug, Error)]
+pub enum RequestPasswordResetError {
+    #[error("Password reset code is invalid")]
+    InvalidCode,
+    #[error("Password reset code has expired")]
+    CodeExpired,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize, Validate)]
+pub struct RequestEnrollTotp {
+    pub country: String,
+    pub region: String,
+    pub city: String,
+    #[validate(custom(function = "validate_timeuuid"))]
+    pub user_id: String,
+}
+
+impl RequestEnrollTotp {
+    pub fn try_into_domain(self) -> AppResult<Self> {
+        self.validate()?;
+        Ok(self)
+    }
+
+    pub fn primary_key(&self) -> RequestGetUserByPrimaryKey {
+        RequestGetUserByPrimaryKey::new(&self.country, &self.region, &self.city, &self.user_id)
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize, Validate)]
+pub struct RequestConfirmTotp {
+    pub country: String,
+    pub region: String,
+    pub city: String,
+    #[validate(custom(function = "validate_timeuuid"))]
+    pub user_id: String,
+    #[validate(length(min = 1))]
+    pub code: String,
+}
+
+impl RequestConfirmTotp {
+    pub fn try_into_domain(self) -> AppResult<Self> {
+        self.validate()?;
+
+        Ok(Self {
+            code: self.code.trim().to_string(),
+            ..self
+        })
+    }
+
+    pub fn primary_key(&self) -> RequestGetUserByPrimaryKey {
+        RequestGetUserByPrimaryKey::new(&self.country, &self.region, &self.city, &self.user_id)
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize, Validate)]
+pub struct RequestResetMfa {
+    pub country: String,
+    pub region: String,
+    pub city: String,
+    #[validate(custom(function = "validate_timeuuid"))]
+    pub user_id: String,
+}
+
+impl RequestResetMfa {
+    pub fn try_into_domain(self) -> AppResult<Self> {
+        self.validate()?;
+        Ok(self)
+    }
+
+    pub fn primary_key(&self) -> RequestGetUserByPrimaryKey {
+        RequestGetUserByPrimaryKey::new(&self.country, &self.region, &self.city, &self.user_id)
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize, Validate)]
+pub struct RequestVerifyMfa {
+    #[validate(length(min = 1))]
+    pub mfa_token: String,
+    /// A TOTP code or one of the backup codes.
+    #[validate(length(min = 1))]
+    pub code: String,
+}
+
+impl RequestVerifyMfa {
+    pub fn try_into_domain(self) -> AppResult<Self> {
+        self.validate()?;
+
+        Ok(Self {
+            mfa_token: self.mfa_token.trim().to_string(),
+            code: self.code.trim().to_string(),
+        })
+    }
+}
+
+#[derive(Debug, Error)]
+pub enum RequestMfaError {
+    #[error("A second factor is already enrolled")]
+    AlreadyEnrolled,
+    #[error("No second factor is enrolled")]
+    NotEnrolled,
+    #[error("Verification code is invalid")]
+    InvalidCode,
+    #[error("MFA token is invalid or expired")]
+    InvalidChallenge,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize, Validate)]
+pub struct RequestUnlockUser {
+    pub country: String,
+    pub region: String,
+    pub city: String,
+    #[validate(custom(function = "validate_timeuuid"))]
+    pub user_id: String,
+}
+
+impl RequestUnlockUser {
+    pub fn try_into_domain(self) -> AppResult<Self> {
+        self.validate()?;
+        Ok(self)
+    }
+
+    pub fn primary_key(&self) -> RequestGetUserByPrimaryKey {
+        RequestGetUserByPrimaryKey::new(&self.country, &self.region, &self.city, &self.user_id)
+    }
+}
@@ -0,0 +1,32 @@
+use crate::application::topic::response::PublicUser;
+use serde::{Deserialize, Serialize};
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct AuthenticatedUser {
+    pub user: PublicUser,
+    pub access_token: String,
+    pub access_token_expires_at: String,
+    pub refresh_token: String,
+    pub refresh_token_expires_at: String,
+}
+
+/// Result of a password login: either the session, or a challenge to redeem
+/// with a second factor.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub enum LoginOutcome {
+    Authenticated(Box<AuthenticatedUser>),
+    MfaRequired(PendingMfa),
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct PendingMfa {
+    pub mfa_token: String,
+    pub mfa_token_expires_at: String,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct TotpEnrollment {
+    /// Base32 secret, for authenticators without QR support.
+    pub secret: String,
+    pub otpauth_uri: String,
+}
@@ -0,0 +1,23 @@
+use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine};
+use rand::{rngs::OsRng, RngCore};
+use sha2::{Digest, Sha256};
+use subtle::ConstantTimeEq;
+
+const SECRET_LEN: usize = 32;
+
+/// A random, URL-safe secret with 256 bits of entropy.
+pub fn generate_secret() -> String {
+    let mut bytes = [0u8; SECRET_LEN];
+    OsRng.fill_bytes(&mut bytes);
+    URL_SAFE_NO_PAD.encode(bytes)
+}
+
+/// The form a secret is stored in. Secrets are high entropy, so a plain SHA-256
+/// is enough.
+pub fn hash_secret(secret: &str) -> String {
+    URL_SAFE_NO_PAD.encode(Sha256::digest(secret.as_bytes()))
+}
+
+pub fn secret_matches(secret: &str, hash: &str) -> bool {
+    hash_secret(secret).as_bytes().ct_eq(hash.as_bytes()).into()
+}
@@ -0,0 +1,80 @@
+use anyhow::anyhow;
+use rand::{rngs::OsRng, Rng, RngCore};
+use subtle::ConstantTimeEq;
+use totp_rs::{Algorithm, TOTP};
+use uptop_core::common::result::AppResult;
+
+pub const TOTP_ISSUER: &str = "Uptop";
+pub const TOTP_DIGITS: usize = 6;
+pub const TOTP_STEP_SECONDS: u64 = 30;
+/// Steps accepted on either side of the current one, for clock drift.
+const TOTP_SKEW: u64 = 1;
+const TOTP_SECRET_LEN: usize = 20;
+
+pub const BACKUP_CODE_COUNT: usize = 10;
+const BACKUP_CODE_GROUPS: usize = 4;
+const BACKUP_CODE_GROUP_LEN: usize = 4;
+const BACKUP_CODE_ALPHABET: &[u8] = b"abcdefghjkmnpqrstuvwxyz23456789";
+
+/// A fresh 160 bit shared secret, as recommended by RFC 4226.
+pub fn generate_totp_secret() -> Vec<u8> {
+    let mut secret = vec![0u8; TOTP_SECRET_LEN];
+    OsRng.fill_bytes(&mut secret);
+    secret
+}
+
+pub fn new_totp(secret: Vec<u8>, account_name: &str) -> AppResult<TOTP> {
+    TOTP::new(
+        Algorithm::SHA1,
+        TOTP_DIGITS,
+        TOTP_SKEW as u8,
+        TOTP_STEP_SECONDS,
+        secret,
+        Some(TOTP_ISSUER.to_owned()),
+        account_name.replace(':', ""),
+    )
+    .map_err(|err| anyhow!("Can not build TOTP: {err:?}"))
+}
+
+/// The time step `code` was generated for, if it is valid around `now`
+/// (Unix seconds).
+pub fn matching_step(totp: &TOTP, code: &str, now: u64) -> Option<u64> {
+    let current = now / TOTP_STEP_SECONDS;
+    (current.saturating_sub(TOTP_SKEW)..=current + TOTP_SKEW).find(|step| {
+        totp.generate(step * TOTP_STEP_SECONDS)
+            .as_bytes()
+            .ct_eq(code.as_bytes())
+            .into()
+    })
+}
+
+pub fn is_totp_code(code: &str) -> bool {
+    code.len() == TOTP_DIGITS && code.bytes().all(|b| b.is_ascii_digit())
+}
+
+/// Single use recovery codes shown once at enrollment, e.g. `k7mq-2xhp-9ad3-wr4n`.
+pub fn generate_backup_codes() -> Vec<String> {
+    (0..BACKUP_CODE_COUNT)
+        .map(|_| {
+            (0..BACKUP_CODE_GROUPS)
+                .map(|_| {
+                    (0..BACKUP_CODE_GROUP_LEN)
+                        .map(|_| {
+                            let index = OsRng.gen_range(0..BACKUP_CODE_ALPHABET.len());
+                            BACKUP_CODE_ALPHABET[index] as char
+                        })
+                        .collect::<String>()
+                })
+                .collect::<Vec<_>>()
+                .join("-")
+        })
+        .collect()
+}
+
+/// The form backup codes are hashed in: lowercase without separators.
+pub fn normalize_backup_code(code: &str) -> String {
+    code.chars()
+        .filter(|c| c.is_ascii_alphanumeric())
+        .map(|c| c.to_ascii_lowercase())
+        .collect()
+}
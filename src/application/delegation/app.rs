@@ -0,0 +1,375 @@
+use super::{
+    request::{
+        RequestDelegationError, RequestDisableManagedUser, RequestEnableManagedUser,
+        RequestGetDelegationAudit, RequestGrantDelegation, RequestListManagedUsers,
+        RequestResetManagedUserPassword, RequestRevokeDelegation,
+    },
+    response::{PublicDelegationAuditEntry, PublicDelegationAuditPage, PublicManagedUser},
+};
+use crate::{
+    application::{
+        access::app::Actor,
+        auth::policy::PasswordPolicy,
+        id::parse_id,
+        topic::{
+            app::change_status,
+            cursor::PageTokenSigner,
+            request::{RequestFindUserError, RequestGetUserByPrimaryKey, DEFAULT_PAGE_SIZE},
+            response::PublicUser,
+        },
+    },
+    domain::{
+        auth::repository::RefreshTokenRepository,
+        user::{
+            entity::{
+                DelegationAction, DelegationAuditEntry, DelegationRole, ManagedUser,
+                ReasonOfStatus, StatusChangedBy, User, UserColumn, UserStatus,
+            },
+            repository::{DelegationRepository, UserRepository},
+        },
+    },
+};
+use anyhow::bail;
+use charybdis::types::Timeuuid;
+use chrono::Utc;
+use std::{future::Future, sync::Arc};
+use uptop_core::common::{result::AppResult, utils::new_password};
+
+/// Users managing other users through `User::owners` and `User::admins`.
+/// Who may call what is decided by the caller, with the `user.delegate`,
+/// `user.reset_password` and `user.disable` permissions; every change is
+/// recorded in the delegation audit of the managed user.
+pub trait DelegationAppInterface: Clone + Send + Sync + 'static {
+    /// Makes `req.manager_id` an owner or admin of `req.user_id`. Granting
+    /// the role the manager already has changes nothing.
+    fn grant_delegation(
+        &self,
+        actor: &Actor,
+        req: RequestGrantDelegation,
+    ) -> impl Future<Output = AppResult<PublicUser>> + Send;
+
+    fn revoke_delegation(
+        &self,
+        actor: &Actor,
+        req: RequestRevokeDelegation,
+    ) -> impl Future<Output = AppResult<PublicUser>> + Send;
+
+    /// The live users `req.manager_id` manages.
+    fn list_managed_users(
+        &self,
+        req: RequestListManagedUsers,
+    ) -> impl Future<Output = AppResult<Vec<PublicManagedUser>>> + Send;
+
+    fn reset_managed_user_password(
+        &self,
+        actor: &Actor,
+        req: RequestResetManagedUserPassword,
+    ) -> impl Future<Output = AppResult<PublicUser>> + Send;
+
+    fn disable_managed_user(
+        &self,
+        actor: &Actor,
+        req: RequestDisableManagedUser,
+    ) -> impl Future<Output = AppResult<PublicUser>> + Send;
+
+    fn enable_managed_user(
+        &self,
+        actor: &Actor,
+        req: RequestEnableManagedUser,
+    ) -> impl Future<Output = AppResult<PublicUser>> + Send;
+
+    fn get_delegation_audit(
+        &self,
+        req: RequestGetDelegationAudit,
+    ) -> impl Future<Output = AppResult<PublicDelegationAuditPage>> + Send;
+}
+
+#[derive(Clone, Debug)]
+pub struct DelegationApp<US, DS, RS>
+where
+    US: UserRepository,
+    DS: DelegationRepository,
+    RS: RefreshTokenRepository,
+{
+    user_repo: Arc<US>,
+    delegation_repo: Arc<DS>,
+    refresh_token_repo: Arc<RS>,
+    password_policy: PasswordPolicy,
+    page_tokens: PageTokenSigner,
+}
+
+impl<US, DS, RS> DelegationApp<US, DS, RS>
+where
+    US: UserRepository,
+    DS: DelegationRepository,
+    RS: RefreshTokenRepository,
+{
+    pub fn new(
+        user_repo: Arc<US>,
+        delegation_repo: Arc<DS>,
+        refresh_token_repo: Arc<RS>,
+        password_policy: PasswordPolicy,
+        page_tokens: PageTokenSigner,
+    ) -> Self {
+        Self {
+            user_repo,
+            delegation_repo,
+            refresh_token_repo,
+            password_policy,
+            page_tokens,
+        }
+    }
+
+    /// A live user, wherever it is stored.
+    async fn user(&self, user_id: Timeuuid) -> AppResult<User> {
+        let user = self
+            .user_repo
+            .find_user_by_id(&RequestGetUserByPrimaryKey::from_user_id(user_id))
+            .await?;
+        if user.is_deleted() {
+            bail!(RequestFindUserError::UserNotFound)
+        }
+        Ok(user)
+    }
+
+    /// Records `action` of `actor` on `user`, with the `note` of the request.
+    async fn audit(
+        &self,
+        user: &User,
+        actor: &Actor,
+        action: DelegationAction,
+        note: Option<String>,
+    ) -> AppResult<()> {
+        let entry = DelegationAuditEntry {
+            note,
+            ..DelegationAuditEntry::new(user.user_id, actor.user_id, action)
+        };
+        self.delegation_repo.add_audit_entry(&entry).await
+    }
+}
+
+impl<US, DS, RS> DelegationAppInterface for DelegationApp<US, DS, RS>
+where
+    US: UserRepository,
+    DS: DelegationRepository,
+    RS: RefreshTokenRepository,
+{
+    async fn grant_delegation(
+        &self,
+        actor: &Actor,
+        req: RequestGrantDelegation,
+    ) -> AppResult<PublicUser> {
+        let user_id = parse_id("user_id", &req.user_id)?;
+        let manager_id = parse_id("manager_id", &req.manager_id)?;
+        if user_id == manager_id {
+            bail!(RequestDelegationError::SelfDelegation)
+        }
+        let role = DelegationRole::parse(req.role.as_deref())?;
+
+        let mut user = self.user(user_id).await?;
+        match self.user(manager_id).await {
+            Ok(_) => {}
+            Err(err) if err.is::<RequestFindUserError>() => {
+                bail!(RequestDelegationError::ManagerNotFound)
+            }
+            Err(err) => return Err(err),
+        }
+        if user.delegation_role(manager_id) == Some(role) {
+            return (&user).try_into();
+        }
+
+        let grant = ManagedUser {
+            manager_id,
+            user_id,
+            role: role.to_string(),
+            granted_by: actor.user_id,
+            granted_at: Utc::now(),
+        };
+        self.delegation_repo.grant_delegation(&user, &grant).await?;
+        user.set_delegation(manager_id, Some(role));
+
+        let entry = DelegationAuditEntry {
+            manager_id: Some(manager_id),
+            role: Some(role.to_string()),
+            note: req.note,
+            ..DelegationAuditEntry::new(user_id, actor.user_id, DelegationAction::Grant)
+        };
+        self.delegation_repo.add_audit_entry(&entry).await?;
+
+        (&user).try_into()
+    }
+
+    async fn revoke_delegation(
+        &self,
+        actor: &Actor,
+        req: RequestRevokeDelegation,
+    ) -> AppResult<PublicUser> {
+        let manager_id = parse_id("manager_id", &req.manager_id)?;
+        // Deleted users can still be cleaned up.
+        let mut user = self
+            .user_repo
+            .find_user_by_id(&RequestGetUserByPrimaryKey::from_user_id(&req.user_id))
+            .await?;
+        let Some(role) = user.delegation_role(manager_id) else {
+            bail!(RequestDelegationError::NotAManager)
+        };
+
+        self.delegation_repo
+            .revoke_delegation(&user, manager_id)
+            .await?;
+        user.set_delegation(manager_id, None);
+
+        let entry = DelegationAuditEntry {
+            manager_id: Some(manager_id),
+            role: Some(role.to_string()),
+            note: req.note,
+            ..DelegationAuditEntry::new(user.user_id, actor.user_id, DelegationAction::Revoke)
+        };
+        self.delegation_repo.add_audit_entry(&entry).await?;
+
+        (&user).try_into()
+    }
+
+    async fn list_managed_users(
+        &self,
+        req: RequestListManagedUsers,
+    ) -> AppResult<Vec<PublicManagedUser>> {
+        let manager_id = parse_id("manager_id", &req.manager_id)?;
+        let mut managed_users = Vec::new();
+        for managed in self.delegation_repo.find_managed_users(manager_id).await? {
+            let user = match self.user(managed.user_id).await {
+                Ok(user) => user,
+                Err(err) if err.is::<RequestFindUserError>() => continue,
+                Err(err) => return Err(err),
+            };
+            // The lists on the user are what counts, the row may be stale.
+            let Some(role) = user.delegation_role(manager_id) else {
+                continue;
+            };
+            let managed = ManagedUser {
+                role: role.to_string(),
+                ..managed
+            };
+            managed_users.push(PublicManagedUser::new((&user).try_into()?, &managed));
+        }
+        Ok(managed_users)
+    }
+
+    async fn reset_managed_user_password(
+        &self,
+        actor: &Actor,
+        req: RequestResetManagedUserPassword,
+    ) -> AppResult<PublicUser> {
+        let mut user = self.user(parse_id("user_id", &req.user_id)?).await?;
+        self.password_policy.check(
+            "new_password",
+            &req.new_password,
+            &user.user_name,
+            &user.email,
+        )?;
+
+        user.password = new_password(&req.new_password)?;
+        user.password_recovery_code = None;
+        user.password_recovery_code_expires_at = None;
+        user.updated_at = Utc::now();
+        // The delegation lists are only written by grants and revokes.
+        self.user_repo
+            .update_user_columns(
+                &user,
+                &[
+                    UserColumn::Password,
+                    UserColumn::PasswordRecoveryCode,
+                    UserColumn::PasswordRecoveryCodeExpiresAt,
+                    UserColumn::UpdatedAt,
+                ],
+            )
+            .await?;
+        self.refresh_token_repo
+            .revoke_refresh_tokens(user.user_id)
+            .await?;
+
+        self.audit(&user, actor, DelegationAction::ResetPassword, req.note)
+            .await?;
+        (&user).try_into()
+    }
+
+    async fn disable_managed_user(
+        &self,
+        actor: &Actor,
+        req: RequestDisableManagedUser,
+    ) -> AppResult<PublicUser> {
+        let mut user = self.user(parse_id("user_id", &req.user_id)?).await?;
+        change_status(
+            self.user_repo.as_ref(),
+            &mut user,
+            UserStatus::Disable(ReasonOfStatus::ManagerRequested),
+            StatusChangedBy::Delegate,
+            Some(actor.user_id),
+            req.note.clone(),
+        )
+        .await?;
+        self.refresh_token_repo
+            .revoke_refresh_tokens(user.user_id)
+            .await?;
+
+        self.audit(&user, actor, DelegationAction::Disable, req.note)
+            .await?;
+        (&user).try_into()
+    }
+
+    async fn enable_managed_user(
+        &self,
+        actor: &Actor,
+        req: RequestEnableManagedUser,
+    ) -> AppResult<PublicUser> {
+        let mut user = self.user(parse_id("user_id", &req.user_id)?).await?;
+        change_status(
+            self.user_repo.as_ref(),
+            &mut user,
+            UserStatus::Active(ReasonOfStatus::ComeBackAccess),
+            StatusChangedBy::Delegate,
+            Some(actor.user_id),
+            req.note.clone(),
+        )
+        .await?;
+
+        self.audit(&user, actor, DelegationAction::Enable, req.note)
+            .await?;
+        (&user).try_into()
+    }
+
+    async fn get_delegation_audit(
+        &self,
+        req: RequestGetDelegationAudit,
+    ) -> AppResult<PublicDelegationAuditPage> {
+        let user = self
+            .user_repo
+            .find_user_by_id(&RequestGetUserByPrimaryKey::from_user_id(&req.user_id))
+            .await?;
+        let user_id = user.user_id.to_string();
+        let paging_state = match req.page_token.as_deref() {
+            Some(token) => Some(self.page_tokens.verify_delegation_audit(&user_id, token)?),
+            None => None,
+        };
+
+        let page = self
+            .delegation_repo
+            .find_audit_entries(
+                user.user_id,
+                req.page_size.unwrap_or(DEFAULT_PAGE_SIZE),
+                paging_state,
+            )
+            .await?;
+
+        Ok(PublicDelegationAuditPage {
+            entries: page
+                .entries
+                .iter()
+                .map(PublicDelegationAuditEntry::from)
+                .collect(),
+            next_page_token: page
+                .paging_state
+                .map(|state| self.page_tokens.sign_delegation_audit(&user_id, &state)),
+        })
+    }
+}
@@ -0,0 +1,157 @@
+use crate::application::{id::validate_timeuuid, topic::request::DEFAULT_PAGE_SIZE};
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+use uptop_core::common::result::AppResult;
+use validator::Validate;
+
+/// Lists `manager_id` in the `owners` or `admins` of `user_id`. `role` is
+/// `Owner` or `Admin`, the default.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize, Validate)]
+pub struct RequestGrantDelegation {
+    #[validate(custom(function = "validate_timeuuid"))]
+    pub user_id: String,
+    #[validate(custom(function = "validate_timeuuid"))]
+    pub manager_id: String,
+    pub role: Option<String>,
+    #[validate(length(max = 500))]
+    pub note: Option<String>,
+}
+
+impl RequestGrantDelegation {
+    pub fn try_into_domain(self) -> AppResult<Self> {
+        self.validate()?;
+        Ok(Self {
+            role: self.role.filter(|role| !role.is_empty()),
+            note: trimmed(self.note),
+            ..self
+        })
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize, Validate)]
+pub struct RequestRevokeDelegation {
+    #[validate(custom(function = "validate_timeuuid"))]
+    pub user_id: String,
+    #[validate(custom(function = "validate_timeuuid"))]
+    pub manager_id: String,
+    #[validate(length(max = 500))]
+    pub note: Option<String>,
+}
+
+impl RequestRevokeDelegation {
+    pub fn try_into_domain(self) -> AppResult<Self> {
+        self.validate()?;
+        Ok(Self {
+            note: trimmed(self.note),
+            ..self
+        })
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize, Validate)]
+pub struct RequestListManagedUsers {
+    #[validate(custom(function = "validate_timeuuid"))]
+    pub manager_id: String,
+}
+
+impl RequestListManagedUsers {
+    pub fn try_into_domain(self) -> AppResult<Self> {
+        self.validate()?;
+        Ok(self)
+    }
+}
+
+/// Sets a new password on a managed user and signs it out everywhere.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize, Validate)]
+pub struct RequestResetManagedUserPassword {
+    #[validate(custom(function = "validate_timeuuid"))]
+    pub user_id: String,
+    #[validate(length(min = 1))]
+    pub new_password: String,
+    #[validate(length(max = 500))]
+    pub note: Option<String>,
+}
+
+impl RequestResetManagedUserPassword {
+    pub fn try_into_domain(self) -> AppResult<Self> {
+        self.validate()?;
+        Ok(Self {
+            note: trimmed(self.note),
+            ..self
+        })
+    }
+}
+
+/// Moves a managed user to `Disable:ManagerRequested`.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize, Validate)]
+pub struct RequestDisableManagedUser {
+    #[validate(custom(function = "validate_timeuuid"))]
+    pub user_id: String,
+    #[validate(length(max = 500))]
+    pub note: Option<String>,
+}
+
+impl RequestDisableManagedUser {
+    pub fn try_into_domain(self) -> AppResult<Self> {
+        self.validate()?;
+        Ok(Self {
+            note: trimmed(self.note),
+            ..self
+        })
+    }
+}
+
+/// Lifts a `Disable:ManagerRequested` status again.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize, Validate)]
+pub struct RequestEnableManagedUser {
+    #[validate(custom(function = "validate_timeuuid"))]
+    pub user_id: String,
+    #[validate(length(max = 500))]
+    pub note: Option<String>,
+}
+
+impl RequestEnableManagedUser {
+    pub fn try_into_domain(self) -> AppResult<Self> {
+        self.validate()?;
+        Ok(Self {
+            note: trimmed(self.note),
+            ..self
+        })
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize, Validate)]
+pub struct RequestGetDelegationAudit {
+    #[validate(custom(function = "validate_timeuuid"))]
+    pub user_id: String,
+    #[validate(range(min = 1, max = 500))]
+    pub page_size: Option<i32>,
+    pub page_token: Option<String>,
+}
+
+impl RequestGetDelegationAudit {
+    pub fn try_into_domain(self) -> AppResult<Self> {
+        self.validate()?;
+        Ok(Self {
+            page_size: Some(self.page_size.unwrap_or(DEFAULT_PAGE_SIZE)),
+            page_token: self.page_token.filter(|token| !token.is_empty()),
+            ..self
+        })
+    }
+}
+
+#[derive(Debug, Error)]
+pub enum RequestDelegationError {
+    #[error("A user can not manage itself")]
+    SelfDelegation,
+    #[error("Manager not found")]
+    ManagerNotFound,
+    #[error("User is not managed by this manager")]
+    NotAManager,
+}
+
+fn trimmed(value: Option<String>) -> Option<String> {
+    value
+        .map(|value| value.trim().to_string())
+        .filter(|value| !value.is_empty())
+}
@@ -0,0 +1,58 @@
+use crate::{
+    application::topic::response::PublicUser,
+    domain::user::entity::{DelegationAuditEntry, ManagedUser},
+};
+use serde::{Deserialize, Serialize};
+
+/// A user someone manages, with the role it is managed in.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct PublicManagedUser {
+    pub user: PublicUser,
+    pub role: String,
+    pub granted_by: String,
+    pub granted_at: String,
+}
+
+impl PublicManagedUser {
+    pub fn new(user: PublicUser, managed: &ManagedUser) -> Self {
+        Self {
+            user,
+            role: (*managed.role).to_string(),
+            granted_by: managed.granted_by.to_string(),
+            granted_at: managed.granted_at.to_rfc3339(),
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct PublicDelegationAuditEntry {
+    pub user_id: String,
+    pub entry_id: String,
+    pub actor_id: String,
+    pub action: String,
+    pub manager_id: Option<String>,
+    pub role: Option<String>,
+    pub note: Option<String>,
+    pub created_at: String,
+}
+
+impl From<&DelegationAuditEntry> for PublicDelegationAuditEntry {
+    fn from(entry: &DelegationAuditEntry) -> Self {
+        Self {
+            user_id: entry.user_id.to_string(),
+            entry_id: entry.entry_id.to_string(),
+            actor_id: entry.actor_id.to_string(),
+            action: (*entry.action).to_string(),
+            manager_id: entry.manager_id.map(|id| id.to_string()),
+            role: entry.role.clone(),
+            note: entry.note.clone(),
+            created_at: entry.created_at.to_rfc3339(),
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct PublicDelegationAuditPage {
+    pub entries: Vec<PublicDelegationAuditEntry>,
+    pub next_page_token: Option<String>,
+}
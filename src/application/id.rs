@@ -0,0 +1,25 @@
+use charybdis::types::Timeuuid;
+use std::str::FromStr;
+use uptop_core::common::result::AppResult;
+use validator::{ValidationError, ValidationErrors};
+
+/// Validates an id sent by a client, a timeuuid in its string form.
+pub(crate) fn validate_timeuuid(id: &str) -> Result<(), ValidationError> {
+    match Timeuuid::from_str(id) {
+        Ok(_) => Ok(()),
+        Err(_) => Err(ValidationError::new("timeuuid")),
+    }
+}
+
+/// Parses an id checked by [`validate_timeuuid`]. Anything else is reported
+/// as a violation of `field`, like a failed validation.
+pub fn parse_id(field: &'static str, id: &str) -> AppResult<Timeuuid> {
+    match Timeuuid::from_str(id) {
+        Ok(id) => Ok(id),
+        Err(_) => {
+            let mut errors = ValidationErrors::new();
+            errors.add(field, ValidationError::new("timeuuid"));
+            Err(errors.into())
+        }
+    }
+}
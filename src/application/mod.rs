@@ -1 +1,7 @@
+pub mod access;
+pub mod auth;
+pub mod delegation;
+pub mod id;
+pub mod organization;
 pub mod topic;
+pub use topic as user;
@@ -0,0 +1,600 @@
+use super::{
+    invitation::{find_invitation, redeem_invitation, InvitationPolicy},
+    request::{
+        RequestAcceptInvitation, RequestAddOrganizationMember, RequestCreateInvitation,
+        RequestCreateOrganization, RequestDeleteOrganization, RequestGetOrganization,
+        RequestInvitationError, RequestListInvitations, RequestListOrganizationMembers,
+        RequestListUserOrganizations, RequestOrganizationError, RequestRemoveOrganizationMember,
+        RequestRevokeInvitation, RequestSwitchActiveOrganization, RequestUpdateOrganization,
+    },
+    response::{
+        PublicOrganization, PublicOrganizationInvitation, PublicOrganizationMember,
+        PublicOrganizationMemberPage,
+    },
+};
+use crate::{
+    application::{
+        access::app::Actor,
+        auth::secret::{generate_secret, hash_secret},
+        id,
+        topic::{
+            cursor::PageTokenSigner,
+            request::{RequestFindUserError, RequestGetUserByPrimaryKey, DEFAULT_PAGE_SIZE},
+            response::PublicUser,
+        },
+    },
+    domain::{
+        mail::{Mail, MailSender},
+        organization::{
+            entity::{
+                InvitationQuota, Organization, OrganizationInvitation, OrganizationMember,
+                OrganizationRole,
+            },
+            repository::{InvitationRepository, OrganizationRepository},
+        },
+        user::{entity::User, repository::UserRepository},
+    },
+};
+use anyhow::bail;
+use charybdis::types::Timeuuid;
+use chrono::Utc;
+use std::{future::Future, sync::Arc};
+use uptop_core::common::{result::AppResult, utils::now_timeuuid};
+
+/// How many members a deletion unlinks per page.
+const DELETE_PAGE_SIZE: i32 = 500;
+/// How often counting an invitation is retried after losing a race.
+const QUOTA_RETRIES: usize = 5;
+
+pub trait OrganizationAppInterface: Clone + Send + Sync + 'static {
+    /// Creates an organization owned by `req.owner_id`, who becomes its
+    /// first member. The handler resolves the owner from the caller.
+    fn create_organization(
+        &self,
+        req: RequestCreateOrganization,
+    ) -> impl Future<Output = AppResult<PublicOrganization>> + Send;
+
+    fn get_organization(
+        &self,
+        req: RequestGetOrganization,
+    ) -> impl Future<Output = AppResult<PublicOrganization>> + Send;
+
+    fn update_organization(
+        &self,
+        req: RequestUpdateOrganization,
+    ) -> impl Future<Output = AppResult<PublicOrganization>> + Send;
+
+    /// Deletes the organization after taking it off every member.
+    fn delete_organization(
+        &self,
+        req: RequestDeleteOrganization,
+    ) -> impl Future<Output = AppResult<bool>> + Send;
+
+    fn add_member(
+        &self,
+        req: RequestAddOrganizationMember,
+    ) -> impl Future<Output = AppResult<PublicOrganizationMember>> + Send;
+
+    fn remove_member(
+        &self,
+        req: RequestRemoveOrganizationMember,
+    ) -> impl Future<Output = AppResult<bool>> + Send;
+
+    fn list_members(
+        &self,
+        req: RequestListOrganizationMembers,
+    ) -> impl Future<Output = AppResult<PublicOrganizationMemberPage>> + Send;
+
+    fn list_user_organizations(
+        &self,
+        req: RequestListUserOrganizations,
+    ) -> impl Future<Output = AppResult<Vec<PublicOrganization>>> + Send;
+
+    /// Sets the organization the user acts in. Only organizations the user is
+    /// a member of can be made active.
+    fn switch_active_organization(
+        &self,
+        req: RequestSwitchActiveOrganization,
+    ) -> impl Future<Output = AppResult<PublicUser>> + Send;
+
+    /// Mails an invitation token to `req.email` on behalf of `inviter`.
+    /// Counts against the inviter's limit in [`InvitationPolicy`].
+    fn create_invitation(
+        &self,
+        inviter: &Actor,
+        req: RequestCreateInvitation,
+    ) -> impl Future<Output = AppResult<PublicOrganizationInvitation>> + Send;
+
+    fn list_invitations(
+        &self,
+        req: RequestListInvitations,
+    ) -> impl Future<Output = AppResult<Vec<PublicOrganizationInvitation>>> + Send;
+
+    fn revoke_invitation(
+        &self,
+        req: RequestRevokeInvitation,
+    ) -> impl Future<Output = AppResult<bool>> + Send;
+
+    /// Makes an existing user a member, if the invitation was sent to the
+    /// user's email.
+    fn accept_invitation(
+        &self,
+        req: RequestAcceptInvitation,
+    ) -> impl Future<Output = AppResult<PublicOrganizationMember>> + Send;
+}
+
+#[derive(Clone, Debug)]
+pub struct OrganizationApp<US, OS, IS, MS>
+where
+    US: UserRepository,
+    OS: OrganizationRepository,
+    IS: InvitationRepository,
+    MS: MailSender,
+{
+    user_repo: Arc<US>,
+    organization_repo: Arc<OS>,
+    invitation_repo: Arc<IS>,
+    mail_sender: Arc<MS>,
+    invitation_policy: InvitationPolicy,
+    page_tokens: PageTokenSigner,
+}
+
+impl<US, OS, IS, MS> OrganizationApp<US, OS, IS, MS>
+where
+    US: UserRepository,
+    OS: OrganizationRepository,
+    IS: InvitationRepository,
+    MS: MailSender,
+{
+    pub fn new(
+        user_repo: Arc<US>,
+        organization_repo: Arc<OS>,
+        invitation_repo: Arc<IS>,
+        mail_sender: Arc<MS>,
+        invitation_policy: InvitationPolicy,
+        page_tokens: PageTokenSigner,
+    ) -> Self {
+        Self {
+            user_repo,
+            organization_repo,
+            invitation_repo,
+            mail_sender,
+            invitation_policy,
+            page_tokens,
+        }
+    }
+
+    async fn organization(&self, organization_id: Timeuuid) -> AppResult<Organization> {
+        match self
+            .organization_repo
+            .find_organization(organization_id)
+            .await?
+        {
+            Some(organization) => Ok(organization),
+            None => bail!(RequestOrganizationError::OrganizationNotFound),
+        }
+    }
+
+    /// A live user, wherever it is stored.
+    async fn user(&self, user_id: Timeuuid) -> AppResult<User> {
+        let user = self
+            .user_repo
+            .find_user_by_id(&RequestGetUserByPrimaryKey::from_user_id(user_id))
+            .await?;
+        if user.is_deleted() {
+            bail!(RequestFindUserError::UserNotFound)
+        }
+        Ok(user)
+    }
+
+    /// Counts one invitation against `inviter_id`, or fails once the limit
+    /// of the current window is reached. The count is compared and set, so
+    /// concurrent invitations can not go past the limit together.
+    async fn count_invitation(&self, inviter_id: Timeuuid) -> AppResult<()> {
+        let window = self.invitation_policy.inviter_window;
+        for _ in 0..QUOTA_RETRIES {
+            let now = Utc::now();
+            let previous = self
+                .invitation_repo
+                .find_invitation_quota(inviter_id)
+                .await?;
+            let quota = match &previous {
+                Some(quota) if quota.window_started_at + window > now => quota.clone(),
+                _ => InvitationQuota {
+                    inviter_id,
+                    sent: 0,
+                    window_started_at: now,
+                },
+            };
+
+            let resets_in = quota.window_started_at + window - now;
+            if quota.sent >= self.invitation_policy.inviter_limit {
+                bail!(RequestInvitationError::TooManyInvitations {
+                    retry_after_seconds: resets_in.num_seconds().max(1),
+                })
+            }
+
+            let quota = InvitationQuota {
+                sent: quota.sent + 1,
+                ..quota
+            };
+            if self
+                .invitation_repo
+                .save_invitation_quota(previous.as_ref(), &quota, resets_in.num_seconds() as i32)
+                .await?
+            {
+                return Ok(());
+            }
+        }
+
+        // Other invitations keep winning the race, so the limit is close.
+        bail!(RequestInvitationError::TooManyInvitations {
+            retry_after_seconds: 1,
+        })
+    }
+
+    /// Mails the invitation token. Delivery failures are logged, the
+    /// invitation can be revoked and sent again.
+    async fn send_invitation(
+        &self,
+        organization: &Organization,
+        invitation: &OrganizationInvitation,
+        token: &str,
+    ) {
+        let mail = Mail {
+            to: (*invitation.email).to_string(),
+            subject: format!("Join {} on uptop", organization.name),
+            body: format!(
+                "You are invited to join {} as {}. Your invitation token is {token}. It expires at {}.",
+                organization.name,
+                invitation.role,
+                invitation.expires_at.to_rfc3339(),
+            ),
+        };
+
+        if let Err(err) = self.mail_sender.send(mail).await {
+            tracing::error!("Can not send invitation mail: {err:?}");
+        }
+    }
+}
+
+impl<US, OS, IS, MS> OrganizationAppInterface for OrganizationApp<US, OS, IS, MS>
+where
+    US: UserRepository,
+    OS: OrganizationRepository,
+    IS: InvitationRepository,
+    MS: MailSender,
+{
+    async fn create_organization(
+        &self,
+        req: RequestCreateOrganization,
+    ) -> AppResult<PublicOrganization> {
+        let owner = self.user(id::parse_id("owner_id", &req.owner_id)?).await?;
+        let now = Utc::now();
+        let organization = Organization {
+            organization_id: now_timeuuid(),
+            name: req.name,
+            description: req.description,
+            owner_id: owner.user_id,
+            created_at: now,
+            updated_at: now,
+        };
+
+        self.organization_repo
+            .create_organization(&organization, &owner)
+            .await?;
+        Ok((&organization).into())
+    }
+
+    async fn get_organization(&self, req: RequestGetOrganization) -> AppResult<PublicOrganization> {
+        let organization = self
+            .organization(id::parse_id("organization_id", &req.organization_id)?)
+            .await?;
+        Ok((&organization).into())
+    }
+
+    async fn update_organization(
+        &self,
+        req: RequestUpdateOrganization,
+    ) -> AppResult<PublicOrganization> {
+        let mut organization = self
+            .organization(id::parse_id("organization_id", &req.organization_id)?)
+            .await?;
+        if let Some(name) = req.name {
+            organization.name = name;
+        }
+        if let Some(description) = req.description {
+            organization.description = Some(description).filter(|value| !value.is_empty());
+        }
+        organization.updated_at = Utc::now();
+
+        self.organization_repo
+            .update_organization(&organization)
+            .await?;
+        Ok((&organization).into())
+    }
+
+    async fn delete_organization(&self, req: RequestDeleteOrganization) -> AppResult<bool> {
+        let organization = self
+            .organization(id::parse_id("organization_id", &req.organization_id)?)
+            .await?;
+
+        // Unlink every member first, so no user keeps pointing at it.
+        let mut paging_state = None;
+        loop {
+            let page = self
+                .organization_repo
+                .find_members(organization.organization_id, DELETE_PAGE_SIZE, paging_state)
+                .await?;
+            for member in &page.members {
+                let lookup = RequestGetUserByPrimaryKey::from_user_id(member.user_id);
+                match self.user_repo.find_user_by_id(&lookup).await {
+                    Ok(user) => {
+                        self.organization_repo
+                            .remove_member(organization.organization_id, &user)
+                            .await?
+                    }
+                    Err(err) if err.is::<RequestFindUserError>() => {}
+                    Err(err) => return Err(err),
+                }
+            }
+
+            paging_state = page.paging_state;
+            if paging_state.is_none() {
+                break;
+            }
+        }
+
+        self.invitation_repo
+            .delete_invitations(organization.organization_id)
+            .await?;
+        self.organization_repo
+            .delete_organization(organization.organization_id)
+            .await?;
+        Ok(true)
+    }
+
+    async fn add_member(
+        &self,
+        req: RequestAddOrganizationMember,
+    ) -> AppResult<PublicOrganizationMember> {
+        let organization = self
+            .organization(id::parse_id("organization_id", &req.organization_id)?)
+            .await?;
+        let user = self.user(id::parse_id("user_id", &req.user_id)?).await?;
+
+        let role = OrganizationRole::parse(req.role.as_deref())?;
+        if role == OrganizationRole::Owner {
+            bail!(RequestOrganizationError::OwnerRoleTaken)
+        }
+        if user.user_id == organization.owner_id {
+            bail!(RequestOrganizationError::OwnerUnchangeable)
+        }
+
+        let joined_at = self
+            .organization_repo
+            .find_member(organization.organization_id, user.user_id)
+            .await?
+            .map_or_else(Utc::now, |member| member.joined_at);
+        let member = OrganizationMember {
+            organization_id: organization.organization_id,
+            user_id: user.user_id,
+            role: role.to_string(),
+            joined_at,
+        };
+
+        self.organization_repo.add_member(&member, &user).await?;
+        Ok((&member).into())
+    }
+
+    async fn remove_member(&self, req: RequestRemoveOrganizationMember) -> AppResult<bool> {
+        let organization = self
+            .organization(id::parse_id("organization_id", &req.organization_id)?)
+            .await?;
+        let user_id = id::parse_id("user_id", &req.user_id)?;
+        if user_id == organization.owner_id {
+            bail!(RequestOrganizationError::OwnerUnchangeable)
+        }
+
+        let member = self
+            .organization_repo
+            .find_member(organization.organization_id, user_id)
+            .await?;
+        if member.is_none() {
+            bail!(RequestOrganizationError::NotAMember)
+        }
+
+        let user = self
+            .user_repo
+            .find_user_by_id(&RequestGetUserByPrimaryKey::from_user_id(user_id))
+            .await?;
+        self.organization_repo
+            .remove_member(organization.organization_id, &user)
+            .await?;
+        Ok(true)
+    }
+
+    async fn list_members(
+        &self,
+        req: RequestListOrganizationMembers,
+    ) -> AppResult<PublicOrganizationMemberPage> {
+        let organization = self
+            .organization(id::parse_id("organization_id", &req.organization_id)?)
+            .await?;
+        let organization_id = organization.organization_id.to_string();
+        let paging_state = match req.page_token.as_deref() {
+            Some(token) => Some(self.page_tokens.verify_members(&organization_id, token)?),
+            None => None,
+        };
+
+        let page = self
+            .organization_repo
+            .find_members(
+                organization.organization_id,
+                req.page_size.unwrap_or(DEFAULT_PAGE_SIZE),
+                paging_state,
+            )
+            .await?;
+
+        Ok(PublicOrganizationMemberPage {
+            members: page
+                .members
+                .iter()
+                .map(PublicOrganizationMember::from)
+                .collect(),
+            next_page_token: page
+                .paging_state
+                .map(|state| self.page_tokens.sign_members(&organization_id, &state)),
+        })
+    }
+
+    async fn list_user_organizations(
+        &self,
+        req: RequestListUserOrganizations,
+    ) -> AppResult<Vec<PublicOrganization>> {
+        let user = self.user(id::parse_id("user_id", &req.user_id)?).await?;
+
+        let mut organizations = vec![];
+        for organization_id in user.organizations.unwrap_or_default() {
+            if let Some(organization) = self
+                .organization_repo
+                .find_organization(organization_id)
+                .await?
+            {
+                organizations.push(PublicOrganization::from(&organization));
+            }
+        }
+        Ok(organizations)
+    }
+
+    async fn switch_active_organization(
+        &self,
+        req: RequestSwitchActiveOrganization,
+    ) -> AppResult<PublicUser> {
+        let lookup =
+            RequestGetUserByPrimaryKey::new(req.country, req.region, req.city, req.user_id);
+        let mut user = self.user_repo.find_user_by_id(&lookup).await?;
+        if user.is_deleted() {
+            bail!(RequestFindUserError::UserNotFound)
+        }
+
+        let organization_id = req
+            .organization_id
+            .as_deref()
+            .map(|organization_id| id::parse_id("organization_id", organization_id))
+            .transpose()?;
+        if let Some(organization_id) = organization_id {
+            let member = self
+                .organization_repo
+                .find_member(organization_id, user.user_id)
+                .await?;
+            if member.is_none() {
+                bail!(RequestOrganizationError::NotAMember)
+            }
+        }
+
+        self.organization_repo
+            .set_active_organization(&user, organization_id)
+            .await?;
+        user.active_organization = organization_id;
+        user.updated_at = Utc::now();
+        (&user).try_into()
+    }
+
+    async fn create_invitation(
+        &self,
+        inviter: &Actor,
+        req: RequestCreateInvitation,
+    ) -> AppResult<PublicOrganizationInvitation> {
+        let organization = self
+            .organization(id::parse_id("organization_id", &req.organization_id)?)
+            .await?;
+        let inviter_id = inviter.user_id;
+
+        let role = OrganizationRole::parse(req.role.as_deref())?;
+        if role == OrganizationRole::Owner {
+            bail!(RequestOrganizationError::OwnerRoleTaken)
+        }
+        self.count_invitation(inviter_id).await?;
+
+        let secret = generate_secret();
+        let now = Utc::now();
+        let invitation = OrganizationInvitation {
+            organization_id: organization.organization_id,
+            invitation_id: now_timeuuid(),
+            email: req.email,
+            role: role.to_string(),
+            invited_by: inviter_id,
+            token_hash: hash_secret(&secret),
+            created_at: now,
+            expires_at: now + self.invitation_policy.ttl(req.expires_in_hours),
+        };
+        self.invitation_repo.create_invitation(&invitation).await?;
+
+        let token = invitation.to_client_value(&secret);
+        self.send_invitation(&organization, &invitation, &token)
+            .await;
+        Ok((&invitation).into())
+    }
+
+    async fn list_invitations(
+        &self,
+        req: RequestListInvitations,
+    ) -> AppResult<Vec<PublicOrganizationInvitation>> {
+        let organization = self
+            .organization(id::parse_id("organization_id", &req.organization_id)?)
+            .await?;
+        let now = Utc::now();
+
+        let invitations = self
+            .invitation_repo
+            .find_invitations(organization.organization_id)
+            .await?;
+        Ok(invitations
+            .iter()
+            .filter(|invitation| invitation.expires_at > now)
+            .map(PublicOrganizationInvitation::from)
+            .collect())
+    }
+
+    async fn revoke_invitation(&self, req: RequestRevokeInvitation) -> AppResult<bool> {
+        let revoked = self
+            .invitation_repo
+            .take_invitation(
+                id::parse_id("organization_id", &req.organization_id)?,
+                id::parse_id("invitation_id", &req.invitation_id)?,
+            )
+            .await?;
+        if revoked.is_none() {
+            bail!(RequestInvitationError::InvitationNotFound)
+        }
+        Ok(true)
+    }
+
+    async fn accept_invitation(
+        &self,
+        req: RequestAcceptInvitation,
+    ) -> AppResult<PublicOrganizationMember> {
+        let mut user = self.user(id::parse_id("user_id", &req.user_id)?).await?;
+        let invitation =
+            find_invitation(self.invitation_repo.as_ref(), &req.token, &user.email).await?;
+        let organization = self.organization(invitation.organization_id).await?;
+
+        let member = self
+            .organization_repo
+            .find_member(organization.organization_id, user.user_id)
+            .await?;
+        if member.is_some() {
+            bail!(RequestInvitationError::AlreadyAMember)
+        }
+
+        let member = redeem_invitation(
+            self.organization_repo.as_ref(),
+            self.invitation_repo.as_ref(),
+            &invitation,
+            &mut user,
+        )
+        .await?;
+        Ok((&member).into())
+    }
+}
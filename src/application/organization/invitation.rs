@@ -0,0 +1,146 @@
+use super::request::RequestInvitationError;
+use crate::{
+    application::auth::secret::secret_matches,
+    domain::{
+        organization::{
+            entity::{OrganizationInvitation, OrganizationMember},
+            repository::{InvitationRepository, OrganizationRepository},
+        },
+        user::{entity::User, identifier::canonical_email},
+    },
+};
+use anyhow::{anyhow, bail, Context};
+use chrono::{Duration, Utc};
+use uptop_core::common::result::AppResult;
+
+pub const DEFAULT_INVITATION_TTL_HOURS: i64 = 7 * 24;
+pub const DEFAULT_INVITATION_MAX_TTL_HOURS: i64 = 30 * 24;
+pub const DEFAULT_INVITER_LIMIT: i32 = 20;
+pub const DEFAULT_INVITER_WINDOW_SECONDS: i64 = 60 * 60;
+
+/// How long invitations live and how many one user may send. An inviter can
+/// send `inviter_limit` invitations per `inviter_window`, counted from the
+/// first one. Read from the environment: `INVITATION_TTL_HOURS`,
+/// `INVITATION_MAX_TTL_HOURS`, `INVITER_LIMIT` and `INVITER_WINDOW_SECONDS`.
+#[derive(Clone, Debug, PartialEq)]
+pub struct InvitationPolicy {
+    pub default_ttl: Duration,
+    pub max_ttl: Duration,
+    pub inviter_limit: i32,
+    pub inviter_window: Duration,
+}
+
+impl Default for InvitationPolicy {
+    fn default() -> Self {
+        Self {
+            default_ttl: Duration::hours(DEFAULT_INVITATION_TTL_HOURS),
+            max_ttl: Duration::hours(DEFAULT_INVITATION_MAX_TTL_HOURS),
+            inviter_limit: DEFAULT_INVITER_LIMIT,
+            inviter_window: Duration::seconds(DEFAULT_INVITER_WINDOW_SECONDS),
+        }
+    }
+}
+
+impl InvitationPolicy {
+    pub fn from_env() -> AppResult<Self> {
+        Ok(Self {
+            default_ttl: Duration::hours(env_or(
+                "INVITATION_TTL_HOURS",
+                DEFAULT_INVITATION_TTL_HOURS,
+            )?),
+            max_ttl: Duration::hours(env_or(
+                "INVITATION_MAX_TTL_HOURS",
+                DEFAULT_INVITATION_MAX_TTL_HOURS,
+            )?),
+            inviter_limit: env_or("INVITER_LIMIT", DEFAULT_INVITER_LIMIT)?,
+            inviter_window: Duration::seconds(env_or(
+                "INVITER_WINDOW_SECONDS",
+                DEFAULT_INVITER_WINDOW_SECONDS,
+            )?),
+        })
+    }
+
+    /// How long an invitation lives, `expires_in_hours` capped at `max_ttl`.
+    pub fn ttl(&self, expires_in_hours: Option<i64>) -> Duration {
+        // Capped before converting, `Duration::hours` panics on overflow.
+        expires_in_hours
+            .map_or(self.default_ttl, |hours| {
+                Duration::hours(hours.min(self.max_ttl.num_hours()))
+            })
+            .min(self.max_ttl)
+    }
+}
+
+/// The pending invitation behind `token`, which must be addressed to `email`.
+pub(crate) async fn find_invitation<IS: InvitationRepository>(
+    invitation_repo: &IS,
+    token: &str,
+    email: &str,
+) -> AppResult<OrganizationInvitation> {
+    let Some((organization_id, invitation_id, secret)) =
+        OrganizationInvitation::parse_client_value(token)
+    else {
+        bail!(RequestInvitationError::InvalidToken)
+    };
+
+    let invitation = invitation_repo
+        .find_invitation(organization_id, invitation_id)
+        .await?
+        .filter(|invitation| secret_matches(secret, &invitation.token_hash))
+        .ok_or_else(|| anyhow!(RequestInvitationError::InvalidToken))?;
+    if invitation.expires_at <= Utc::now() {
+        bail!(RequestInvitationError::Expired)
+    }
+    if canonical_email(&invitation.email) != canonical_email(email) {
+        bail!(RequestInvitationError::EmailMismatch)
+    }
+
+    Ok(invitation)
+}
+
+/// Uses up `invitation` and makes `user` a member with the invited role.
+pub(crate) async fn redeem_invitation<OS, IS>(
+    organization_repo: &OS,
+    invitation_repo: &IS,
+    invitation: &OrganizationInvitation,
+    user: &mut User,
+) -> AppResult<OrganizationMember>
+where
+    OS: OrganizationRepository,
+    IS: InvitationRepository,
+{
+    let taken = invitation_repo
+        .take_invitation(invitation.organization_id, invitation.invitation_id)
+        .await?;
+    if taken.is_none() {
+        bail!(RequestInvitationError::InvalidToken)
+    }
+
+    let member = OrganizationMember {
+        organization_id: invitation.organization_id,
+        user_id: user.user_id,
+        role: invitation.role.clone(),
+        joined_at: Utc::now(),
+    };
+    organization_repo.add_member(&member, user).await?;
+
+    let organizations = user.organizations.get_or_insert_with(Vec::new);
+    if !organizations.contains(&member.organization_id) {
+        organizations.push(member.organization_id);
+    }
+    Ok(member)
+}
+
+fn env_or<T>(key: &str, default: T) -> AppResult<T>
+where
+    T: std::str::FromStr,
+    T::Err: std::error::Error + Send + Sync + 'static,
+{
+    match std::env::var(key) {
+        Ok(value) => value
+            .trim()
+            .parse()
+            .with_context(|| format!("{key} is not valid")),
+        Err(_) => Ok(default),
+    }
+}
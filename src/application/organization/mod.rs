@@ -0,0 +1,4 @@
+pub mod app;
+pub mod invitation;
+pub mod request;
+pub mod response;
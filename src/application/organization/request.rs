@@ -0,0 +1,274 @@
+use crate::application::{id::validate_timeuuid, topic::request::DEFAULT_PAGE_SIZE};
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+use uptop_core::common::result::AppResult;
+use validator::Validate;
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize, Validate)]
+pub struct RequestCreateOrganization {
+    #[validate(length(min = 1, max = 100))]
+    pub name: String,
+    #[validate(length(max = 500))]
+    pub description: Option<String>,
+    #[validate(custom(function = "validate_timeuuid"))]
+    pub owner_id: String,
+}
+
+impl RequestCreateOrganization {
+    pub fn try_into_domain(self) -> AppResult<Self> {
+        let req = Self {
+            name: self.name.trim().to_string(),
+            description: trimmed(self.description),
+            ..self
+        };
+        req.validate()?;
+        Ok(req)
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize, Validate)]
+pub struct RequestGetOrganization {
+    #[validate(custom(function = "validate_timeuuid"))]
+    pub organization_id: String,
+}
+
+impl RequestGetOrganization {
+    pub fn try_into_domain(self) -> AppResult<Self> {
+        self.validate()?;
+        Ok(self)
+    }
+}
+
+/// Changes the fields that are set.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize, Validate)]
+pub struct RequestUpdateOrganization {
+    #[validate(custom(function = "validate_timeuuid"))]
+    pub organization_id: String,
+    #[validate(length(min = 1, max = 100))]
+    pub name: Option<String>,
+    #[validate(length(max = 500))]
+    pub description: Option<String>,
+}
+
+impl RequestUpdateOrganization {
+    pub fn try_into_domain(self) -> AppResult<Self> {
+        let req = Self {
+            name: self.name.map(|name| name.trim().to_string()),
+            description: self
+                .description
+                .map(|description| description.trim().to_string()),
+            ..self
+        };
+        req.validate()?;
+        Ok(req)
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize, Validate)]
+pub struct RequestDeleteOrganization {
+    #[validate(custom(function = "validate_timeuuid"))]
+    pub organization_id: String,
+}
+
+impl RequestDeleteOrganization {
+    pub fn try_into_domain(self) -> AppResult<Self> {
+        self.validate()?;
+        Ok(self)
+    }
+}
+
+/// Adds a user to an organization, or changes the role of a member. `role`
+/// is `Admin` or `Member`, the default.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize, Validate)]
+pub struct RequestAddOrganizationMember {
+    #[validate(custom(function = "validate_timeuuid"))]
+    pub organization_id: String,
+    #[validate(custom(function = "validate_timeuuid"))]
+    pub user_id: String,
+    pub role: Option<String>,
+}
+
+impl RequestAddOrganizationMember {
+    pub fn try_into_domain(self) -> AppResult<Self> {
+        self.validate()?;
+        Ok(Self {
+            role: self.role.filter(|role| !role.is_empty()),
+            ..self
+        })
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize, Validate)]
+pub struct RequestRemoveOrganizationMember {
+    #[validate(custom(function = "validate_timeuuid"))]
+    pub organization_id: String,
+    #[validate(custom(function = "validate_timeuuid"))]
+    pub user_id: String,
+}
+
+impl RequestRemoveOrganizationMember {
+    pub fn try_into_domain(self) -> AppResult<Self> {
+        self.validate()?;
+        Ok(self)
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize, Validate)]
+pub struct RequestListOrganizationMembers {
+    #[validate(custom(function = "validate_timeuuid"))]
+    pub organization_id: String,
+    #[validate(range(min = 1, max = 500))]
+    pub page_size: Option<i32>,
+    pub page_token: Option<String>,
+}
+
+impl RequestListOrganizationMembers {
+    pub fn try_into_domain(self) -> AppResult<Self> {
+        self.validate()?;
+        Ok(Self {
+            page_size: Some(self.page_size.unwrap_or(DEFAULT_PAGE_SIZE)),
+            page_token: self.page_token.filter(|token| !token.is_empty()),
+            ..self
+        })
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize, Validate)]
+pub struct RequestListUserOrganizations {
+    #[validate(custom(function = "validate_timeuuid"))]
+    pub user_id: String,
+}
+
+impl RequestListUserOrganizations {
+    pub fn try_into_domain(self) -> AppResult<Self> {
+        self.validate()?;
+        Ok(self)
+    }
+}
+
+/// Makes one of the user's organizations the active one, or clears it when
+/// `organization_id` is not set.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize, Validate)]
+pub struct RequestSwitchActiveOrganization {
+    pub country: String,
+    pub region: String,
+    pub city: String,
+    #[validate(custom(function = "validate_timeuuid"))]
+    pub user_id: String,
+    #[validate(custom(function = "validate_timeuuid"))]
+    pub organization_id: Option<String>,
+}
+
+impl RequestSwitchActiveOrganization {
+    pub fn try_into_domain(self) -> AppResult<Self> {
+        let req = Self {
+            organization_id: self.organization_id.filter(|id| !id.is_empty()),
+            ..self
+        };
+        req.validate()?;
+        Ok(req)
+    }
+}
+
+/// Invites `email` to join as `role`, `Admin` or `Member` (the default).
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize, Validate)]
+pub struct RequestCreateInvitation {
+    #[validate(custom(function = "validate_timeuuid"))]
+    pub organization_id: String,
+    #[validate(email)]
+    pub email: String,
+    pub role: Option<String>,
+    #[validate(range(min = 1))]
+    pub expires_in_hours: Option<i64>,
+}
+
+impl RequestCreateInvitation {
+    pub fn try_into_domain(self) -> AppResult<Self> {
+        let req = Self {
+            email: self.email.trim().to_string(),
+            role: self.role.filter(|role| !role.is_empty()),
+            ..self
+        };
+        req.validate()?;
+        Ok(req)
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize, Validate)]
+pub struct RequestListInvitations {
+    #[validate(custom(function = "validate_timeuuid"))]
+    pub organization_id: String,
+}
+
+impl RequestListInvitations {
+    pub fn try_into_domain(self) -> AppResult<Self> {
+        self.validate()?;
+        Ok(self)
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize, Validate)]
+pub struct RequestRevokeInvitation {
+    #[validate(custom(function = "validate_timeuuid"))]
+    pub organization_id: String,
+    #[validate(custom(function = "validate_timeuuid"))]
+    pub invitation_id: String,
+}
+
+impl RequestRevokeInvitation {
+    pub fn try_into_domain(self) -> AppResult<Self> {
+        self.validate()?;
+        Ok(self)
+    }
+}
+
+/// Accepts an invitation as an existing user. New users pass the token to
+/// `CreateUser` instead.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize, Validate)]
+pub struct RequestAcceptInvitation {
+    #[validate(length(min = 1))]
+    pub token: String,
+    #[validate(custom(function = "validate_timeuuid"))]
+    pub user_id: String,
+}
+
+impl RequestAcceptInvitation {
+    pub fn try_into_domain(self) -> AppResult<Self> {
+        self.validate()?;
+        Ok(self)
+    }
+}
+
+#[derive(Debug, Error)]
+pub enum RequestInvitationError {
+    #[error("Invitation not found")]
+    InvitationNotFound,
+    #[error("Invitation token is invalid")]
+    InvalidToken,
+    #[error("Invitation has expired")]
+    Expired,
+    #[error("Invitation was sent to another email")]
+    EmailMismatch,
+    #[error("User already is a member of the organization")]
+    AlreadyAMember,
+    #[error("Too many invitations, retry in {retry_after_seconds} seconds")]
+    TooManyInvitations { retry_after_seconds: i64 },
+}
+
+#[derive(Debug, Error)]
+pub enum RequestOrganizationError {
+    #[error("Organization not found")]
+    OrganizationNotFound,
+    #[error("User is not a member of the organization")]
+    NotAMember,
+    #[error("The owner can not be removed or change role")]
+    OwnerUnchangeable,
+    #[error("An organization has exactly one owner")]
+    OwnerRoleTaken,
+}
+
+fn trimmed(value: Option<String>) -> Option<String> {
+    value
+        .map(|value| value.trim().to_string())
+        .filter(|value| !value.is_empty())
+}
@@ -0,0 +1,78 @@
+use crate::domain::organization::entity::{
+    Organization, OrganizationInvitation, OrganizationMember,
+};
+use serde::{Deserialize, Serialize};
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct PublicOrganization {
+    pub organization_id: String,
+    pub name: String,
+    pub description: Option<String>,
+    pub owner_id: String,
+    pub created_at: String,
+    pub updated_at: String,
+}
+
+impl From<&Organization> for PublicOrganization {
+    fn from(organization: &Organization) -> Self {
+        Self {
+            organization_id: organization.organization_id.to_string(),
+            name: (*organization.name).to_string(),
+            description: organization.description.clone(),
+            owner_id: organization.owner_id.to_string(),
+            created_at: organization.created_at.to_rfc3339(),
+            updated_at: organization.updated_at.to_rfc3339(),
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct PublicOrganizationMember {
+    pub organization_id: String,
+    pub user_id: String,
+    pub role: String,
+    pub joined_at: String,
+}
+
+impl From<&OrganizationMember> for PublicOrganizationMember {
+    fn from(member: &OrganizationMember) -> Self {
+        Self {
+            organization_id: member.organization_id.to_string(),
+            user_id: member.user_id.to_string(),
+            role: (*member.role).to_string(),
+            joined_at: member.joined_at.to_rfc3339(),
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct PublicOrganizationMemberPage {
+    pub members: Vec<PublicOrganizationMember>,
+    pub next_page_token: Option<String>,
+}
+
+/// A pending invitation. Its token is only ever mailed to the invitee.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct PublicOrganizationInvitation {
+    pub organization_id: String,
+    pub invitation_id: String,
+    pub email: String,
+    pub role: String,
+    pub invited_by: String,
+    pub created_at: String,
+    pub expires_at: String,
+}
+
+impl From<&OrganizationInvitation> for PublicOrganizationInvitation {
+    fn from(invitation: &OrganizationInvitation) -> Self {
+        Self {
+            organization_id: invitation.organization_id.to_string(),
+            invitation_id: invitation.invitation_id.to_string(),
+            email: (*invitation.email).to_string(),
+            role: (*invitation.role).to_string(),
+            invited_by: invitation.invited_by.to_string(),
+            created_at: invitation.created_at.to_rfc3339(),
+            expires_at: invitation.expires_at.to_rfc3339(),
+        }
+    }
+}
@@ -1,12 +1,57 @@
 use super::{
+    cursor::PageTokenSigner,
     request::{
-        RequestCreateUser, RequestGetUser, RequestGetUserByPrimaryKey, RequestUpdateUserStatus,
+        RequestChangePassword, RequestChangePasswordError, RequestCreateUser, RequestDeleteUser,
+        RequestDeleteUserError, RequestFindUserError, RequestGetUser, RequestGetUserByPrimaryKey,
+        RequestGetUserStatusHistory, RequestListUsers, RequestMoveUserLocation,
+        RequestMoveUserLocationError, RequestPurgeUser, RequestRestoreUser, RequestUpdateUser,
+        RequestUpdateUserStatus, RequestVerifyEmail, RequestVerifyEmailError, DEFAULT_PAGE_SIZE,
     },
-    response::PublicUser,
+    response::{PublicUser, PublicUserPage, PublicUserStatus, PublicUserStatusPage},
 };
-use crate::domain::user::{entity::User, repository::UserRepository};
+use crate::{
+    application::{
+        access::permission::{Permission, PermissionError, RolePermissions},
+        auth::{
+            password::verify_password,
+            policy::PasswordPolicy,
+            secret::{generate_secret, hash_secret, secret_matches},
+        },
+        id::parse_id,
+        organization::{
+            invitation::{find_invitation, redeem_invitation},
+            request::RequestOrganizationError,
+        },
+    },
+    domain::{
+        auth::{
+            entity::{LoginAttempt, RefreshToken},
+            repository::{LoginAttemptRepository, MfaRepository, RefreshTokenRepository},
+        },
+        mail::{Mail, MailSender},
+        organization::{
+            entity::{Organization, OrganizationInvitation, OrganizationMember, OrganizationRole},
+            repository::{InvitationRepository, OrganizationRepository},
+        },
+        user::{
+            entity::{
+                ReasonOfStatus, StatusChangedBy, User, UserColumn, UserDeletion, UserStatus,
+                UserStatusError,
+            },
+            identifier::canonical_email,
+            repository::UserRepository,
+        },
+    },
+};
+use anyhow::bail;
+use charybdis::types::Timeuuid;
+use chrono::{Duration, Utc};
 use std::{future::Future, sync::Arc};
-use uptop_core::common::result::AppResult;
+use uptop_core::common::{result::AppResult, utils::new_password};
+
+const EMAIL_VERIFY_CODE_TTL_HOURS: i64 = 24;
+/// How long a soft deleted user can be restored before it is purged.
+pub const DELETION_GRACE_PERIOD_DAYS: i64 = 30;
 
 pub trait UserAppInterface: Clone + Send + Sync + 'static {
     fn create_user(
@@ -24,8 +69,20 @@ pub trait UserAppInterface: Clone + Send + Sync + 'static {
         query: &RequestGetUser,
     ) -> impl Future<Output = AppResult<PublicUser>> + Send;
 
-    fn update_user(&self, user: &User) -> impl Future<Output = AppResult<PublicUser>> + Send;
+    /// Deleted users are left out of a page, so it can be shorter than the
+    /// page size, or empty, while a next page token is still returned.
+    fn find_users(
+        &self,
+        query: &RequestListUsers,
+    ) -> impl Future<Output = AppResult<PublicUserPage>> + Send;
+
+    fn update_user(
+        &self,
+        changes: RequestUpdateUser,
+    ) -> impl Future<Output = AppResult<PublicUser>> + Send;
 
+    /// Changes the status of a user, if [`UserStatus::check_transition`]
+    /// allows it for the actor named in `payload`.
     fn push_new_user_status(
         &self,
         payload: &RequestUpdateUserStatus,
@@ -35,62 +92,754 @@ pub trait UserAppInterface: Clone + Send + Sync + 'static {
         &self,
         query: &RequestGetUser,
     ) -> impl Future<Output = AppResult<User>> + Send;
+
+    fn verify_email(
+        &self,
+        req: RequestVerifyEmail,
+    ) -> impl Future<Output = AppResult<PublicUser>> + Send;
+
+    fn change_password(
+        &self,
+        req: RequestChangePassword,
+    ) -> impl Future<Output = AppResult<PublicUser>> + Send;
+
+    fn move_user_location(
+        &self,
+        req: RequestMoveUserLocation,
+    ) -> impl Future<Output = AppResult<PublicUser>> + Send;
+
+    fn delete_user(
+        &self,
+        req: RequestDeleteUser,
+    ) -> impl Future<Output = AppResult<PublicUser>> + Send;
+
+    fn restore_user(
+        &self,
+        req: RequestRestoreUser,
+    ) -> impl Future<Output = AppResult<PublicUser>> + Send;
+
+    fn purge_user(
+        &self,
+        req: RequestPurgeUser,
+    ) -> impl Future<Output = AppResult<PublicUser>> + Send;
+
+    fn get_user_status_history(
+        &self,
+        req: RequestGetUserStatusHistory,
+    ) -> impl Future<Output = AppResult<PublicUserStatusPage>> + Send;
+
+    /// Purges the soft deleted users whose grace period ended. Returns how
+    /// many were purged.
+    fn purge_expired_users(&self) -> impl Future<Output = AppResult<usize>> + Send;
 }
 
 #[derive(Clone, Debug)]
-pub struct UserApp<US>
+pub struct UserApp<US, RS, FS, LS, MS, OS, IS>
 where
     US: UserRepository,
+    RS: RefreshTokenRepository,
+    FS: MfaRepository,
+    LS: LoginAttemptRepository,
+    MS: MailSender,
+    OS: OrganizationRepository,
+    IS: InvitationRepository,
 {
     user_repo: Arc<US>,
+    refresh_token_repo: Arc<RS>,
+    mfa_repo: Arc<FS>,
+    login_attempt_repo: Arc<LS>,
+    mail_sender: Arc<MS>,
+    organization_repo: Arc<OS>,
+    invitation_repo: Arc<IS>,
+    role_permissions: Arc<RolePermissions>,
+    password_policy: PasswordPolicy,
+    page_tokens: PageTokenSigner,
 }
 
-impl<US> UserApp<US>
+impl<US, RS, FS, LS, MS, OS, IS> UserApp<US, RS, FS, LS, MS, OS, IS>
 where
     US: UserRepository,
+    RS: RefreshTokenRepository,
+    FS: MfaRepository,
+    LS: LoginAttemptRepository,
+    MS: MailSender,
+    OS: OrganizationRepository,
+    IS: InvitationRepository,
 {
-    pub fn new(user_repo: Arc<US>) -> Self {
-        Self { user_repo }
+    #[allow(clippy::too_many_arguments)]
+    pub fn new(
+        user_repo: Arc<US>,
+        refresh_token_repo: Arc<RS>,
+        mfa_repo: Arc<FS>,
+        login_attempt_repo: Arc<LS>,
+        mail_sender: Arc<MS>,
+        organization_repo: Arc<OS>,
+        invitation_repo: Arc<IS>,
+        role_permissions: Arc<RolePermissions>,
+        password_policy: PasswordPolicy,
+        page_tokens: PageTokenSigner,
+    ) -> Self {
+        Self {
+            user_repo,
+            refresh_token_repo,
+            mfa_repo,
+            login_attempt_repo,
+            mail_sender,
+            organization_repo,
+            invitation_repo,
+            role_permissions,
+            password_policy,
+            page_tokens,
+        }
+    }
+
+    /// The organizations named by `company_id` on sign up, which must exist.
+    async fn find_companies(&self, company_ids: &[String]) -> AppResult<Vec<Organization>> {
+        let mut organizations = Vec::with_capacity(company_ids.len());
+        for company_id in company_ids {
+            match self
+                .organization_repo
+                .find_organization(parse_id("company_id", company_id)?)
+                .await?
+            {
+                Some(organization) => organizations.push(organization),
+                None => bail!(RequestOrganizationError::OrganizationNotFound),
+            }
+        }
+        Ok(organizations)
+    }
+
+    /// Makes the new `user` a member of `companies` and redeems `invitation`.
+    /// The first organization joined is the one the user starts in.
+    async fn join_organizations(
+        &self,
+        user: &mut User,
+        companies: &[Organization],
+        invitation: Option<&OrganizationInvitation>,
+    ) -> AppResult<()> {
+        for company in companies {
+            // Joined with the invited role below.
+            if invitation
+                .is_some_and(|invitation| invitation.organization_id == company.organization_id)
+            {
+                continue;
+            }
+            let member = OrganizationMember {
+                organization_id: company.organization_id,
+                user_id: user.user_id,
+                role: OrganizationRole::Member.to_string(),
+                joined_at: user.created_at,
+            };
+            self.organization_repo.add_member(&member, user).await?;
+            user.organizations
+                .get_or_insert_with(Vec::new)
+                .push(company.organization_id);
+        }
+        if let Some(invitation) = invitation {
+            redeem_invitation(
+                self.organization_repo.as_ref(),
+                self.invitation_repo.as_ref(),
+                invitation,
+                user,
+            )
+            .await?;
+        }
+        let first = user.organizations.as_deref().and_then(|ids| ids.first());
+        if let Some(&organization_id) = first {
+            self.organization_repo
+                .set_active_organization(user, Some(organization_id))
+                .await?;
+            user.active_organization = Some(organization_id);
+        }
+        Ok(())
+    }
+
+    /// Takes back a user whose sign up failed half way, so the user name and
+    /// email can be used again. Failures are logged, the caller already
+    /// reports the error that got it here.
+    async fn remove_created_user(&self, user: &User) {
+        for &organization_id in user.organizations.as_deref().unwrap_or_default() {
+            if let Err(err) = self
+                .organization_repo
+                .remove_member(organization_id, user)
+                .await
+            {
+                tracing::error!("Can not remove member of a failed sign up: {err:?}");
+            }
+        }
+        if let Err(err) = self.user_repo.remove_user(user).await {
+            tracing::error!("Can not remove user of a failed sign up: {err:?}");
+        }
+    }
+
+    /// Mails the verification code. Delivery failures are logged rather than
+    /// failing the caller, the account itself is already stored.
+    async fn send_email_verification(&self, email: &str, code: &str) {
+        let mail = Mail {
+            to: email.to_owned(),
+            subject: "Verify your email".to_owned(),
+            body: format!(
+                "Your verification code is {code}. It expires in {EMAIL_VERIFY_CODE_TTL_HOURS} hours."
+            ),
+        };
+
+        if let Err(err) = self.mail_sender.send(mail).await {
+            tracing::error!("Can not send verification mail: {err:?}");
+        }
+    }
+
+    /// Gives up `email` for `user_id`. Failures are logged, the email stays
+    /// claimed by the user until it is released again.
+    async fn release_email(&self, email: &str, user_id: Timeuuid) {
+        if let Err(err) = self.user_repo.release_email(email, user_id).await {
+            tracing::error!(%user_id, "Can not release email: {err:?}");
+        }
+    }
+
+    async fn push_status(&self, user: &mut User, status: UserStatus) -> AppResult<()> {
+        change_status(
+            self.user_repo.as_ref(),
+            user,
+            status,
+            StatusChangedBy::System,
+            None,
+            None,
+        )
+        .await
+    }
+
+    /// Scrubs `user` down to a tombstone, drops its second factor and failed
+    /// logins and signs it out everywhere. The tombstone is written last, so
+    /// an erase that failed part way is finished by the next one.
+    async fn erase(&self, mut user: User) -> AppResult<User> {
+        let erased = UserStatus::Deleted(ReasonOfStatus::Erased);
+        if user.current_status()? != erased {
+            self.push_status(&mut user, erased).await?;
+        }
+
+        self.mfa_repo.delete_mfa_factor(user.user_id).await?;
+        self.login_attempt_repo
+            .clear_login_attempt(LoginAttempt::USER_SCOPE, &user.user_id.to_string())
+            .await?;
+        self.refresh_token_repo
+            .revoke_refresh_tokens(user.user_id)
+            .await?;
+
+        let mut tombstone = user.clone();
+        let scrubbed = tombstone.scrub_personal_data();
+        self.user_repo
+            .purge_user(&user, &tombstone, &scrubbed)
+            .await?;
+        Ok(tombstone)
+    }
+
+    /// Family of the session behind `refresh_token`, if it is a live token
+    /// of `user`.
+    async fn session_family(
+        &self,
+        user: &User,
+        refresh_token: &str,
+    ) -> AppResult<Option<Timeuuid>> {
+        let Some((user_id, token_id, secret)) = RefreshToken::parse_client_value(refresh_token)
+        else {
+            return Ok(None);
+        };
+        if user_id != user.user_id {
+            return Ok(None);
+        }
+
+        let family = self
+            .refresh_token_repo
+            .find_refresh_token(user_id, token_id)
+            .await?
+            .filter(|token| token.expires_at > Utc::now())
+            .filter(|token| secret_matches(secret, &token.token_hash))
+            .map(|token| token.family_id);
+
+        Ok(family)
     }
 }
 
-impl<US> UserAppInterface for UserApp<US>
+impl<US, RS, FS, LS, MS, OS, IS> UserAppInterface for UserApp<US, RS, FS, LS, MS, OS, IS>
 where
     US: UserRepository,
+    RS: RefreshTokenRepository,
+    FS: MfaRepository,
+    LS: LoginAttemptRepository,
+    MS: MailSender,
+    OS: OrganizationRepository,
+    IS: InvitationRepository,
 {
     async fn create_user(&self, req: RequestCreateUser) -> AppResult<PublicUser> {
-        self.user_repo
-            .create_user(&User::try_from(req)?)
+        self.password_policy
+            .check("password", &req.password, &req.user_name, &req.email)?;
+        let req = RequestCreateUser {
+            password: new_password(&req.password)?,
+            ..req
+        };
+
+        let companies = self
+            .find_companies(req.company_id.as_deref().unwrap_or_default())
+            .await?;
+        let invitation = match req.invitation_token.as_deref() {
+            Some(token) => {
+                Some(find_invitation(self.invitation_repo.as_ref(), token, &req.email).await?)
+            }
+            None => None,
+        };
+        // The handler only checked the companies of sign ups without an
+        // invitation; an invitation vouches for its own organization alone.
+        if let Some(invitation) = &invitation {
+            if companies
+                .iter()
+                .any(|company| company.organization_id != invitation.organization_id)
+            {
+                bail!(PermissionError::Denied {
+                    permission: Permission::OrgManageMembers
+                })
+            }
+        }
+        let mut user = User::try_from(req)?;
+        let code = issue_email_verify_code(&mut user);
+
+        self.user_repo.create_user(&user).await?;
+        if let Err(err) = self
+            .join_organizations(&mut user, &companies, invitation.as_ref())
             .await
-            .map(|user| user.try_into())?
+        {
+            self.remove_created_user(&user).await;
+            return Err(err);
+        }
+
+        self.send_email_verification(&user.email, &code).await;
+        (&user).try_into()
     }
 
     async fn find_user_by_id(&self, query: &RequestGetUserByPrimaryKey) -> AppResult<PublicUser> {
-        self.user_repo
-            .find_user_by_id(query)
-            .await
-            .map(|ref user| user.try_into())?
+        let user = self.user_repo.find_user_by_id(query).await?;
+        if user.is_deleted() {
+            bail!(RequestFindUserError::UserNotFound)
+        }
+        (&user).try_into()
     }
 
     async fn find_user(&self, query: &RequestGetUser) -> AppResult<PublicUser> {
-        self.user_repo
-            .find_user(query)
-            .await
-            .map(|ref user| user.try_into())?
+        let user = self.user_repo.find_user(query).await?;
+        if user.is_deleted() {
+            bail!(RequestFindUserError::UserNotFound)
+        }
+        (&user).try_into()
     }
 
-    async fn update_user(&self, user: &User) -> AppResult<PublicUser> {
-        self.user_repo
-            .update_user(user)
-            .await
-            .map(|user| user.try_into())?
+    async fn find_users(&self, query: &RequestListUsers) -> AppResult<PublicUserPage> {
+        let partition = query.partition_key();
+        let paging_state = match query.page_token.as_deref() {
+            Some(token) => Some(self.page_tokens.verify(&partition, token)?),
+            None => None,
+        };
+
+        let page = self
+            .user_repo
+            .find_users(
+                &partition,
+                query.page_size.unwrap_or(DEFAULT_PAGE_SIZE),
+                paging_state,
+            )
+            .await?;
+
+        Ok(PublicUserPage {
+            users: page
+                .users
+                .iter()
+                .filter(|user| !user.is_deleted())
+                .map(PublicUser::try_from)
+                .collect::<AppResult<_>>()?,
+            next_page_token: page
+                .paging_state
+                .map(|state| self.page_tokens.sign(&partition, &state)),
+        })
+    }
+
+    async fn update_user(&self, changes: RequestUpdateUser) -> AppResult<PublicUser> {
+        let mut user = self
+            .user_repo
+            .find_user_by_id(&changes.primary_key())
+            .await?;
+        if user.is_deleted() || user.purged_at.is_some() {
+            bail!(RequestFindUserError::UserNotFound)
+        }
+        let previous_email = user.email.clone();
+        let mut columns = user.apply_update(changes);
+
+        let email_changed = canonical_email(&user.email) != canonical_email(&previous_email);
+        let mut code = None;
+        if email_changed {
+            code = Some(issue_email_verify_code(&mut user));
+            columns.extend([
+                UserColumn::EmailVerifyCode,
+                UserColumn::EmailVerifyCodeExpiresAt,
+            ]);
+            self.user_repo.claim_email(&user).await?;
+        }
+        if let Err(err) = self.user_repo.update_user_columns(&user, &columns).await {
+            if email_changed {
+                self.release_email(&user.email, user.user_id).await;
+            }
+            return Err(err);
+        }
+        if email_changed {
+            self.release_email(&previous_email, user.user_id).await;
+        }
+        if let Some(code) = code {
+            self.send_email_verification(&user.email, &code).await;
+        }
+
+        (&user).try_into()
     }
 
     async fn push_new_user_status(&self, payload: &RequestUpdateUserStatus) -> AppResult<bool> {
-        self.user_repo.push_new_user_status(payload).await
+        let mut user = self
+            .user_repo
+            .find_user_by_id(&payload.primary_key())
+            .await?;
+        let status = UserStatus::parse(Some(&payload.status))?;
+
+        let actor_id = payload
+            .actor_id
+            .as_deref()
+            .map(|actor_id| parse_id("actor_id", actor_id))
+            .transpose()?;
+        let by = match actor_id {
+            None => StatusChangedBy::System,
+            Some(actor_id) => {
+                let actor = match actor_id == user.user_id {
+                    true => user.clone(),
+                    false => {
+                        self.user_repo
+                            .find_user_by_id(&RequestGetUserByPrimaryKey::from_user_id(actor_id))
+                            .await?
+                    }
+                };
+                if self
+                    .role_permissions
+                    .global_grants(&actor.role, Permission::UserUpdateStatus)
+                {
+                    StatusChangedBy::Admin
+                } else if actor_id == user.user_id
+                    && self
+                        .role_permissions
+                        .own_grants(Permission::UserUpdateStatus)
+                {
+                    StatusChangedBy::Owner
+                } else {
+                    bail!(UserStatusError::NotPermitted {
+                        status: payload.status.clone()
+                    })
+                }
+            }
+        };
+
+        change_status(
+            self.user_repo.as_ref(),
+            &mut user,
+            status,
+            by,
+            actor_id,
+            payload.note.clone(),
+        )
+        .await?;
+        Ok(true)
     }
 
     async fn get_full_field_user(&self, query: &RequestGetUser) -> AppResult<User> {
         self.user_repo.find_user(query).await
     }
+
+    async fn verify_email(&self, req: RequestVerifyEmail) -> AppResult<PublicUser> {
+        let lookup = RequestGetUser {
+            email: Some((*req.email).to_string()),
+            ..Default::default()
+        };
+        let mut user = match self.user_repo.find_user(&lookup).await {
+            Ok(user) => user,
+            Err(err) if err.downcast_ref::<RequestFindUserError>().is_some() => {
+                bail!(RequestVerifyEmailError::InvalidCode)
+            }
+            Err(err) => return Err(err),
+        };
+
+        // Only the holder of a code learns whether the email is verified.
+        let code_matches = user
+            .email_verify_code
+            .as_deref()
+            .is_some_and(|hash| secret_matches(&req.code, hash));
+        if !code_matches {
+            bail!(RequestVerifyEmailError::InvalidCode)
+        }
+        if user.email_verified_at.is_some() {
+            bail!(RequestVerifyEmailError::AlreadyVerified)
+        }
+
+        let now = Utc::now();
+        if user
+            .email_verify_code_expires_at
+            .is_none_or(|expires_at| expires_at <= now)
+        {
+            bail!(RequestVerifyEmailError::CodeExpired)
+        }
+
+        // The code is kept so sending it again reports `AlreadyVerified`.
+        user.email_verified_at = Some(now);
+        user.email_verify_code_expires_at = None;
+        user.updated_at = now;
+        self.user_repo
+            .update_user_columns(
+                &user,
+                &[
+                    UserColumn::EmailVerifiedAt,
+                    UserColumn::EmailVerifyCodeExpiresAt,
+                    UserColumn::UpdatedAt,
+                ],
+            )
+            .await?;
+
+        if user.current_status()? == UserStatus::Inactive(ReasonOfStatus::FirstTimeAccess) {
+            self.push_status(&mut user, UserStatus::Active(ReasonOfStatus::AfterRegister))
+                .await?;
+        }
+
+        (&user).try_into()
+    }
+
+    async fn change_password(&self, req: RequestChangePassword) -> AppResult<PublicUser> {
+        let mut user = self.user_repo.find_user_by_id(&req.primary_key()).await?;
+
+        if !verify_password(&req.current_password, &user.password) {
+            bail!(RequestChangePasswordError::IncorrectPassword)
+        }
+        self.password_policy.check(
+            "new_password",
+            &req.new_password,
+            &user.user_name,
+            &user.email,
+        )?;
+
+        user.password = new_password(&req.new_password)?;
+        user.updated_at = Utc::now();
+        self.user_repo
+            .update_user_columns(&user, &[UserColumn::Password, UserColumn::UpdatedAt])
+            .await?;
+
+        if req.revoke_other_sessions {
+            let family = match req.refresh_token.as_deref() {
+                Some(token) => self.session_family(&user, token).await?,
+                None => None,
+            };
+            match family {
+                Some(family_id) => {
+                    self.refresh_token_repo
+                        .revoke_other_refresh_token_families(user.user_id, family_id)
+                        .await?
+                }
+                None => {
+                    self.refresh_token_repo
+                        .revoke_refresh_tokens(user.user_id)
+                        .await?
+                }
+            }
+        }
+
+        (&user).try_into()
+    }
+
+    async fn move_user_location(&self, req: RequestMoveUserLocation) -> AppResult<PublicUser> {
+        let user = self.user_repo.find_user_by_id(&req.primary_key()).await?;
+        let moved = User {
+            country: req.new_country,
+            region: req.new_region,
+            city: req.new_city,
+            post_code: req.new_post_code.unwrap_or_else(|| user.post_code.clone()),
+            updated_at: Utc::now(),
+            ..user.clone()
+        };
+
+        // The request may carry a stale location that already is the target.
+        if (&moved.country, &moved.region, &moved.city) == (&user.country, &user.region, &user.city)
+        {
+            bail!(RequestMoveUserLocationError::SameLocation)
+        }
+
+        self.user_repo.move_user(&user, &moved).await?;
+        (&moved).try_into()
+    }
+
+    async fn delete_user(&self, req: RequestDeleteUser) -> AppResult<PublicUser> {
+        let mut user = self.user_repo.find_user_by_id(&req.primary_key()).await?;
+        if user.is_deleted() {
+            bail!(RequestDeleteUserError::AlreadyDeleted)
+        }
+
+        let reason = ReasonOfStatus::parse(req.reason.as_deref().unwrap_or_default())?;
+        // The status goes first so a refused transition leaves nothing behind.
+        self.push_status(&mut user, UserStatus::Deleted(reason))
+            .await?;
+        let deleted_at = Utc::now();
+        user.deleted_at = Some(deleted_at);
+        user.updated_at = deleted_at;
+        self.user_repo
+            .update_user_columns(&user, &[UserColumn::DeletedAt, UserColumn::UpdatedAt])
+            .await?;
+
+        self.user_repo
+            .schedule_purge(&UserDeletion {
+                user_id: user.user_id,
+                country: (*user.country).to_string(),
+                region: (*user.region).to_string(),
+                city: (*user.city).to_string(),
+                deleted_at,
+                purge_after: deleted_at + Duration::days(DELETION_GRACE_PERIOD_DAYS),
+            })
+            .await?;
+        self.refresh_token_repo
+            .revoke_refresh_tokens(user.user_id)
+            .await?;
+
+        (&user).try_into()
+    }
+
+    async fn restore_user(&self, req: RequestRestoreUser) -> AppResult<PublicUser> {
+        let mut user = self.user_repo.find_user_by_id(&req.primary_key()).await?;
+        if !user.is_deleted() {
+            bail!(RequestDeleteUserError::NotDeleted)
+        }
+
+        let restorable = user.purged_at.is_none()
+            && user.deleted_at.is_some_and(|deleted_at| {
+                Utc::now() < deleted_at + Duration::days(DELETION_GRACE_PERIOD_DAYS)
+            });
+        if !restorable {
+            bail!(RequestDeleteUserError::RestorePeriodEnded)
+        }
+
+        self.push_status(&mut user, UserStatus::Active(ReasonOfStatus::Restored))
+            .await?;
+        user.deleted_at = None;
+        user.updated_at = Utc::now();
+        self.user_repo
+            .update_user_columns(&user, &[UserColumn::DeletedAt, UserColumn::UpdatedAt])
+            .await?;
+        self.user_repo.cancel_purge(user.user_id).await?;
+
+        (&user).try_into()
+    }
+
+    async fn purge_user(&self, req: RequestPurgeUser) -> AppResult<PublicUser> {
+        let user = self.user_repo.find_user_by_id(&req.primary_key()).await?;
+        if user.purged_at.is_some() {
+            return (&user).try_into();
+        }
+
+        let tombstone = self.erase(user).await?;
+        (&tombstone).try_into()
+    }
+
+    async fn purge_expired_users(&self) -> AppResult<usize> {
+        let mut purged = 0;
+        for deletion in self.user_repo.find_due_purges(Utc::now()).await? {
+            let lookup = RequestGetUserByPrimaryKey::new(
+                deletion.country,
+                deletion.region,
+                deletion.city,
+                deletion.user_id,
+            );
+            let result = match self.user_repo.find_user_by_id(&lookup).await {
+                // Restored while the job was queued.
+                Ok(user) if !user.is_deleted() || user.purged_at.is_some() => {
+                    self.user_repo.cancel_purge(user.user_id).await
+                }
+                Ok(user) => self.erase(user).await.map(|_| purged += 1),
+                Err(err) if err.is::<RequestFindUserError>() => {
+                    self.user_repo.cancel_purge(deletion.user_id).await
+                }
+                Err(err) => Err(err),
+            };
+
+            // One broken account must not hold up the others.
+            if let Err(err) = result {
+                tracing::error!(user_id = %deletion.user_id, "Can not purge user: {err:?}");
+            }
+        }
+        Ok(purged)
+    }
+
+    async fn get_user_status_history(
+        &self,
+        req: RequestGetUserStatusHistory,
+    ) -> AppResult<PublicUserStatusPage> {
+        let user = self.user_repo.find_user_by_id(&req.primary_key()).await?;
+        let user_id = user.user_id.to_string();
+        let paging_state = match req.page_token.as_deref() {
+            Some(token) => Some(self.page_tokens.verify_status_history(&user_id, token)?),
+            None => None,
+        };
+
+        let page = self
+            .user_repo
+            .find_status_history(
+                user.user_id,
+                req.page_size.unwrap_or(DEFAULT_PAGE_SIZE),
+                paging_state,
+            )
+            .await?;
+
+        Ok(PublicUserStatusPage {
+            changes: page.changes.iter().map(PublicUserStatus::from).collect(),
+            next_page_token: page
+                .paging_state
+                .map(|state| self.page_tokens.sign_status_history(&user_id, &state)),
+        })
+    }
+}
+
+/// Gives `user` a new email verification code and returns it for the mail;
+/// only its hash is stored.
+fn issue_email_verify_code(user: &mut User) -> String {
+    let code = generate_secret();
+    user.email_verify_code = Some(hash_secret(&code));
+    user.email_verify_code_expires_at =
+        Some(Utc::now() + Duration::hours(EMAIL_VERIFY_CODE_TTL_HOURS));
+    code
+}
+
+/// Moves `user` to `status` if the state machine allows it. The write only
+/// applies while the stored status still is the one `user` was read with.
+pub(crate) async fn change_status<US: UserRepository>(
+    user_repo: &US,
+    user: &mut User,
+    status: UserStatus,
+    by: StatusChangedBy,
+    actor_id: Option<Timeuuid>,
+    note: Option<String>,
+) -> AppResult<()> {
+    let current = user.current_status()?;
+    current.check_transition(&status, by)?;
+
+    let payload = RequestUpdateUserStatus {
+        status: UserStatus::transform(&status),
+        country: (*user.country).to_string(),
+        region: (*user.region).to_string(),
+        city: (*user.city).to_string(),
+        user_id: user.user_id.to_string(),
+        actor_id: actor_id.map(|actor_id| actor_id.to_string()),
+        note,
+    };
+    if user_repo.push_new_user_status(&payload, &current).await? {
+        user.set_status(&status, Utc::now());
+        return Ok(());
+    }
+
+    // Either gone, which the lookup reports, or changed concurrently, in
+    // which case `user` is brought up to date for the caller.
+    *user = user_repo.find_user_by_id(&payload.primary_key()).await?;
+    bail!(UserStatusError::Changed)
 }
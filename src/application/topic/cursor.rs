@@ -0,0 +1,240 @@
+use super::request::{RequestGetUserByPartitionKey, RequestListUsersError};
+use anyhow::{anyhow, Context};
+use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine};
+use hmac::{Hmac, Mac};
+use sha2::Sha256;
+use std::{
+    fmt::{Debug, Formatter},
+    sync::Arc,
+};
+use uptop_core::common::result::AppResult;
+
+type HmacSha256 = Hmac<Sha256>;
+
+const SIGNATURE_LEN: usize = 32;
+/// Shortest accepted `PAGE_TOKEN_SECRET`, the size of the HMAC-SHA256 output.
+const MIN_SECRET_LEN: usize = 32;
+const USERS_SCOPE: &str = "users";
+const STATUS_HISTORY_SCOPE: &str = "status_history";
+const ORGANIZATION_MEMBERS_SCOPE: &str = "organization_members";
+const DELEGATION_AUDIT_SCOPE: &str = "delegation_audit";
+
+/// Turns raw Scylla paging states into opaque page tokens and back.
+///
+/// A token is `base64url(paging_state || hmac)`, where the HMAC also covers the
+/// kind of listing and the partition being listed, so a token can neither be
+/// forged nor replayed against another listing, another city, another user's
+/// status history or another organization.
+#[derive(Clone)]
+pub struct PageTokenSigner {
+    key: Arc<[u8]>,
+}
+
+impl PageTokenSigner {
+    pub fn new(key: impl AsRef<[u8]>) -> Self {
+        Self {
+            key: Arc::from(key.as_ref()),
+        }
+    }
+
+    pub fn from_env() -> AppResult<Self> {
+        let key = std::env::var("PAGE_TOKEN_SECRET").context("PAGE_TOKEN_SECRET is not set")?;
+        if key.len() < MIN_SECRET_LEN {
+            return Err(anyhow!(
+                "PAGE_TOKEN_SECRET must be at least {MIN_SECRET_LEN} bytes"
+            ));
+        }
+        Ok(Self::new(key))
+    }
+
+    pub fn sign(&self, partition: &RequestGetUserByPartitionKey, paging_state: &[u8]) -> String {
+        self.sign_scope(USERS_SCOPE, &location(partition), paging_state)
+    }
+
+    pub fn verify(
+        &self,
+        partition: &RequestGetUserByPartitionKey,
+        token: &str,
+    ) -> AppResult<Vec<u8>> {
+        self.verify_scope(USERS_SCOPE, &location(partition), token)
+    }
+
+    pub fn sign_status_history(&self, user_id: &str, paging_state: &[u8]) -> String {
+        self.sign_scope(STATUS_HISTORY_SCOPE, &[user_id], paging_state)
+    }
+
+    pub fn verify_status_history(&self, user_id: &str, token: &str) -> AppResult<Vec<u8>> {
+        self.verify_scope(STATUS_HISTORY_SCOPE, &[user_id], token)
+    }
+
+    pub fn sign_members(&self, organization_id: &str, paging_state: &[u8]) -> String {
+        self.sign_scope(ORGANIZATION_MEMBERS_SCOPE, &[organization_id], paging_state)
+    }
+
+    pub fn verify_members(&self, organization_id: &str, token: &str) -> AppResult<Vec<u8>> {
+        self.verify_scope(ORGANIZATION_MEMBERS_SCOPE, &[organization_id], token)
+    }
+
+    pub fn sign_delegation_audit(&self, user_id: &str, paging_state: &[u8]) -> String {
+        self.sign_scope(DELEGATION_AUDIT_SCOPE, &[user_id], paging_state)
+    }
+
+    pub fn verify_delegation_audit(&self, user_id: &str, token: &str) -> AppResult<Vec<u8>> {
+        self.verify_scope(DELEGATION_AUDIT_SCOPE, &[user_id], token)
+    }
+
+    fn sign_scope(&self, kind: &str, scope: &[&str], paging_state: &[u8]) -> String {
+        let mut token = paging_state.to_vec();
+        token.extend_from_slice(&self.mac(kind, scope, paging_state).finalize().into_bytes());
+        URL_SAFE_NO_PAD.encode(token)
+    }
+
+    fn verify_scope(&self, kind: &str, scope: &[&str], token: &str) -> AppResult<Vec<u8>> {
+        let raw = URL_SAFE_NO_PAD
+            .decode(token)
+            .map_err(|_| anyhow!(RequestListUsersError::InvalidPageToken))?;
+
+        if raw.len() <= SIGNATURE_LEN {
+            return Err(anyhow!(RequestListUsersError::InvalidPageToken));
+        }
+
+        let (paging_state, signature) = raw.split_at(raw.len() - SIGNATURE_LEN);
+        self.mac(kind, scope, paging_state)
+            .verify_slice(signature)
+            .map_err(|_| anyhow!(RequestListUsersError::InvalidPageToken))?;
+
+        Ok(paging_state.to_vec())
+    }
+
+    /// Every field is length prefixed and the parts are counted, so no two
+    /// scopes feed the same bytes into the HMAC.
+    fn mac(&self, kind: &str, scope: &[&str], paging_state: &[u8]) -> HmacSha256 {
+        let mut mac = HmacSha256::new_from_slice(&self.key).expect("HMAC accepts keys of any size");
+        mac.update(&(kind.len() as u64).to_be_bytes());
+        mac.update(kind.as_bytes());
+        mac.update(&(scope.len() as u64).to_be_bytes());
+        for part in scope {
+            mac.update(&(part.len() as u64).to_be_bytes());
+            mac.update(part.as_bytes());
+        }
+        mac.update(paging_state);
+        mac
+    }
+}
+
+fn location(partition: &RequestGetUserByPartitionKey) -> [&str; 3] {
+    [&partition.country, &partition.region, &partition.city]
+}
+
+impl Debug for PageTokenSigner {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("PageTokenSigner").finish_non_exhaustive()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn partition(city: &str) -> RequestGetUserByPartitionKey {
+        RequestGetUserByPartitionKey {
+            country: "VN".to_owned(),
+            region: "HN".to_owned(),
+            city: city.to_owned(),
+        }
+    }
+
+    fn is_invalid_token(result: AppResult<Vec<u8>>) -> bool {
+        matches!(
+            result.unwrap_err().downcast_ref(),
+            Some(RequestListUsersError::InvalidPageToken)
+        )
+    }
+
+    #[test]
+    fn verify_returns_the_signed_paging_state() {
+        let signer = PageTokenSigner::new("secret");
+        let token = signer.sign(&partition("Ba Dinh"), b"paging-state");
+
+        let paging_state = signer.verify(&partition("Ba Dinh"), &token).unwrap();
+
+        assert_eq!(paging_state, b"paging-state");
+    }
+
+    #[test]
+    fn verify_rejects_a_tampered_token() {
+        let signer = PageTokenSigner::new("secret");
+        let mut raw = URL_SAFE_NO_PAD
+            .decode(signer.sign(&partition("Ba Dinh"), b"paging-state"))
+            .unwrap();
+        raw[0] ^= 1;
+
+        let token = URL_SAFE_NO_PAD.encode(raw);
+
+        assert!(is_invalid_token(
+            signer.verify(&partition("Ba Dinh"), &token)
+        ));
+    }
+
+    #[test]
+    fn verify_rejects_another_partition() {
+        let signer = PageTokenSigner::new("secret");
+        let token = signer.sign(&partition("Ba Dinh"), b"paging-state");
+
+        assert!(is_invalid_token(
+            signer.verify(&partition("Hoan Kiem"), &token)
+        ));
+    }
+
+    #[test]
+    fn verify_rejects_another_key() {
+        let token = PageTokenSigner::new("secret").sign(&partition("Ba Dinh"), b"paging-state");
+
+        let result = PageTokenSigner::new("other").verify(&partition("Ba Dinh"), &token);
+
+        assert!(is_invalid_token(result));
+    }
+
+    #[test]
+    fn verify_rejects_another_scope() {
+        let signer = PageTokenSigner::new("secret");
+        let token = signer.sign_status_history("user", b"paging-state");
+
+        assert!(is_invalid_token(
+            signer.verify_delegation_audit("user", &token)
+        ));
+        assert!(is_invalid_token(
+            signer.verify_status_history("other", &token)
+        ));
+    }
+
+    #[test]
+    fn verify_rejects_malformed_tokens() {
+        let signer = PageTokenSigner::new("secret");
+
+        assert!(is_invalid_token(
+            signer.verify(&partition("Ba Dinh"), "not base64!")
+        ));
+        assert!(is_invalid_token(signer.verify(&partition("Ba Dinh"), "")));
+    }
+
+    #[test]
+    fn from_env_rejects_a_short_secret() {
+        std::env::set_var("PAGE_TOKEN_SECRET", "too short");
+        assert!(PageTokenSigner::from_env().is_err());
+
+        std::env::set_var("PAGE_TOKEN_SECRET", "x".repeat(MIN_SECRET_LEN));
+        assert!(PageTokenSigner::from_env().is_ok());
+        std::env::remove_var("PAGE_TOKEN_SECRET");
+    }
+
+    #[test]
+    fn verify_rejects_a_token_of_another_kind_over_the_same_parts() {
+        let signer = PageTokenSigner::new("secret");
+        let token = signer.sign_members("user", b"paging-state");
+
+        assert!(is_invalid_token(
+            signer.verify_status_history("user", &token)
+        ));
+    }
+}
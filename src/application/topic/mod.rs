@@ -1,3 +1,4 @@
 pub mod app;
+pub mod cursor;
 pub mod request;
 pub mod response;
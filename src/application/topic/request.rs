@@ -1,16 +1,26 @@
-use crate::domain::user::entity::{UserRole, UserStatus};
+use crate::{
+    application::id::validate_timeuuid,
+    domain::user::{
+        entity::{ReasonOfStatus, UserRole, UserStatus},
+        identifier::{check_user_name, display_form},
+    },
+};
 use anyhow::bail;
+use charybdis::types::Timeuuid;
 use serde::{Deserialize, Serialize};
+use std::str::FromStr;
 use thiserror::Error;
-use uptop_core::common::{
-    result::{AppError, AppResult},
-    utils::new_password,
-};
-use validator::Validate;
+use uptop_core::common::result::AppResult;
+use validator::{Validate, ValidateEmail, ValidationError};
 
 #[derive(Clone, Debug, PartialEq, Serialize, Deserialize, Validate)]
 pub struct RequestCreateUser {
+    /// Organizations to join as a member; the first one becomes active.
+    #[validate(custom(function = "validate_company_ids"))]
     pub company_id: Option<Vec<String>>,
+    /// Token of an organization invitation sent to `email`, accepted once the
+    /// user is stored.
+    pub invitation_token: Option<String>,
     #[validate(length(min = 3))]
     pub user_name: String,
     #[validate(email)]
@@ -26,29 +36,27 @@ pub struct RequestCreateUser {
     pub region: String,
     pub city: String,
     pub post_code: String,
-    pub email_verify_code: Option<String>,
 }
 
 impl RequestCreateUser {
     pub fn try_into_domain(self) -> AppResult<Self> {
-        match self.validate() {
-            Ok(_) => (),
-            Err(err) => bail!(AppError::BadRequest {
-                msg: err.to_string()
-            }),
-        };
+        self.validate()?;
+        let user_name = display_form(&self.user_name);
+        let email = display_form(&self.email);
+        check_user_name("user_name", &user_name)?;
 
         let parse_status = UserStatus::parse(self.status.as_deref())?;
+        parse_status.check_reason()?;
         let status = Some(UserStatus::transform(&parse_status));
 
-        let pass_hashed = new_password(&self.password)?;
         let role = Some(UserRole::matching(self.role.as_deref())?);
 
         Ok(Self {
             company_id: self.company_id,
-            user_name: self.user_name,
-            email: self.email,
-            password: pass_hashed,
+            invitation_token: self.invitation_token.filter(|token| !token.is_empty()),
+            user_name,
+            email,
+            password: self.password,
             status,
             role,
             display_name: self.display_name,
@@ -59,7 +67,6 @@ impl RequestCreateUser {
             region: self.region,
             city: self.city,
             post_code: self.post_code,
-            email_verify_code: self.email_verify_code,
         })
     }
 }
@@ -77,17 +84,13 @@ pub struct RequestGetUserByPrimaryKey {
     pub country: String,
     pub region: String,
     pub city: String,
+    #[validate(custom(function = "validate_timeuuid"))]
     pub user_id: String,
 }
 
 impl RequestGetUserByPrimaryKey {
     pub fn try_into_domain(self) -> AppResult<Self> {
-        match self.validate() {
-            Ok(_) => (),
-            Err(err) => bail!(AppError::BadRequest {
-                msg: err.to_string()
-            }),
-        };
+        self.validate()?;
 
         Ok(Self {
             country: self.country,
@@ -96,6 +99,29 @@ impl RequestGetUserByPrimaryKey {
             user_id: self.user_id,
         })
     }
+
+    pub fn new(
+        country: impl ToString,
+        region: impl ToString,
+        city: impl ToString,
+        user_id: impl ToString,
+    ) -> Self {
+        Self {
+            country: country.to_string(),
+            region: region.to_string(),
+            city: city.to_string(),
+            user_id: user_id.to_string(),
+        }
+    }
+
+    /// A lookup by id alone; the repository resolves the location.
+    pub fn from_user_id(user_id: impl ToString) -> Self {
+        Self::new("", "", "", user_id)
+    }
+
+    pub fn has_location(&self) -> bool {
+        !self.country.is_empty() && !self.region.is_empty() && !self.city.is_empty()
+    }
 }
 
 #[derive(Clone, Debug, PartialEq, Serialize, Deserialize, Validate)]
@@ -107,12 +133,7 @@ pub struct RequestGetUserByPartitionKey {
 
 impl RequestGetUserByPartitionKey {
     pub fn try_into_domain(self) -> AppResult<Self> {
-        match self.validate() {
-            Ok(_) => (),
-            Err(err) => bail!(AppError::BadRequest {
-                msg: err.to_string()
-            }),
-        };
+        self.validate()?;
 
         Ok(Self {
             country: self.country,
@@ -122,6 +143,46 @@ impl RequestGetUserByPartitionKey {
     }
 }
 
+pub const DEFAULT_PAGE_SIZE: i32 = 50;
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize, Validate)]
+pub struct RequestListUsers {
+    pub country: String,
+    pub region: String,
+    pub city: String,
+    #[validate(range(min = 1, max = 500))]
+    pub page_size: Option<i32>,
+    pub page_token: Option<String>,
+}
+
+impl RequestListUsers {
+    pub fn try_into_domain(self) -> AppResult<Self> {
+        self.validate()?;
+
+        Ok(Self {
+            country: self.country,
+            region: self.region,
+            city: self.city,
+            page_size: Some(self.page_size.unwrap_or(DEFAULT_PAGE_SIZE)),
+            page_token: self.page_token.filter(|token| !token.is_empty()),
+        })
+    }
+
+    pub fn partition_key(&self) -> RequestGetUserByPartitionKey {
+        RequestGetUserByPartitionKey {
+            country: (*self.country).to_string(),
+            region: (*self.region).to_string(),
+            city: (*self.city).to_string(),
+        }
+    }
+}
+
+#[derive(Debug, Error)]
+pub enum RequestListUsersError {
+    #[error("Page token is invalid")]
+    InvalidPageToken,
+}
+
 #[derive(Default, Clone, Debug, PartialEq, Serialize, Deserialize)]
 pub struct RequestGetUser {
     pub user_name: String,
@@ -134,81 +195,346 @@ pub enum RequestFindUserError {
     UserNotFound,
 }
 
+/// Partial update of a user. Every `None` field keeps the stored value; status,
+/// password and verification codes have their own operations.
 #[derive(Clone, Debug, PartialEq, Serialize, Deserialize, Validate)]
 pub struct RequestUpdateUser {
-    pub company_id: Option<Vec<String>>,
+    pub country: String,
+    pub region: String,
+    pub city: String,
+    #[validate(custom(function = "validate_timeuuid"))]
+    pub user_id: String,
     #[validate(email)]
     pub email: Option<String>,
-    pub status: Option<String>,
     pub role: Option<String>,
+    #[validate(length(min = 1))]
     pub display_name: Option<String>,
     pub phone_number: Option<String>,
     pub language: Option<String>,
     pub address: Option<String>,
-    pub email_verify_code: Option<String>,
-    pub password_recovery_code: Option<String>,
-    pub password_recovered_at: Option<String>,
-    pub email_verified_at: Option<String>,
+    #[validate(custom(function = "validate_other_emails"))]
+    pub other_emails: Option<Vec<String>>,
 }
 
 impl RequestUpdateUser {
     pub fn try_into_domain(self) -> AppResult<Self> {
-        match self.validate() {
-            Ok(_) => (),
-            Err(err) => bail!(AppError::BadRequest {
-                msg: err.to_string()
-            }),
-        };
+        self.validate()?;
 
-        let parse_status = UserStatus::parse(self.status.as_deref())?;
-        let status = Some(UserStatus::transform(&parse_status));
-
-        let role = UserRole::matching(self.role.as_deref())?;
+        let role = match self.role.as_deref() {
+            Some(role) => Some(UserRole::matching(Some(role))?),
+            None => None,
+        };
 
         Ok(Self {
-            company_id: self.company_id,
-            email: self.email,
-            status,
-            role: Some(role),
+            country: self.country,
+            region: self.region,
+            city: self.city,
+            user_id: self.user_id,
+            email: self.email.as_deref().map(display_form),
+            role,
             display_name: self.display_name,
             phone_number: self.phone_number,
             language: self.language,
             address: self.address,
-            email_verify_code: self.email_verify_code,
-            password_recovery_code: self.password_recovery_code,
-            password_recovered_at: self.password_recovered_at,
-            email_verified_at: self.email_verified_at,
+            other_emails: self.other_emails,
+        })
+    }
+
+    pub fn primary_key(&self) -> RequestGetUserByPrimaryKey {
+        RequestGetUserByPrimaryKey::new(&self.country, &self.region, &self.city, &self.user_id)
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize, Validate)]
+pub struct RequestChangePassword {
+    pub country: String,
+    pub region: String,
+    pub city: String,
+    #[validate(custom(function = "validate_timeuuid"))]
+    pub user_id: String,
+    #[validate(length(min = 1))]
+    pub current_password: String,
+    #[validate(length(min = 1))]
+    pub new_password: String,
+    /// Signs out every other session of the user.
+    pub revoke_other_sessions: bool,
+    /// Refresh token of the calling session, which is kept when
+    /// `revoke_other_sessions` is set. Without it every session is revoked.
+    pub refresh_token: Option<String>,
+}
+
+impl RequestChangePassword {
+    pub fn try_into_domain(self) -> AppResult<Self> {
+        self.validate()?;
+
+        if self.current_password == self.new_password {
+            bail!(RequestChangePasswordError::SamePassword)
+        }
+
+        Ok(Self {
+            refresh_token: self
+                .refresh_token
+                .map(|token| token.trim().to_string())
+                .filter(|token| !token.is_empty()),
+            ..self
         })
     }
+
+    pub fn primary_key(&self) -> RequestGetUserByPrimaryKey {
+        RequestGetUserByPrimaryKey::new(&self.country, &self.region, &self.city, &self.user_id)
+    }
+}
+
+#[derive(Debug, Error)]
+pub enum RequestChangePasswordError {
+    #[error("Current password is incorrect")]
+    IncorrectPassword,
+    #[error("New password must differ from the current one")]
+    SamePassword,
+}
+
+/// Moves a user to another partition. `user_id`, `created_at` and the status
+/// history are kept.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize, Validate)]
+pub struct RequestMoveUserLocation {
+    pub country: String,
+    pub region: String,
+    pub city: String,
+    #[validate(custom(function = "validate_timeuuid"))]
+    pub user_id: String,
+    #[validate(length(min = 1))]
+    pub new_country: String,
+    #[validate(length(min = 1))]
+    pub new_region: String,
+    #[validate(length(min = 1))]
+    pub new_city: String,
+    /// Keeps the stored post code when `None`.
+    pub new_post_code: Option<String>,
+}
+
+impl RequestMoveUserLocation {
+    pub fn try_into_domain(self) -> AppResult<Self> {
+        self.validate()?;
+
+        let req = Self {
+            new_country: self.new_country.trim().to_string(),
+            new_region: self.new_region.trim().to_string(),
+            new_city: self.new_city.trim().to_string(),
+            new_post_code: self.new_post_code.map(|code| code.trim().to_string()),
+            ..self
+        };
+        if req.country == req.new_country
+            && req.region == req.new_region
+            && req.city == req.new_city
+        {
+            bail!(RequestMoveUserLocationError::SameLocation)
+        }
+        Ok(req)
+    }
+
+    pub fn primary_key(&self) -> RequestGetUserByPrimaryKey {
+        RequestGetUserByPrimaryKey::new(&self.country, &self.region, &self.city, &self.user_id)
+    }
+}
+
+#[derive(Debug, Error)]
+pub enum RequestMoveUserLocationError {
+    #[error("User already lives in this location")]
+    SameLocation,
 }
 
+/// Soft deletes a user. The account can be restored until the grace period
+/// ends, then it is purged.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize, Validate)]
+pub struct RequestDeleteUser {
+    pub country: String,
+    pub region: String,
+    pub city: String,
+    #[validate(custom(function = "validate_timeuuid"))]
+    pub user_id: String,
+    /// Reason of the `Deleted` status, `UserRequested` when `None`.
+    pub reason: Option<String>,
+}
+
+impl RequestDeleteUser {
+    pub fn try_into_domain(self) -> AppResult<Self> {
+        self.validate()?;
+
+        let reason = match self.reason.as_deref() {
+            Some(reason) => ReasonOfStatus::parse(reason)?,
+            None => ReasonOfStatus::UserRequested,
+        };
+
+        Ok(Self {
+            reason: Some(ReasonOfStatus::transform(&reason)),
+            ..self
+        })
+    }
+
+    pub fn primary_key(&self) -> RequestGetUserByPrimaryKey {
+        RequestGetUserByPrimaryKey::new(&self.country, &self.region, &self.city, &self.user_id)
+    }
+}
+
+/// Restores a soft deleted user within the grace period.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize, Validate)]
+pub struct RequestRestoreUser {
+    pub country: String,
+    pub region: String,
+    pub city: String,
+    #[validate(custom(function = "validate_timeuuid"))]
+    pub user_id: String,
+}
+
+impl RequestRestoreUser {
+    pub fn try_into_domain(self) -> AppResult<Self> {
+        self.validate()?;
+        Ok(self)
+    }
+
+    pub fn primary_key(&self) -> RequestGetUserByPrimaryKey {
+        RequestGetUserByPrimaryKey::new(&self.country, &self.region, &self.city, &self.user_id)
+    }
+}
+
+/// Erases the personal data of a user right away, soft deleted or not.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize, Validate)]
+pub struct RequestPurgeUser {
+    pub country: String,
+    pub region: String,
+    pub city: String,
+    #[validate(custom(function = "validate_timeuuid"))]
+    pub user_id: String,
+}
+
+impl RequestPurgeUser {
+    pub fn try_into_domain(self) -> AppResult<Self> {
+        self.validate()?;
+        Ok(self)
+    }
+
+    pub fn primary_key(&self) -> RequestGetUserByPrimaryKey {
+        RequestGetUserByPrimaryKey::new(&self.country, &self.region, &self.city, &self.user_id)
+    }
+}
+
+#[derive(Debug, Error)]
+pub enum RequestDeleteUserError {
+    #[error("User is already deleted")]
+    AlreadyDeleted,
+    #[error("User is not deleted")]
+    NotDeleted,
+    #[error("Grace period to restore the user has ended")]
+    RestorePeriodEnded,
+}
+
+fn validate_company_ids(company_ids: &[String]) -> Result<(), ValidationError> {
+    match company_ids.iter().all(|id| Timeuuid::from_str(id).is_ok()) {
+        true => Ok(()),
+        false => Err(ValidationError::new("timeuuid")),
+    }
+}
+
+fn validate_other_emails(emails: &[String]) -> Result<(), ValidationError> {
+    match emails.iter().all(|email| email.validate_email()) {
+        true => Ok(()),
+        false => Err(ValidationError::new("email")),
+    }
+}
+
+/// Appends to the status history of a user. `status` is `Status:Reason`.
 #[derive(Clone, Debug, PartialEq, Serialize, Deserialize, Validate)]
 pub struct RequestUpdateUserStatus {
     pub status: String,
     pub country: String,
     pub region: String,
     pub city: String,
+    #[validate(custom(function = "validate_timeuuid"))]
     pub user_id: String,
+    /// User making the change; `None` for changes made by the system.
+    #[validate(custom(function = "validate_timeuuid"))]
+    pub actor_id: Option<String>,
+    #[validate(length(max = 500))]
+    pub note: Option<String>,
 }
 
 impl RequestUpdateUserStatus {
     pub fn try_into_domain(self) -> AppResult<Self> {
-        match self.validate() {
-            Ok(_) => (),
-            Err(err) => bail!(AppError::BadRequest {
-                msg: err.to_string()
-            }),
-        };
+        self.validate()?;
 
         let parse_status = UserStatus::parse(Some(self.status.as_str()))?;
+        parse_status.check_reason()?;
         let status = UserStatus::transform(&parse_status);
 
         Ok(Self {
             status,
-            country: self.country,
-            region: self.region,
-            city: self.city,
-            user_id: self.user_id,
+            actor_id: self.actor_id.filter(|actor_id| !actor_id.is_empty()),
+            note: self
+                .note
+                .map(|note| note.trim().to_string())
+                .filter(|note| !note.is_empty()),
+            ..self
+        })
+    }
+
+    pub fn primary_key(&self) -> RequestGetUserByPrimaryKey {
+        RequestGetUserByPrimaryKey::new(&self.country, &self.region, &self.city, &self.user_id)
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize, Validate)]
+pub struct RequestGetUserStatusHistory {
+    pub country: String,
+    pub region: String,
+    pub city: String,
+    #[validate(custom(function = "validate_timeuuid"))]
+    pub user_id: String,
+    #[validate(range(min = 1, max = 500))]
+    pub page_size: Option<i32>,
+    pub page_token: Option<String>,
+}
+
+impl RequestGetUserStatusHistory {
+    pub fn try_into_domain(self) -> AppResult<Self> {
+        self.validate()?;
+
+        Ok(Self {
+            page_size: Some(self.page_size.unwrap_or(DEFAULT_PAGE_SIZE)),
+            page_token: self.page_token.filter(|token| !token.is_empty()),
+            ..self
         })
     }
+
+    pub fn primary_key(&self) -> RequestGetUserByPrimaryKey {
+        RequestGetUserByPrimaryKey::new(&self.country, &self.region, &self.city, &self.user_id)
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize, Validate)]
+pub struct RequestVerifyEmail {
+    #[validate(email)]
+    pub email: String,
+    #[validate(length(min = 1))]
+    pub code: String,
+}
+
+impl RequestVerifyEmail {
+    pub fn try_into_domain(self) -> AppResult<Self> {
+        self.validate()?;
+
+        Ok(Self {
+            email: self.email,
+            code: self.code.trim().to_string(),
+        })
+    }
+}
+
+#[derive(Debug, Error)]
+pub enum RequestVerifyEmailError {
+    #[error("Verification code is invalid")]
+    InvalidCode,
+    #[error("Verification code has expired")]
+    CodeExpired,
+    #[error("Email is already verified")]
+    AlreadyVerified,
 }
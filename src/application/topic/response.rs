@@ -1,4 +1,5 @@
-use crate::domain::user::entity::User;
+use crate::domain::user::entity::{User, UserStatusChange};
+use charybdis::types::Timeuuid;
 use serde::{Deserialize, Serialize};
 use uptop_core::common::result::AppResult;
 
@@ -16,7 +17,12 @@ pub struct PublicUser {
     pub region: String,
     pub city: String,
     pub post_code: String,
-    pub status: String,
+    pub status: PublicUserStatus,
+    pub organizations: Vec<String>,
+    pub active_organization: Option<String>,
+    /// Users managing this one, see `User::owners` and `User::admins`.
+    pub owners: Vec<String>,
+    pub admins: Vec<String>,
     pub other_emails: Option<Vec<String>>,
     pub email_verified_at: Option<String>,
     pub password_recovered_at: Option<String>,
@@ -41,16 +47,64 @@ impl TryFrom<&User> for PublicUser {
             region: (*user.region).to_string(),
             city: (*user.city).to_string(),
             post_code: (*user.post_code).to_string(),
+            organizations: ids_to_strings(user.organizations.as_deref()),
+            active_organization: user.active_organization.map(|id| id.to_string()),
+            owners: ids_to_strings(user.owners.as_deref()),
+            admins: ids_to_strings(user.admins.as_deref()),
             other_emails: Some(user.other_emails.as_deref().unwrap_or_default().to_vec()),
             email_verified_at: user.email_verified_at.map(|value| value.to_string()),
             password_recovered_at: user.password_recovered_at.map(|value| value.to_string()),
             created_at: user.created_at.to_rfc3339(),
             updated_at: user.updated_at.to_rfc3339(),
-            status: user
-                .status
-                .last()
-                .expect("Can not find status of user!")
-                .to_string(),
+            status: PublicUserStatus {
+                status: (*user.latest_status).to_string(),
+                reason: (*user.latest_status_reason).to_string(),
+                changed_at: user.latest_status_at.map(|value| value.to_rfc3339()),
+                actor_id: None,
+                note: None,
+            },
         })
     }
 }
+
+/// A status with its reason, as the current status of a user or as an entry
+/// of its history.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct PublicUserStatus {
+    pub status: String,
+    pub reason: String,
+    pub changed_at: Option<String>,
+    pub actor_id: Option<String>,
+    pub note: Option<String>,
+}
+
+impl From<&UserStatusChange> for PublicUserStatus {
+    fn from(change: &UserStatusChange) -> Self {
+        Self {
+            status: (*change.status).to_string(),
+            reason: (*change.reason).to_string(),
+            changed_at: Some(change.changed_at.to_rfc3339()),
+            actor_id: change.actor_id.map(|id| id.to_string()),
+            note: change.note.clone(),
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct PublicUserStatusPage {
+    pub changes: Vec<PublicUserStatus>,
+    pub next_page_token: Option<String>,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct PublicUserPage {
+    pub users: Vec<PublicUser>,
+    pub next_page_token: Option<String>,
+}
+
+fn ids_to_strings(ids: Option<&[Timeuuid]>) -> Vec<String> {
+    ids.unwrap_or_default()
+        .iter()
+        .map(|id| id.to_string())
+        .collect()
+}
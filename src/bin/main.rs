@@ -1,96 +1,42 @@
-use identification::application::topic::app::UserApp;
-use identification::infrastructure::persistence::IDRepositories;
-use identification::interfaces::actions::IdentificationModuleServices;
-use identification::interfaces::user_handler::{on_create_new_user, on_find_user, UserHandler};
+use identification::application::access::{app::AccessApp, permission::RolePermissions};
+use identification::application::auth::{
+    app::AuthApp, cipher::SecretCipher, lockout::LockoutPolicy, policy::PasswordPolicy,
+    token::TokenConfig,
+};
+use identification::application::delegation::app::DelegationApp;
+use identification::application::organization::{
+    app::OrganizationApp, invitation::InvitationPolicy,
+};
+use identification::application::topic::{app::UserApp, cursor::PageTokenSigner};
+use identification::infrastructure::{mail::LogMailSender, persistence::IDRepositories};
+use identification::interfaces::auth_handler::AuthHandler;
+use identification::interfaces::delegation_handler::DelegationHandler;
+use identification::interfaces::grpc::{
+    auth_service::AuthGrpcService,
+    delegation_service::DelegationGrpcService,
+    identity::v1::{
+        auth_service_server::AuthServiceServer, delegation_service_server::DelegationServiceServer,
+        organization_service_server::OrganizationServiceServer,
+        user_service_server::UserServiceServer,
+    },
+    interceptor::AuthInterceptor,
+    message::message_server::MessageServer,
+    message_service::MessageService,
+    organization_service::OrganizationGrpcService,
+    user_service::UserGrpcService,
+    FILE_DESCRIPTOR_SET,
+};
+use identification::interfaces::organization_handler::OrganizationHandler;
+use identification::interfaces::purge_job::{run_purge_job, PURGE_INTERVAL};
+use identification::interfaces::user_handler::UserHandler;
 use scylla::CachingSession;
 use std::sync::Arc;
 use tokio::sync::Mutex;
-use tonic::{transport::Server, Request, Response, Status};
+use tonic::transport::Server;
 use uptop_core::common::result::AppResult;
 use uptop_core::common::trace::tracing_init;
 use uptop_core::infrastructure::cassandra::{create_db_session, create_keyspace};
 
-mod message {
-    tonic::include_proto!("message");
-}
-
-use message::message_server::{Message, MessageServer};
-use message::{MessageRequest, MessageResponse};
-
-struct MessageService {
-    repositories: Arc<IDRepositories>,
-}
-
-impl MessageService {
-    fn new(repos: IDRepositories) -> Self {
-        Self {
-            repositories: Arc::new(repos),
-        }
-    }
-}
-
-#[tonic::async_trait]
-impl Message for MessageService {
-    async fn send_message(
-        &self,
-        request: Request<MessageRequest>,
-    ) -> Result<Response<MessageResponse>, Status> {
-        // Extract the inner message from the request
-        let payload = request.into_inner();
-        let command = payload.id;
-        let message = payload.message;
-
-        let mut status_code = "OK".to_string();
-        let mut response: MessageResponse = MessageResponse {
-            id: "Internal Server Error".to_owned(),
-            message: "Please try again!".to_owned(),
-        };
-
-        let user_app = UserApp::new(Arc::new(self.repositories.user.clone()));
-        let user_handler = UserHandler {
-            user_app: Arc::new(user_app),
-        };
-
-        match IdentificationModuleServices::action(&command) {
-            Some(IdentificationModuleServices::CreateUser) => {
-                let message = match on_create_new_user(user_handler, message).await {
-                    Ok(res) => res,
-                    Err(err) => {
-                        status_code = "ERROR".to_string();
-                        err.to_string()
-                    }
-                };
-                response = MessageResponse {
-                    id: status_code,
-                    message,
-                };
-            }
-            Some(IdentificationModuleServices::GetUser) => {
-                let message = match on_find_user(user_handler, message).await {
-                    Ok(res) => res,
-                    Err(err) => {
-                        status_code = "ERROR".to_string();
-                        err.to_string()
-                    }
-                };
-                response = MessageResponse {
-                    id: status_code,
-                    message,
-                };
-            }
-            Some(IdentificationModuleServices::GetUsers) => {
-                todo!()
-            }
-            Some(IdentificationModuleServices::UpdateUser) => {
-                todo!()
-            }
-            _none => (),
-        }
-
-        Ok(Response::new(response))
-    }
-}
-
 #[tokio::main]
 async fn main() -> AppResult<()> {
     dotenv::dotenv().ok();
@@ -101,21 +47,112 @@ async fn main() -> AppResult<()> {
     let cache_session = CachingSession::from(cassandra, 1);
     let repos = IDRepositories::new(Arc::new(Mutex::new(cache_session)));
     repos.auto_mod_identification_migrate().await?;
+    let password_policy = PasswordPolicy::from_env()?;
+    let role_permissions = Arc::new(RolePermissions::from_env()?);
+    let token_config = TokenConfig::from_env()?;
+    let interceptor = AuthInterceptor::new(token_config.verifier()?);
 
-    pub(crate) const FILE_MESSAGE_DESCRIPTOR_SET: &[u8] =
-        tonic::include_file_descriptor_set!("message_descriptor");
     let reflect_sv = tonic_reflection::server::Builder::configure()
-        .register_encoded_file_descriptor_set(FILE_MESSAGE_DESCRIPTOR_SET)
+        .register_encoded_file_descriptor_set(FILE_DESCRIPTOR_SET)
         .build_v1()
         .unwrap();
 
+    let user_app = Arc::new(UserApp::new(
+        Arc::new(repos.user.clone()),
+        Arc::new(repos.refresh_token.clone()),
+        Arc::new(repos.mfa.clone()),
+        Arc::new(repos.login_attempt.clone()),
+        Arc::new(LogMailSender),
+        Arc::new(repos.organization.clone()),
+        Arc::new(repos.invitation.clone()),
+        role_permissions.clone(),
+        password_policy.clone(),
+        PageTokenSigner::from_env()?,
+    ));
+    tokio::spawn(run_purge_job(user_app.clone(), PURGE_INTERVAL));
+    let access_app = AccessApp::new(
+        Arc::new(repos.user.clone()),
+        Arc::new(repos.organization.clone()),
+        role_permissions,
+    );
+    let access_app = Arc::new(access_app);
+    let user_handler = UserHandler {
+        user_app,
+        access_app: access_app.clone(),
+    };
+    let user_service = UserGrpcService::new(user_handler);
+    let msg_service = MessageService::new(user_service.clone());
+
+    let auth_app = AuthApp::new(
+        Arc::new(repos.user.clone()),
+        Arc::new(repos.refresh_token.clone()),
+        Arc::new(repos.mfa.clone()),
+        Arc::new(repos.login_attempt.clone()),
+        Arc::new(LogMailSender),
+        SecretCipher::from_env()?,
+        LockoutPolicy::from_env()?,
+        password_policy.clone(),
+        token_config.issuer()?,
+        token_config.verifier()?,
+        token_config.refresh_token_ttl,
+    );
+    let auth_handler = AuthHandler {
+        auth_app: Arc::new(auth_app),
+        access_app: access_app.clone(),
+    };
+    let auth_service = AuthGrpcService::new(auth_handler);
+
+    let organization_app = OrganizationApp::new(
+        Arc::new(repos.user.clone()),
+        Arc::new(repos.organization.clone()),
+        Arc::new(repos.invitation.clone()),
+        Arc::new(LogMailSender),
+        InvitationPolicy::from_env()?,
+        PageTokenSigner::from_env()?,
+    );
+    let organization_handler = OrganizationHandler {
+        organization_app: Arc::new(organization_app),
+        access_app: access_app.clone(),
+    };
+    let organization_service = OrganizationGrpcService::new(organization_handler);
+
+    let delegation_app = DelegationApp::new(
+        Arc::new(repos.user.clone()),
+        Arc::new(repos.delegation.clone()),
+        Arc::new(repos.refresh_token.clone()),
+        password_policy,
+        PageTokenSigner::from_env()?,
+    );
+    let delegation_handler = DelegationHandler {
+        delegation_app: Arc::new(delegation_app),
+        access_app,
+    };
+    let delegation_service = DelegationGrpcService::new(delegation_handler);
+
     let server_addr = "0.0.0.0:3000".parse().unwrap();
     tracing::info!(message = "Starting server on", %server_addr);
-    let msg_service = MessageService::new(repos);
 
     Server::builder()
         .add_service(reflect_sv)
-        .add_service(MessageServer::new(msg_service))
+        .add_service(UserServiceServer::with_interceptor(
+            user_service,
+            interceptor.clone(),
+        ))
+        // Login and refresh send no access token, so they pass the
+        // interceptor anonymously even once the old one has expired.
+        .add_service(AuthServiceServer::with_interceptor(
+            auth_service,
+            interceptor.clone(),
+        ))
+        .add_service(OrganizationServiceServer::with_interceptor(
+            organization_service,
+            interceptor.clone(),
+        ))
+        .add_service(DelegationServiceServer::with_interceptor(
+            delegation_service,
+            interceptor.clone(),
+        ))
+        .add_service(MessageServer::with_interceptor(msg_service, interceptor))
         .serve(server_addr)
         .await
         .unwrap();
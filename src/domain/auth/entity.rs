@@ -0,0 +1,138 @@
+use charybdis::{
+    macros::charybdis_model,
+    types::{BigInt, Int, List, Text, Timestamp, Timeuuid},
+};
+use serde::{Deserialize, Serialize};
+use std::str::FromStr;
+
+/// A refresh token. Only the hash of its secret is stored; every refresh rotates
+/// the token inside the same `family_id`, so a replayed (already rotated) token
+/// reveals the whole family as compromised.
+#[derive(Default, Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[charybdis_model(
+    table_name = uptop.refresh_tokens,
+    partition_keys = [user_id],
+    clustering_keys = [token_id],
+    global_secondary_indexes = [],
+    table_options = r#"
+        CLUSTERING ORDER BY (token_id DESC);
+    "#
+)]
+pub struct RefreshToken {
+    pub user_id: Timeuuid,
+    pub token_id: Timeuuid,
+    pub family_id: Timeuuid,
+    pub token_hash: Text,
+    pub country: Text,
+    pub region: Text,
+    pub city: Text,
+    pub rotated_at: Option<Timestamp>,
+    pub created_at: Timestamp,
+    pub expires_at: Timestamp,
+}
+
+impl RefreshToken {
+    /// The opaque value handed to clients: `<user_id>.<token_id>.<secret>`.
+    pub fn to_client_value(&self, secret: &str) -> String {
+        join_client_value(self.user_id, self.token_id, secret)
+    }
+
+    /// Splits a client value into `(user_id, token_id, secret)`.
+    pub fn parse_client_value(value: &str) -> Option<(Timeuuid, Timeuuid, &str)> {
+        split_client_value(value)
+    }
+}
+
+/// A user's TOTP second factor. The shared secret is stored encrypted; it only
+/// counts as enrolled once `confirmed_at` is set by a first valid code.
+#[derive(Default, Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[charybdis_model(
+    table_name = uptop.mfa_factors,
+    partition_keys = [user_id],
+    clustering_keys = [],
+    global_secondary_indexes = []
+)]
+pub struct MfaFactor {
+    pub user_id: Timeuuid,
+    pub secret_ciphertext: Text,
+    /// Hashes of the unused backup codes.
+    pub backup_code_hashes: Option<List<Text>>,
+    /// Last accepted TOTP time step, so a code can not be replayed.
+    pub last_used_step: Option<BigInt>,
+    pub confirmed_at: Option<Timestamp>,
+    pub created_at: Timestamp,
+    pub updated_at: Timestamp,
+}
+
+impl MfaFactor {
+    pub fn is_confirmed(&self) -> bool {
+        self.confirmed_at.is_some()
+    }
+}
+
+/// Issued by a password login for an account with a second factor. Like a
+/// refresh token only the hash of its secret is stored, and it can be redeemed
+/// once.
+#[derive(Default, Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[charybdis_model(
+    table_name = uptop.mfa_challenges,
+    partition_keys = [user_id],
+    clustering_keys = [challenge_id],
+    global_secondary_indexes = []
+)]
+pub struct MfaChallenge {
+    pub user_id: Timeuuid,
+    pub challenge_id: Timeuuid,
+    pub challenge_hash: Text,
+    pub country: Text,
+    pub region: Text,
+    pub city: Text,
+    pub created_at: Timestamp,
+    pub expires_at: Timestamp,
+}
+
+impl MfaChallenge {
+    /// The opaque value handed to clients: `<user_id>.<challenge_id>.<secret>`.
+    pub fn to_client_value(&self, secret: &str) -> String {
+        join_client_value(self.user_id, self.challenge_id, secret)
+    }
+
+    /// Splits a client value into `(user_id, challenge_id, secret)`.
+    pub fn parse_client_value(value: &str) -> Option<(Timeuuid, Timeuuid, &str)> {
+        split_client_value(value)
+    }
+}
+
+pub(crate) fn join_client_value(user_id: Timeuuid, row_id: Timeuuid, secret: &str) -> String {
+    format!("{user_id}.{row_id}.{secret}")
+}
+
+pub(crate) fn split_client_value(value: &str) -> Option<(Timeuuid, Timeuuid, &str)> {
+    let mut parts = value.splitn(3, '.');
+    let user_id = Timeuuid::from_str(parts.next()?).ok()?;
+    let row_id = Timeuuid::from_str(parts.next()?).ok()?;
+    let secret = parts.next().filter(|secret| !secret.is_empty())?;
+    Some((user_id, row_id, secret))
+}
+
+/// Failed logins of one subject: a user id or a client IP, told apart by
+/// `scope`. Rows are written with a TTL so counters reset on their own.
+#[derive(Default, Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[charybdis_model(
+    table_name = uptop.login_attempts,
+    partition_keys = [scope, subject],
+    clustering_keys = [],
+    global_secondary_indexes = []
+)]
+pub struct LoginAttempt {
+    pub scope: Text,
+    pub subject: Text,
+    pub failures: Int,
+    pub last_failed_at: Timestamp,
+    pub locked_until: Option<Timestamp>,
+}
+
+impl LoginAttempt {
+    pub const USER_SCOPE: &'static str = "user";
+    pub const IP_SCOPE: &'static str = "ip";
+}
@@ -0,0 +1,108 @@
+use super::entity::{LoginAttempt, MfaChallenge, MfaFactor, RefreshToken};
+use charybdis::types::Timeuuid;
+use std::future::Future;
+use uptop_core::common::result::AppResult;
+
+pub trait RefreshTokenRepository: Clone + Send + Sync + 'static {
+    /// Stores `token`; the row expires on its own at `token.expires_at`.
+    fn create_refresh_token(
+        &self,
+        token: &RefreshToken,
+    ) -> impl Future<Output = AppResult<()>> + Send;
+
+    fn find_refresh_token(
+        &self,
+        user_id: Timeuuid,
+        token_id: Timeuuid,
+    ) -> impl Future<Output = AppResult<Option<RefreshToken>>> + Send;
+
+    /// Marks `token` as rotated. Returns `false` when it was already rotated or
+    /// no longer exists, i.e. it is being reused.
+    fn mark_refresh_token_rotated(
+        &self,
+        token: &RefreshToken,
+    ) -> impl Future<Output = AppResult<bool>> + Send;
+
+    fn revoke_refresh_token_family(
+        &self,
+        user_id: Timeuuid,
+        family_id: Timeuuid,
+    ) -> impl Future<Output = AppResult<()>> + Send;
+
+    /// Revokes every family of `user_id` except `keep_family_id`.
+    fn revoke_other_refresh_token_families(
+        &self,
+        user_id: Timeuuid,
+        keep_family_id: Timeuuid,
+    ) -> impl Future<Output = AppResult<()>> + Send;
+
+    fn revoke_refresh_tokens(
+        &self,
+        user_id: Timeuuid,
+    ) -> impl Future<Output = AppResult<()>> + Send;
+}
+
+pub trait MfaRepository: Clone + Send + Sync + 'static {
+    /// Inserts or replaces the factor of `factor.user_id`.
+    fn save_mfa_factor(&self, factor: &MfaFactor) -> impl Future<Output = AppResult<()>> + Send;
+
+    fn find_mfa_factor(
+        &self,
+        user_id: Timeuuid,
+    ) -> impl Future<Output = AppResult<Option<MfaFactor>>> + Send;
+
+    /// Deletes the factor of `user_id` with its secret and backup codes.
+    fn delete_mfa_factor(&self, user_id: Timeuuid) -> impl Future<Output = AppResult<()>> + Send;
+
+    /// Records `step` as the last accepted TOTP step. Returns `false` when
+    /// another request used a step since `factor` was read.
+    fn mark_totp_step_used(
+        &self,
+        factor: &MfaFactor,
+        step: i64,
+    ) -> impl Future<Output = AppResult<bool>> + Send;
+
+    /// Replaces the backup code hashes. Returns `false` when they changed since
+    /// `factor` was read, i.e. a code is being used twice.
+    fn replace_backup_codes(
+        &self,
+        factor: &MfaFactor,
+        backup_code_hashes: Vec<String>,
+    ) -> impl Future<Output = AppResult<bool>> + Send;
+
+    /// Stores `challenge`; the row expires on its own at `challenge.expires_at`.
+    fn create_mfa_challenge(
+        &self,
+        challenge: &MfaChallenge,
+    ) -> impl Future<Output = AppResult<()>> + Send;
+
+    /// Deletes and returns a challenge. Only one caller ever gets it back.
+    fn take_mfa_challenge(
+        &self,
+        user_id: Timeuuid,
+        challenge_id: Timeuuid,
+    ) -> impl Future<Output = AppResult<Option<MfaChallenge>>> + Send;
+}
+
+pub trait LoginAttemptRepository: Clone + Send + Sync + 'static {
+    fn find_login_attempt(
+        &self,
+        scope: &str,
+        subject: &str,
+    ) -> impl Future<Output = AppResult<Option<LoginAttempt>>> + Send;
+
+    /// Stores `attempt` for `ttl_seconds` in place of `previous`. Returns
+    /// `false` when the stored failures changed since `previous` was read.
+    fn save_login_attempt(
+        &self,
+        previous: Option<&LoginAttempt>,
+        attempt: &LoginAttempt,
+        ttl_seconds: i32,
+    ) -> impl Future<Output = AppResult<bool>> + Send;
+
+    fn clear_login_attempt(
+        &self,
+        scope: &str,
+        subject: &str,
+    ) -> impl Future<Output = AppResult<()>> + Send;
+}
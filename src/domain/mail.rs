@@ -0,0 +1,15 @@
+use std::future::Future;
+use uptop_core::common::result::AppResult;
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Mail {
+    pub to: String,
+    pub subject: String,
+    pub body: String,
+}
+
+/// Outgoing mail transport. The service only depends on this trait so the
+/// delivery backend can be swapped without touching the flows that send mail.
+pub trait MailSender: Clone + Send + Sync + 'static {
+    fn send(&self, mail: Mail) -> impl Future<Output = AppResult<()>> + Send;
+}
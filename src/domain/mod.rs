@@ -1 +1,5 @@
+pub mod auth;
+pub mod mail;
+pub mod organization;
 pub mod topic;
+pub use topic as user;
@@ -0,0 +1,126 @@
+use crate::domain::auth::entity::{join_client_value, split_client_value};
+use anyhow::anyhow;
+use charybdis::{
+    macros::charybdis_model,
+    types::{Int, Text, Timestamp, Timeuuid},
+};
+use serde::{Deserialize, Serialize};
+use std::fmt::{Display, Formatter};
+use thiserror::Error;
+use uptop_core::common::result::AppResult;
+
+/// An organization users belong to. Its members live in
+/// `organization_members` and are mirrored on each member's
+/// `User::organizations`.
+#[derive(Default, Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[charybdis_model(
+    table_name = uptop.organizations,
+    partition_keys = [organization_id],
+    clustering_keys = [],
+    global_secondary_indexes = []
+)]
+pub struct Organization {
+    pub organization_id: Timeuuid,
+    pub name: Text,
+    pub description: Option<Text>,
+    /// The member with the `Owner` role, who can not be removed.
+    pub owner_id: Timeuuid,
+    pub created_at: Timestamp,
+    pub updated_at: Timestamp,
+}
+
+/// One member of an organization and its role there.
+#[derive(Default, Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[charybdis_model(
+    table_name = uptop.organization_members,
+    partition_keys = [organization_id],
+    clustering_keys = [user_id],
+    global_secondary_indexes = []
+)]
+pub struct OrganizationMember {
+    pub organization_id: Timeuuid,
+    pub user_id: Timeuuid,
+    pub role: Text,
+    pub joined_at: Timestamp,
+}
+
+/// An invitation mailed to `email` to join as `role`. Like a refresh token only
+/// the hash of its secret is stored, the row expires at `expires_at` and it can
+/// be accepted once.
+#[derive(Default, Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[charybdis_model(
+    table_name = uptop.organization_invitations,
+    partition_keys = [organization_id],
+    clustering_keys = [invitation_id],
+    global_secondary_indexes = []
+)]
+pub struct OrganizationInvitation {
+    pub organization_id: Timeuuid,
+    pub invitation_id: Timeuuid,
+    pub email: Text,
+    pub role: Text,
+    pub invited_by: Timeuuid,
+    pub token_hash: Text,
+    pub created_at: Timestamp,
+    pub expires_at: Timestamp,
+}
+
+impl OrganizationInvitation {
+    /// The opaque value mailed to the invitee:
+    /// `<organization_id>.<invitation_id>.<secret>`.
+    pub fn to_client_value(&self, secret: &str) -> String {
+        join_client_value(self.organization_id, self.invitation_id, secret)
+    }
+
+    /// Splits a client value into `(organization_id, invitation_id, secret)`.
+    pub fn parse_client_value(value: &str) -> Option<(Timeuuid, Timeuuid, &str)> {
+        split_client_value(value)
+    }
+}
+
+/// Invitations one user sent in the current window. Rows are written with a
+/// TTL so the count resets on its own, like `LoginAttempt`.
+#[derive(Default, Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[charybdis_model(
+    table_name = uptop.invitation_quotas,
+    partition_keys = [inviter_id],
+    clustering_keys = [],
+    global_secondary_indexes = []
+)]
+pub struct InvitationQuota {
+    pub inviter_id: Timeuuid,
+    pub sent: Int,
+    pub window_started_at: Timestamp,
+}
+
+#[derive(Debug, Error)]
+pub enum OrganizationEntityError {
+    #[error("Organization role {role} not found!")]
+    RoleNotFound { role: String },
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub enum OrganizationRole {
+    Owner,
+    Admin,
+    Member,
+}
+
+impl OrganizationRole {
+    pub fn parse(input: Option<&str>) -> AppResult<OrganizationRole> {
+        match input {
+            Some("Owner") => Ok(OrganizationRole::Owner),
+            Some("Admin") => Ok(OrganizationRole::Admin),
+            Some("Member") | None => Ok(OrganizationRole::Member),
+            Some(val) => Err(anyhow!(OrganizationEntityError::RoleNotFound {
+                role: val.to_owned()
+            })),
+        }
+    }
+}
+
+impl Display for OrganizationRole {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{:?}", self)
+    }
+}
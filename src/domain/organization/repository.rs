@@ -0,0 +1,122 @@
+use super::entity::{InvitationQuota, Organization, OrganizationInvitation, OrganizationMember};
+use crate::domain::user::entity::User;
+use charybdis::types::Timeuuid;
+use std::future::Future;
+use uptop_core::common::result::AppResult;
+
+/// One page of an organization's members, like `UserPage`.
+#[derive(Clone, Debug, Default)]
+pub struct MemberPage {
+    pub members: Vec<OrganizationMember>,
+    pub paging_state: Option<Vec<u8>>,
+}
+
+/// Organizations and their members. Every membership change also updates
+/// `User::organizations` of the member, in the same logged batch.
+pub trait OrganizationRepository: Clone + Send + Sync + 'static {
+    /// Inserts `organization` with `owner` as its first member.
+    fn create_organization(
+        &self,
+        organization: &Organization,
+        owner: &User,
+    ) -> impl Future<Output = AppResult<()>> + Send;
+
+    fn find_organization(
+        &self,
+        organization_id: Timeuuid,
+    ) -> impl Future<Output = AppResult<Option<Organization>>> + Send;
+
+    fn update_organization(
+        &self,
+        organization: &Organization,
+    ) -> impl Future<Output = AppResult<()>> + Send;
+
+    /// Deletes the organization and its member rows. Members must have been
+    /// removed from their users first, see [`Self::remove_member`].
+    fn delete_organization(
+        &self,
+        organization_id: Timeuuid,
+    ) -> impl Future<Output = AppResult<()>> + Send;
+
+    fn find_member(
+        &self,
+        organization_id: Timeuuid,
+        user_id: Timeuuid,
+    ) -> impl Future<Output = AppResult<Option<OrganizationMember>>> + Send;
+
+    fn find_members(
+        &self,
+        organization_id: Timeuuid,
+        page_size: i32,
+        paging_state: Option<Vec<u8>>,
+    ) -> impl Future<Output = AppResult<MemberPage>> + Send;
+
+    /// Inserts or replaces `member` and adds the organization to `user`.
+    fn add_member(
+        &self,
+        member: &OrganizationMember,
+        user: &User,
+    ) -> impl Future<Output = AppResult<()>> + Send;
+
+    /// Deletes the member row and takes the organization off `user`, also as
+    /// its active organization.
+    fn remove_member(
+        &self,
+        organization_id: Timeuuid,
+        user: &User,
+    ) -> impl Future<Output = AppResult<()>> + Send;
+
+    fn set_active_organization(
+        &self,
+        user: &User,
+        organization_id: Option<Timeuuid>,
+    ) -> impl Future<Output = AppResult<()>> + Send;
+}
+
+pub trait InvitationRepository: Clone + Send + Sync + 'static {
+    /// Stores `invitation`; the row expires on its own at
+    /// `invitation.expires_at`.
+    fn create_invitation(
+        &self,
+        invitation: &OrganizationInvitation,
+    ) -> impl Future<Output = AppResult<()>> + Send;
+
+    fn find_invitation(
+        &self,
+        organization_id: Timeuuid,
+        invitation_id: Timeuuid,
+    ) -> impl Future<Output = AppResult<Option<OrganizationInvitation>>> + Send;
+
+    /// The pending invitations of an organization.
+    fn find_invitations(
+        &self,
+        organization_id: Timeuuid,
+    ) -> impl Future<Output = AppResult<Vec<OrganizationInvitation>>> + Send;
+
+    /// Deletes and returns an invitation. Only one caller ever gets it back.
+    fn take_invitation(
+        &self,
+        organization_id: Timeuuid,
+        invitation_id: Timeuuid,
+    ) -> impl Future<Output = AppResult<Option<OrganizationInvitation>>> + Send;
+
+    /// Deletes every invitation of an organization.
+    fn delete_invitations(
+        &self,
+        organization_id: Timeuuid,
+    ) -> impl Future<Output = AppResult<()>> + Send;
+
+    fn find_invitation_quota(
+        &self,
+        inviter_id: Timeuuid,
+    ) -> impl Future<Output = AppResult<Option<InvitationQuota>>> + Send;
+
+    /// Stores `quota` for `ttl_seconds` in place of `previous`. Returns
+    /// `false` when the stored quota changed since `previous` was read.
+    fn save_invitation_quota(
+        &self,
+        previous: Option<&InvitationQuota>,
+        quota: &InvitationQuota,
+        ttl_seconds: i32,
+    ) -> impl Future<Output = AppResult<bool>> + Send;
+}
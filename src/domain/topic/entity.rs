@@ -1,3 +1,4 @@
+use super::identifier::canonical_email;
 use crate::application::topic::request::{RequestCreateUser, RequestUpdateUser};
 use anyhow::anyhow;
 use charybdis::{
@@ -7,6 +8,7 @@ use charybdis::{
 use chrono::Utc;
 use serde::{Deserialize, Serialize};
 use std::fmt::{Display, Formatter};
+use thiserror::Error;
 use uptop_core::common::{result::AppResult, utils::now_timeuuid};
 
 #[derive(Default, Clone, Debug, PartialEq, Serialize, Deserialize)]
@@ -14,7 +16,7 @@ use uptop_core::common::{result::AppResult, utils::now_timeuuid};
     table_name = uptop.users,
     partition_keys = [country, region, city],
     clustering_keys = [user_id],
-    global_secondary_indexes = [user_id, user_name, email],
+    global_secondary_indexes = [user_id],
     table_options = r#"
         CLUSTERING ORDER BY (user_id DESC);
     "#
@@ -25,7 +27,10 @@ pub struct User {
     pub display_name: Option<Text>,
     pub email: Text,
     pub password: Text,
-    pub status: List<Text>,
+    /// Current status, also the newest entry of the status history.
+    pub latest_status: Text,
+    pub latest_status_reason: Text,
+    pub latest_status_at: Option<Timestamp>,
     pub role: Text,
     pub phone_number: Option<Text>,
     pub language: Option<Text>,
@@ -40,13 +45,283 @@ pub struct User {
     pub active_organization: Option<Timeuuid>,
     pub other_emails: Option<List<Text>>,
     pub email_verify_code: Option<Text>,
+    pub email_verify_code_expires_at: Option<Timestamp>,
     pub email_verified_at: Option<Timestamp>,
     pub password_recovery_code: Option<Text>,
+    pub password_recovery_code_expires_at: Option<Timestamp>,
     pub password_recovered_at: Option<Timestamp>,
+    pub deleted_at: Option<Timestamp>,
+    pub purged_at: Option<Timestamp>,
     pub created_at: Timestamp,
     pub updated_at: Timestamp,
 }
 
+/// A column of [`User`] that a flow writes on its own. The status columns
+/// are only written by the guarded status change, and `owners`, `admins`
+/// and `organizations` only by collection updates, so they are not listed.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum UserColumn {
+    DisplayName,
+    Email,
+    Password,
+    Role,
+    PhoneNumber,
+    Language,
+    Address,
+    OtherEmails,
+    EmailVerifyCode,
+    EmailVerifyCodeExpiresAt,
+    EmailVerifiedAt,
+    PasswordRecoveryCode,
+    PasswordRecoveryCodeExpiresAt,
+    PasswordRecoveredAt,
+    DeletedAt,
+    PurgedAt,
+    UpdatedAt,
+}
+
+impl UserColumn {
+    pub fn name(&self) -> &'static str {
+        match self {
+            UserColumn::DisplayName => "display_name",
+            UserColumn::Email => "email",
+            UserColumn::Password => "password",
+            UserColumn::Role => "role",
+            UserColumn::PhoneNumber => "phone_number",
+            UserColumn::Language => "language",
+            UserColumn::Address => "address",
+            UserColumn::OtherEmails => "other_emails",
+            UserColumn::EmailVerifyCode => "email_verify_code",
+            UserColumn::EmailVerifyCodeExpiresAt => "email_verify_code_expires_at",
+            UserColumn::EmailVerifiedAt => "email_verified_at",
+            UserColumn::PasswordRecoveryCode => "password_recovery_code",
+            UserColumn::PasswordRecoveryCodeExpiresAt => "password_recovery_code_expires_at",
+            UserColumn::PasswordRecoveredAt => "password_recovered_at",
+            UserColumn::DeletedAt => "deleted_at",
+            UserColumn::PurgedAt => "purged_at",
+            UserColumn::UpdatedAt => "updated_at",
+        }
+    }
+}
+
+/// Claims an email, in canonical form, for one user. Rows are inserted with
+/// `IF NOT EXISTS`, which is what makes emails unique, and point at the
+/// owner's primary key.
+#[derive(Default, Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[charybdis_model(
+    table_name = uptop.users_by_email,
+    partition_keys = [email],
+    clustering_keys = [],
+    global_secondary_indexes = []
+)]
+pub struct UserByEmail {
+    pub email: Text,
+    pub user_id: Timeuuid,
+    pub country: Text,
+    pub region: Text,
+    pub city: Text,
+}
+
+/// Claims a user name for one user, like [`UserByEmail`].
+#[derive(Default, Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[charybdis_model(
+    table_name = uptop.users_by_user_name,
+    partition_keys = [user_name],
+    clustering_keys = [],
+    global_secondary_indexes = []
+)]
+pub struct UserByUserName {
+    pub user_name: Text,
+    pub user_id: Timeuuid,
+    pub country: Text,
+    pub region: Text,
+    pub city: Text,
+}
+
+/// Where a user is stored, so a `user_id` alone is enough to load it.
+#[derive(Default, Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[charybdis_model(
+    table_name = uptop.users_by_id,
+    partition_keys = [user_id],
+    clustering_keys = [],
+    global_secondary_indexes = []
+)]
+pub struct UserById {
+    pub user_id: Timeuuid,
+    pub country: Text,
+    pub region: Text,
+    pub city: Text,
+}
+
+impl From<&User> for UserById {
+    fn from(user: &User) -> Self {
+        Self {
+            user_id: user.user_id,
+            country: (*user.country).to_string(),
+            region: (*user.region).to_string(),
+            city: (*user.city).to_string(),
+        }
+    }
+}
+
+/// A soft deleted user waiting to be purged. Removed again on restore and
+/// once the purge ran.
+#[derive(Default, Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[charybdis_model(
+    table_name = uptop.user_deletions,
+    partition_keys = [user_id],
+    clustering_keys = [],
+    global_secondary_indexes = []
+)]
+pub struct UserDeletion {
+    pub user_id: Timeuuid,
+    pub country: Text,
+    pub region: Text,
+    pub city: Text,
+    pub deleted_at: Timestamp,
+    pub purge_after: Timestamp,
+}
+
+/// One entry of a user's status history, newest first. Keyed by `user_id`
+/// alone so the history survives moves between locations.
+#[derive(Default, Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[charybdis_model(
+    table_name = uptop.user_status_history,
+    partition_keys = [user_id],
+    clustering_keys = [change_id],
+    global_secondary_indexes = [],
+    table_options = r#"
+        CLUSTERING ORDER BY (change_id DESC);
+    "#
+)]
+pub struct UserStatusChange {
+    pub user_id: Timeuuid,
+    pub change_id: Timeuuid,
+    pub status: Text,
+    pub reason: Text,
+    /// User who made the change; `None` for changes made by the system.
+    pub actor_id: Option<Timeuuid>,
+    pub note: Option<Text>,
+    pub changed_at: Timestamp,
+}
+
+/// A user `manager_id` manages as one of its `owners` or `admins`. The
+/// reverse of those lists, so a manager can find its accounts; the lists on
+/// the managed user are what grants access.
+#[derive(Default, Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[charybdis_model(
+    table_name = uptop.managed_users,
+    partition_keys = [manager_id],
+    clustering_keys = [user_id],
+    global_secondary_indexes = []
+)]
+pub struct ManagedUser {
+    pub manager_id: Timeuuid,
+    pub user_id: Timeuuid,
+    pub role: Text,
+    pub granted_by: Timeuuid,
+    pub granted_at: Timestamp,
+}
+
+/// One entry of the delegation audit of a managed user, newest first:
+/// managers granted or revoked and what they did to the account.
+#[derive(Default, Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[charybdis_model(
+    table_name = uptop.delegation_audit,
+    partition_keys = [user_id],
+    clustering_keys = [entry_id],
+    global_secondary_indexes = [],
+    table_options = r#"
+        CLUSTERING ORDER BY (entry_id DESC);
+    "#
+)]
+pub struct DelegationAuditEntry {
+    pub user_id: Timeuuid,
+    pub entry_id: Timeuuid,
+    pub actor_id: Timeuuid,
+    pub action: Text,
+    /// The manager granted or revoked, and its role.
+    pub manager_id: Option<Timeuuid>,
+    pub role: Option<Text>,
+    pub note: Option<Text>,
+    pub created_at: Timestamp,
+}
+
+impl DelegationAuditEntry {
+    pub fn new(user_id: Timeuuid, actor_id: Timeuuid, action: DelegationAction) -> Self {
+        Self {
+            user_id,
+            entry_id: now_timeuuid(),
+            actor_id,
+            action: action.as_str().to_owned(),
+            manager_id: None,
+            role: None,
+            note: None,
+            created_at: Utc::now(),
+        }
+    }
+}
+
+impl UserStatusChange {
+    pub fn new(
+        user_id: Timeuuid,
+        status: &UserStatus,
+        actor_id: Option<Timeuuid>,
+        note: Option<String>,
+    ) -> Self {
+        Self {
+            user_id,
+            change_id: now_timeuuid(),
+            status: status.name().to_owned(),
+            reason: ReasonOfStatus::transform(status.reason()),
+            actor_id,
+            note,
+            changed_at: Utc::now(),
+        }
+    }
+
+    pub fn user_status(&self) -> AppResult<UserStatus> {
+        UserStatus::from_parts(&self.status, &self.reason)
+    }
+}
+
+#[derive(Debug, Error)]
+pub enum UserEntityError {
+    #[error("User status {status} not found!")]
+    Status { status: String },
+    #[error("User reason of status {reason} not found!")]
+    Reason { reason: String },
+    #[error("User role {role} not found!")]
+    Role { role: String },
+    #[error("Delegation role {role} not found!")]
+    DelegationRole { role: String },
+}
+
+/// Why a status change was refused.
+#[derive(Debug, Error)]
+pub enum UserStatusError {
+    #[error("{reason} is not a reason for the {status} status")]
+    InvalidReason { status: String, reason: String },
+    #[error("Can not change status from {from} to {to}")]
+    IllegalTransition { from: String, to: String },
+    #[error("Not allowed to change status to {status}")]
+    NotPermitted { status: String },
+    #[error("Status was changed by someone else, try again")]
+    Changed,
+}
+
+/// Who asks for a status change. `System` covers the service's own flows
+/// (sign in, verification, deletion) and calls that do not name an actor.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum StatusChangedBy {
+    System,
+    Admin,
+    /// The user, changing their own status.
+    Owner,
+    /// One of the `owners` or `admins` of the user.
+    Delegate,
+}
+
 // Define enum of status for user
 #[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
 pub enum UserStatus {
@@ -68,31 +343,150 @@ impl UserStatus {
                     }
                     ["Deleted", reason] => Ok(UserStatus::Deleted(ReasonOfStatus::parse(reason)?)),
                     ["Disable", reason] => Ok(UserStatus::Disable(ReasonOfStatus::parse(reason)?)),
-                    _ => Err(anyhow!("User status not found!")),
+                    _ => Err(anyhow!(UserEntityError::Status {
+                        status: val.to_owned()
+                    })),
                 }
             }
             None => Ok(UserStatus::Inactive(ReasonOfStatus::FirstTimeAccess)),
         }
     }
 
+    pub fn from_parts(status: &str, reason: &str) -> AppResult<UserStatus> {
+        UserStatus::parse(Some(&format!("{status}:{reason}")))
+    }
+
+    /// Parses a legacy `Status:Reason:<timeuuid>` list entry into its status
+    /// and the time of the change.
+    pub fn parse_legacy(input: &str) -> AppResult<(UserStatus, Option<Timeuuid>)> {
+        let mut parts = input.splitn(3, ':');
+        let status = parts.next().unwrap_or_default();
+        let reason = parts.next().unwrap_or_default();
+        let change_id = parts.next().and_then(|id| id.parse::<Timeuuid>().ok());
+        Ok((UserStatus::from_parts(status, reason)?, change_id))
+    }
+
+    /// The `Status:Reason` form accepted by [`UserStatus::parse`].
     pub fn transform(status: &UserStatus) -> String {
-        let status_str = match status {
-            UserStatus::Active(reason) => {
-                format!("Active:{}", ReasonOfStatus::transform(reason))
-            }
+        format!(
+            "{}:{}",
+            status.name(),
+            ReasonOfStatus::transform(status.reason())
+        )
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            UserStatus::Active(_) => "Active",
+            UserStatus::Inactive(_) => "Inactive",
+            UserStatus::Disable(_) => "Disable",
+            UserStatus::Deleted(_) => "Deleted",
+        }
+    }
+
+    pub fn reason(&self) -> &ReasonOfStatus {
+        match self {
+            UserStatus::Active(reason)
+            | UserStatus::Inactive(reason)
+            | UserStatus::Disable(reason)
+            | UserStatus::Deleted(reason) => reason,
+        }
+    }
+
+    /// Rejects reasons that make no sense for the status, e.g. `Active:Spammer`.
+    pub fn check_reason(&self) -> Result<(), UserStatusError> {
+        use ReasonOfStatus::*;
+
+        let valid = match self {
+            UserStatus::Active(reason) => matches!(
+                reason,
+                ComeBackAccess | LoginAgain | AfterRegister | Restored
+            ),
             UserStatus::Inactive(reason) => {
-                format!("Inactive:{}", ReasonOfStatus::transform(reason))
+                matches!(reason, FirstTimeAccess | Logout | LicenseExpired)
             }
             UserStatus::Disable(reason) => {
-                format!("Disable:{}", ReasonOfStatus::transform(reason))
+                reason.is_moderation()
+                    || matches!(
+                        reason,
+                        LicenseExpired | TooManyFailedLogins | ManagerRequested
+                    )
             }
             UserStatus::Deleted(reason) => {
-                format!("Deleted:{}", ReasonOfStatus::transform(reason))
+                reason.is_moderation() || matches!(reason, UserRequested | Erased)
             }
         };
 
-        let timeuuid = now_timeuuid();
-        format!("{}:{}", status_str, timeuuid)
+        match valid {
+            true => Ok(()),
+            false => Err(UserStatusError::InvalidReason {
+                status: self.name().to_owned(),
+                reason: ReasonOfStatus::transform(self.reason()),
+            }),
+        }
+    }
+
+    /// Whether `by` may move a user from this status to `next`:
+    /// - a deleted user can only be restored or erased, and an erased one
+    ///   never changes again;
+    /// - `Active:Restored` is only reached from `Deleted` and
+    ///   `Inactive:FirstTimeAccess` only on sign up;
+    /// - a disabled user comes back through `Active:ComeBackAccess` only;
+    /// - only admins give or lift a moderation reason, e.g. `Disable:Scammer`;
+    /// - users may only sign themselves out or delete themselves, and not
+    ///   while disabled;
+    /// - delegates may only disable a user with `Disable:ManagerRequested`
+    ///   and lift that again.
+    pub fn check_transition(
+        &self,
+        next: &UserStatus,
+        by: StatusChangedBy,
+    ) -> Result<(), UserStatusError> {
+        use ReasonOfStatus::*;
+        use UserStatus::*;
+
+        next.check_reason()?;
+
+        let legal = match (self, next) {
+            (Deleted(Erased), _) => false,
+            (Deleted(_), Active(Restored) | Deleted(Erased)) => true,
+            (Deleted(_), _) => false,
+            (_, Active(Restored) | Inactive(FirstTimeAccess)) => false,
+            (Disable(_), Active(ComeBackAccess)) => true,
+            (Disable(_), Active(_) | Inactive(_)) => false,
+            _ => true,
+        };
+        if !legal {
+            return Err(UserStatusError::IllegalTransition {
+                from: UserStatus::transform(self),
+                to: UserStatus::transform(next),
+            });
+        }
+
+        let moderated = next.reason().is_moderation()
+            || matches!(self, Disable(reason) | Deleted(reason) if reason.is_moderation());
+        let permitted = match by {
+            StatusChangedBy::Admin => true,
+            StatusChangedBy::System => !moderated || matches!(next, Deleted(Erased)),
+            StatusChangedBy::Owner => {
+                !matches!(self, Disable(_))
+                    && matches!(next, Inactive(Logout) | Deleted(UserRequested))
+            }
+            StatusChangedBy::Delegate => {
+                !moderated
+                    && matches!(
+                        (self, next),
+                        (_, Disable(ManagerRequested))
+                            | (Disable(ManagerRequested), Active(ComeBackAccess))
+                    )
+            }
+        };
+        match permitted {
+            true => Ok(()),
+            false => Err(UserStatusError::NotPermitted {
+                status: UserStatus::transform(next),
+            }),
+        }
     }
 }
 
@@ -114,6 +508,11 @@ pub enum ReasonOfStatus {
     Scammer,
     ViolatePolicy,
     MultipleAccounts,
+    TooManyFailedLogins,
+    UserRequested,
+    Restored,
+    Erased,
+    ManagerRequested,
 }
 
 impl ReasonOfStatus {
@@ -129,7 +528,14 @@ impl ReasonOfStatus {
             "Scammer" => Ok(ReasonOfStatus::Scammer),
             "ViolatePolicy" => Ok(ReasonOfStatus::ViolatePolicy),
             "MultipleAccounts" => Ok(ReasonOfStatus::MultipleAccounts),
-            _ => Err(anyhow!("User reason of status not found!")),
+            "TooManyFailedLogins" => Ok(ReasonOfStatus::TooManyFailedLogins),
+            "UserRequested" => Ok(ReasonOfStatus::UserRequested),
+            "Restored" => Ok(ReasonOfStatus::Restored),
+            "Erased" => Ok(ReasonOfStatus::Erased),
+            "ManagerRequested" => Ok(ReasonOfStatus::ManagerRequested),
+            _ => Err(anyhow!(UserEntityError::Reason {
+                reason: input.to_owned()
+            })),
         }
     }
 
@@ -145,8 +551,24 @@ impl ReasonOfStatus {
             ReasonOfStatus::Scammer => "Scammer".to_owned(),
             ReasonOfStatus::ViolatePolicy => "ViolatePolicy".to_owned(),
             ReasonOfStatus::MultipleAccounts => "MultipleAccounts".to_owned(),
+            ReasonOfStatus::TooManyFailedLogins => "TooManyFailedLogins".to_owned(),
+            ReasonOfStatus::UserRequested => "UserRequested".to_owned(),
+            ReasonOfStatus::Restored => "Restored".to_owned(),
+            ReasonOfStatus::Erased => "Erased".to_owned(),
+            ReasonOfStatus::ManagerRequested => "ManagerRequested".to_owned(),
         }
     }
+
+    /// Reasons given when moderating a user, never by the user themselves.
+    pub fn is_moderation(&self) -> bool {
+        matches!(
+            self,
+            ReasonOfStatus::Spammer
+                | ReasonOfStatus::Scammer
+                | ReasonOfStatus::ViolatePolicy
+                | ReasonOfStatus::MultipleAccounts
+        )
+    }
 }
 
 impl Display for ReasonOfStatus {
@@ -171,7 +593,9 @@ impl UserRole {
                 "Member" => Ok(UserRole::Member.to_string()),
                 "Manager" => Ok(UserRole::Manager.to_string()),
                 "Admin" => Ok(UserRole::Admin.to_string()),
-                _ => Err(anyhow!("User role not found!")),
+                _ => Err(anyhow!(UserEntityError::Role {
+                    role: val.to_owned()
+                })),
             },
             None => Ok("Guest".to_owned()),
         }
@@ -184,6 +608,53 @@ impl Display for UserRole {
     }
 }
 
+/// How a user manages another one: listed in its `owners` or `admins`.
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub enum DelegationRole {
+    Owner,
+    Admin,
+}
+
+impl DelegationRole {
+    pub fn parse(input: Option<&str>) -> AppResult<DelegationRole> {
+        match input {
+            Some("Owner") => Ok(DelegationRole::Owner),
+            Some("Admin") | None => Ok(DelegationRole::Admin),
+            Some(val) => Err(anyhow!(UserEntityError::DelegationRole {
+                role: val.to_owned()
+            })),
+        }
+    }
+}
+
+impl Display for DelegationRole {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{:?}", self)
+    }
+}
+
+/// What a [`DelegationAuditEntry`] records.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum DelegationAction {
+    Grant,
+    Revoke,
+    ResetPassword,
+    Disable,
+    Enable,
+}
+
+impl DelegationAction {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            DelegationAction::Grant => "Grant",
+            DelegationAction::Revoke => "Revoke",
+            DelegationAction::ResetPassword => "ResetPassword",
+            DelegationAction::Disable => "Disable",
+            DelegationAction::Enable => "Enable",
+        }
+    }
+}
+
 impl TryFrom<RequestCreateUser> for User {
     type Error = anyhow::Error;
 
@@ -194,7 +665,7 @@ impl TryFrom<RequestCreateUser> for User {
             updated_at: Utc::now(),
             ..Default::default()
         };
-        // user.company_id = Some(value.company_id); // TODO: convert Vec string to Vec Timeuuid
+        // `company_id` memberships are added once the user is stored, see `UserApp::create_user`.
         user.user_name = value.user_name;
         user.email = value.email;
         user.password = value.password;
@@ -207,17 +678,339 @@ impl TryFrom<RequestCreateUser> for User {
         user.region = value.region;
         user.city = value.city;
         user.post_code = value.post_code;
-        user.email_verify_code = value.email_verify_code;
-        user.status.push(value.status.unwrap());
+        user.set_status(
+            &UserStatus::parse(value.status.as_deref())?,
+            user.created_at,
+        );
 
         Ok(user)
     }
 }
 
-impl TryFrom<RequestUpdateUser> for User {
-    type Error = anyhow::Error;
+impl User {
+    /// The latest entry of the status history.
+    pub fn current_status(&self) -> AppResult<UserStatus> {
+        UserStatus::from_parts(&self.latest_status, &self.latest_status_reason)
+    }
+
+    /// Mirrors a status change, already recorded in the history, on the row.
+    pub fn set_status(&mut self, status: &UserStatus, changed_at: Timestamp) {
+        self.latest_status = status.name().to_owned();
+        self.latest_status_reason = ReasonOfStatus::transform(status.reason());
+        self.latest_status_at = Some(changed_at);
+    }
+
+    pub fn is_deleted(&self) -> bool {
+        matches!(self.current_status(), Ok(UserStatus::Deleted(_)))
+    }
+
+    /// Whether `manager_id` is listed in `owners` or `admins`, owners first.
+    pub fn delegation_role(&self, manager_id: Timeuuid) -> Option<DelegationRole> {
+        let listed =
+            |ids: &Option<List<Timeuuid>>| ids.as_deref().unwrap_or_default().contains(&manager_id);
+        if listed(&self.owners) {
+            Some(DelegationRole::Owner)
+        } else if listed(&self.admins) {
+            Some(DelegationRole::Admin)
+        } else {
+            None
+        }
+    }
+
+    /// Mirrors a delegation change, already stored, on the lists: lists
+    /// `manager_id` under `role` only, or nowhere for `None`.
+    pub fn set_delegation(&mut self, manager_id: Timeuuid, role: Option<DelegationRole>) {
+        for ids in [&mut self.owners, &mut self.admins].into_iter().flatten() {
+            ids.retain(|id| *id != manager_id);
+        }
+        let ids = match role {
+            Some(DelegationRole::Owner) => &mut self.owners,
+            Some(DelegationRole::Admin) => &mut self.admins,
+            None => return,
+        };
+        ids.get_or_insert_with(Vec::new).push(manager_id);
+    }
+
+    /// Clears every column holding personal data, leaving a tombstone with
+    /// the id, user name, location, status history and timestamps. Returns
+    /// the columns it changed.
+    pub fn scrub_personal_data(&mut self) -> [UserColumn; 12] {
+        self.email = String::new();
+        self.password = String::new();
+        self.display_name = None;
+        self.phone_number = None;
+        self.address = None;
+        self.other_emails = None;
+        self.email_verify_code = None;
+        self.email_verify_code_expires_at = None;
+        self.password_recovery_code = None;
+        self.password_recovery_code_expires_at = None;
+        self.purged_at = Some(Utc::now());
+        self.updated_at = Utc::now();
+
+        [
+            UserColumn::Email,
+            UserColumn::Password,
+            UserColumn::DisplayName,
+            UserColumn::PhoneNumber,
+            UserColumn::Address,
+            UserColumn::OtherEmails,
+            UserColumn::EmailVerifyCode,
+            UserColumn::EmailVerifyCodeExpiresAt,
+            UserColumn::PasswordRecoveryCode,
+            UserColumn::PasswordRecoveryCodeExpiresAt,
+            UserColumn::PurgedAt,
+            UserColumn::UpdatedAt,
+        ]
+    }
+
+    /// Applies the fields present in `changes` and bumps `updated_at`; absent
+    /// fields keep their stored values. Returns the columns to write.
+    pub fn apply_update(&mut self, changes: RequestUpdateUser) -> Vec<UserColumn> {
+        let mut columns = vec![];
+        if let Some(email) = changes.email {
+            // Another address has to be verified again.
+            if canonical_email(&email) != canonical_email(&self.email) {
+                self.email_verified_at = None;
+                columns.push(UserColumn::EmailVerifiedAt);
+            }
+            self.email = email;
+            columns.push(UserColumn::Email);
+        }
+        if let Some(role) = changes.role {
+            self.role = role;
+            columns.push(UserColumn::Role);
+        }
+        if let Some(display_name) = changes.display_name {
+            self.display_name = Some(display_name);
+            columns.push(UserColumn::DisplayName);
+        }
+        if let Some(phone_number) = changes.phone_number {
+            self.phone_number = Some(phone_number);
+            columns.push(UserColumn::PhoneNumber);
+        }
+        if let Some(language) = changes.language {
+            self.language = Some(language);
+            columns.push(UserColumn::Language);
+        }
+        if let Some(address) = changes.address {
+            self.address = Some(address);
+            columns.push(UserColumn::Address);
+        }
+        if let Some(other_emails) = changes.other_emails {
+            self.other_emails = Some(other_emails);
+            columns.push(UserColumn::OtherEmails);
+        }
+        self.updated_at = Utc::now();
+        columns.push(UserColumn::UpdatedAt);
+        columns
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn status(input: &str) -> UserStatus {
+        UserStatus::parse(Some(input)).unwrap()
+    }
+
+    fn transition(from: &str, to: &str, by: StatusChangedBy) -> Result<(), UserStatusError> {
+        status(from).check_transition(&status(to), by)
+    }
+
+    #[test]
+    fn check_reason_accepts_only_reasons_of_the_status() {
+        assert!(status("Active:LoginAgain").check_reason().is_ok());
+        assert!(status("Disable:Scammer").check_reason().is_ok());
+        assert!(status("Deleted:Erased").check_reason().is_ok());
+        assert!(matches!(
+            status("Active:Spammer").check_reason(),
+            Err(UserStatusError::InvalidReason { .. })
+        ));
+        assert!(matches!(
+            status("Inactive:Restored").check_reason(),
+            Err(UserStatusError::InvalidReason { .. })
+        ));
+    }
+
+    #[test]
+    fn check_transition_checks_the_reason_first() {
+        assert!(matches!(
+            transition(
+                "Active:LoginAgain",
+                "Active:Spammer",
+                StatusChangedBy::Admin
+            ),
+            Err(UserStatusError::InvalidReason { .. })
+        ));
+    }
+
+    #[test]
+    fn deleted_users_are_only_restored_or_erased() {
+        use StatusChangedBy::*;
+
+        assert!(transition("Deleted:UserRequested", "Active:Restored", Admin).is_ok());
+        assert!(transition("Deleted:Spammer", "Deleted:Erased", System).is_ok());
+        for (from, to) in [
+            ("Deleted:UserRequested", "Active:LoginAgain"),
+            ("Deleted:Erased", "Active:Restored"),
+            ("Active:LoginAgain", "Active:Restored"),
+            ("Active:LoginAgain", "Inactive:FirstTimeAccess"),
+        ] {
+            assert!(
+                matches!(
+                    transition(from, to, Admin),
+                    Err(UserStatusError::IllegalTransition { .. })
+                ),
+                "{from} -> {to}"
+            );
+        }
+    }
+
+    #[test]
+    fn disabled_users_come_back_through_come_back_access() {
+        use StatusChangedBy::*;
+
+        assert!(transition("Disable:LicenseExpired", "Active:ComeBackAccess", System).is_ok());
+        assert!(matches!(
+            transition("Disable:TooManyFailedLogins", "Active:LoginAgain", System),
+            Err(UserStatusError::IllegalTransition { .. })
+        ));
+    }
+
+    #[test]
+    fn only_admins_give_or_lift_moderation_reasons() {
+        use StatusChangedBy::*;
+
+        assert!(transition("Active:LoginAgain", "Disable:Spammer", Admin).is_ok());
+        assert!(transition("Disable:Spammer", "Active:ComeBackAccess", Admin).is_ok());
+        for (from, to, by) in [
+            ("Active:LoginAgain", "Disable:Spammer", System),
+            ("Disable:Spammer", "Active:ComeBackAccess", System),
+            ("Disable:Spammer", "Active:ComeBackAccess", Delegate),
+        ] {
+            assert!(
+                matches!(
+                    transition(from, to, by),
+                    Err(UserStatusError::NotPermitted { .. })
+                ),
+                "{from} -> {to} by {by:?}"
+            );
+        }
+    }
+
+    #[test]
+    fn owners_sign_out_or_delete_themselves_unless_disabled() {
+        use StatusChangedBy::*;
+
+        assert!(transition("Active:LoginAgain", "Inactive:Logout", Owner).is_ok());
+        assert!(transition("Active:LoginAgain", "Deleted:UserRequested", Owner).is_ok());
+        for (from, to) in [
+            ("Disable:LicenseExpired", "Deleted:UserRequested"),
+            ("Active:LoginAgain", "Disable:ManagerRequested"),
+        ] {
+            assert!(
+                matches!(
+                    transition(from, to, Owner),
+                    Err(UserStatusError::NotPermitted { .. })
+                ),
+                "{from} -> {to}"
+            );
+        }
+    }
+
+    #[test]
+    fn delegates_only_disable_and_enable_for_the_manager() {
+        use StatusChangedBy::*;
+
+        assert!(transition("Active:LoginAgain", "Disable:ManagerRequested", Delegate).is_ok());
+        assert!(transition(
+            "Disable:ManagerRequested",
+            "Active:ComeBackAccess",
+            Delegate
+        )
+        .is_ok());
+        for (from, to) in [
+            ("Disable:LicenseExpired", "Active:ComeBackAccess"),
+            ("Active:LoginAgain", "Deleted:UserRequested"),
+        ] {
+            assert!(
+                matches!(
+                    transition(from, to, Delegate),
+                    Err(UserStatusError::NotPermitted { .. })
+                ),
+                "{from} -> {to}"
+            );
+        }
+    }
+
+    #[test]
+    fn set_delegation_lists_a_manager_under_one_role_only() {
+        let manager_id = Timeuuid::now_v1(&[1; 6]);
+        let mut user = User::default();
+
+        user.set_delegation(manager_id, Some(DelegationRole::Admin));
+        assert_eq!(
+            user.delegation_role(manager_id),
+            Some(DelegationRole::Admin)
+        );
+
+        user.set_delegation(manager_id, Some(DelegationRole::Owner));
+        assert_eq!(
+            user.delegation_role(manager_id),
+            Some(DelegationRole::Owner)
+        );
+        assert_eq!(user.admins.as_deref(), Some(&[][..]));
+
+        user.set_delegation(manager_id, None);
+        assert_eq!(user.delegation_role(manager_id), None);
+    }
+
+    #[test]
+    fn owners_win_over_admins_when_listed_twice() {
+        let manager_id = Timeuuid::now_v1(&[1; 6]);
+        let user = User {
+            owners: Some(vec![manager_id]),
+            admins: Some(vec![manager_id]),
+            ..Default::default()
+        };
+
+        assert_eq!(
+            user.delegation_role(manager_id),
+            Some(DelegationRole::Owner)
+        );
+    }
+
+    #[test]
+    fn apply_update_unverifies_only_another_email() {
+        let verified = User {
+            email: "Jane.Doe@example.com".to_owned(),
+            email_verified_at: Some(Utc::now()),
+            ..Default::default()
+        };
+        let update = |email: &str| RequestUpdateUser {
+            country: String::new(),
+            region: String::new(),
+            city: String::new(),
+            user_id: String::new(),
+            email: Some(email.to_owned()),
+            role: None,
+            display_name: None,
+            phone_number: None,
+            language: None,
+            address: None,
+            other_emails: None,
+        };
+
+        let mut user = verified.clone();
+        let columns = user.apply_update(update("jane.doe@EXAMPLE.com"));
+        assert!(user.email_verified_at.is_some());
+        assert!(!columns.contains(&UserColumn::EmailVerifiedAt));
 
-    fn try_from(_value: RequestUpdateUser) -> AppResult<Self> {
-        todo!()
+        let mut user = verified.clone();
+        let columns = user.apply_update(update("john@example.com"));
+        assert_eq!(user.email_verified_at, None);
+        assert!(columns.contains(&UserColumn::EmailVerifiedAt));
     }
 }
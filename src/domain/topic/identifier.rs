@@ -0,0 +1,157 @@
+use std::borrow::Cow;
+use unicode_normalization::UnicodeNormalization;
+use unicode_security::{skeleton, GeneralSecurityProfile, MixedScript};
+use validator::{ValidationError, ValidationErrors};
+
+/// Punctuation allowed in user names besides letters and digits.
+const USER_NAME_PUNCTUATION: &[char] = &['.', '-', '_'];
+
+/// Names nobody can register because they look like they belong to the
+/// service. Compared by canonical form, so `Adm1n` is taken too.
+const RESERVED_USER_NAMES: &[&str] = &[
+    "abuse",
+    "admin",
+    "administrator",
+    "api",
+    "billing",
+    "help",
+    "hostmaster",
+    "info",
+    "login",
+    "logout",
+    "mail",
+    "me",
+    "moderator",
+    "no-reply",
+    "noreply",
+    "null",
+    "postmaster",
+    "root",
+    "security",
+    "settings",
+    "signup",
+    "staff",
+    "support",
+    "system",
+    "undefined",
+    "uptop",
+    "webmaster",
+    "www",
+];
+
+/// The form user names and emails are shown in: NFKC normalized and trimmed,
+/// case preserved.
+pub fn display_form(value: &str) -> String {
+    value.trim().nfkc().collect()
+}
+
+/// The form emails are unique in: NFKC normalized and lowercased, local part
+/// and domain alike.
+pub fn canonical_email(email: &str) -> String {
+    display_form(email).to_lowercase()
+}
+
+/// The form user names are unique in: the UTS #39 skeleton of the lowercased
+/// NFKC form, so names that only differ by case or by look-alike characters
+/// (`paypal`, `PayPaI`, Cyrillic `раураl`) collide.
+pub fn canonical_user_name(user_name: &str) -> String {
+    let lowered = display_form(user_name).to_lowercase();
+    skeleton(&lowered).collect::<String>().to_lowercase()
+}
+
+pub fn is_reserved_user_name(user_name: &str) -> bool {
+    let canonical = canonical_user_name(user_name);
+    RESERVED_USER_NAMES
+        .iter()
+        .any(|reserved| canonical_user_name(reserved) == canonical)
+}
+
+/// Checks the display form of a new user name. Violations are reported under
+/// `field`, one entry per broken rule.
+pub fn check_user_name(field: &'static str, user_name: &str) -> Result<(), ValidationErrors> {
+    let mut errors = ValidationErrors::new();
+    let mut violation = |code: &'static str, message: &'static str| {
+        errors.add(
+            field,
+            ValidationError::new(code).with_message(Cow::Borrowed(message)),
+        );
+    };
+
+    let allowed = user_name.chars().all(|c| {
+        USER_NAME_PUNCTUATION.contains(&c) || (c.is_alphanumeric() && c.identifier_allowed())
+    });
+    if !allowed {
+        violation(
+            "user_name_invalid_characters",
+            "May only contain letters, digits, '.', '-' and '_'",
+        );
+    }
+    if !user_name.is_single_script() {
+        violation(
+            "user_name_mixed_script",
+            "Must not mix letters from different scripts",
+        );
+    }
+    if is_reserved_user_name(user_name) {
+        violation("user_name_reserved", "Is reserved");
+    }
+
+    match errors.is_empty() {
+        true => Ok(()),
+        false => Err(errors),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn violations(user_name: &str) -> Vec<String> {
+        match check_user_name("user_name", user_name) {
+            Ok(()) => vec![],
+            Err(errors) => errors.field_errors()["user_name"]
+                .iter()
+                .map(|error| error.code.to_string())
+                .collect(),
+        }
+    }
+
+    #[test]
+    fn display_form_trims_and_normalizes_but_keeps_case() {
+        assert_eq!(display_form("  Ａlice "), "Alice");
+    }
+
+    #[test]
+    fn canonical_email_folds_case_and_compatibility_forms() {
+        assert_eq!(canonical_email(" Alice@Example.COM "), "alice@example.com");
+        assert_eq!(
+            canonical_email("ａｌｉｃｅ@ｅｘａｍｐｌｅ.com"),
+            "alice@example.com"
+        );
+    }
+
+    #[test]
+    fn canonical_user_name_folds_case_compatibility_forms_and_look_alikes() {
+        let canonical = canonical_user_name("paypal");
+
+        assert_eq!(canonical_user_name("PayPal"), canonical);
+        assert_eq!(canonical_user_name("ｐａｙｐａｌ"), canonical);
+        assert_eq!(canonical_user_name("раураl"), canonical);
+        assert_ne!(canonical_user_name("paypals"), canonical);
+    }
+
+    #[test]
+    fn reserved_user_names_ignore_case() {
+        assert!(is_reserved_user_name("Admin"));
+        assert!(is_reserved_user_name("ＲＯＯＴ"));
+        assert!(!is_reserved_user_name("alice"));
+    }
+
+    #[test]
+    fn check_user_name_reports_every_broken_rule() {
+        assert!(violations("alice.nguyen_01").is_empty());
+        assert_eq!(violations("alice!"), ["user_name_invalid_characters"]);
+        assert_eq!(violations("pаypal"), ["user_name_mixed_script"]);
+        assert_eq!(violations("Support"), ["user_name_reserved"]);
+    }
+}
@@ -1,2 +1,3 @@
 pub(crate) mod entity;
+pub mod identifier;
 pub mod repository;
@@ -1,14 +1,47 @@
-use super::entity::User;
+use super::entity::{
+    DelegationAuditEntry, ManagedUser, User, UserColumn, UserDeletion, UserStatus, UserStatusChange,
+};
 use crate::application::topic::request::{
     RequestGetUser, RequestGetUserByPartitionKey, RequestGetUserByPrimaryKey,
     RequestUpdateUserStatus,
 };
+use charybdis::types::{Timestamp, Timeuuid};
 use std::future::Future;
 use uptop_core::common::result::AppResult;
 
+/// One page of a status history, like [`UserPage`].
+#[derive(Clone, Debug, Default)]
+pub struct StatusHistoryPage {
+    pub changes: Vec<UserStatusChange>,
+    pub paging_state: Option<Vec<u8>>,
+}
+
+/// One page of a delegation audit, like [`UserPage`].
+#[derive(Clone, Debug, Default)]
+pub struct DelegationAuditPage {
+    pub entries: Vec<DelegationAuditEntry>,
+    pub paging_state: Option<Vec<u8>>,
+}
+
+/// One page of a partition scan. `paging_state` is the raw Scylla paging state
+/// to resume from, or `None` once the partition is exhausted.
+#[derive(Clone, Debug, Default)]
+pub struct UserPage {
+    pub users: Vec<User>,
+    pub paging_state: Option<Vec<u8>>,
+}
+
 pub trait UserRepository: Clone + Send + Sync + 'static {
+    /// Claims the user name and email, then inserts `user`. Fails with
+    /// `UserNameExisted` or `EmailExisted` when either is taken.
     fn create_user<'c>(&self, user: &'c User) -> impl Future<Output = AppResult<&'c User>> + Send;
 
+    /// Undoes [`Self::create_user`]: deletes `user`, its id mapping and status
+    /// history and gives up its user name and email.
+    fn remove_user(&self, user: &User) -> impl Future<Output = AppResult<()>> + Send;
+
+    /// Loads a user by primary key. When the location is missing or stale it
+    /// is resolved from `user_id`.
     fn find_user_by_id(
         &self,
         query: &RequestGetUserByPrimaryKey,
@@ -19,12 +52,111 @@ pub trait UserRepository: Clone + Send + Sync + 'static {
     fn find_users(
         &self,
         query: &RequestGetUserByPartitionKey,
-    ) -> impl Future<Output = AppResult<Vec<User>>> + Send;
+        page_size: i32,
+        paging_state: Option<Vec<u8>>,
+    ) -> impl Future<Output = AppResult<UserPage>> + Send;
 
+    /// Records a status change in the history and as the current status, if
+    /// the current status still is `from`. Returns `false` when the user does
+    /// not exist or its status changed in the meantime.
     fn push_new_user_status(
         &self,
         payload: &RequestUpdateUserStatus,
+        from: &UserStatus,
     ) -> impl Future<Output = AppResult<bool>> + Send;
 
-    fn update_user<'u>(&self, user: &'u User) -> impl Future<Output = AppResult<&'u User>> + Send;
+    /// Writes only `columns` of `user`, so concurrent flows writing other
+    /// columns do not undo each other.
+    fn update_user_columns<'u>(
+        &self,
+        user: &'u User,
+        columns: &[UserColumn],
+    ) -> impl Future<Output = AppResult<&'u User>> + Send;
+
+    /// Replaces `from` with `to`, which has the same `user_id` in another
+    /// partition, in one logged batch. The lookup tables are repointed first,
+    /// each only while it still names the user.
+    fn move_user<'u>(
+        &self,
+        from: &User,
+        to: &'u User,
+    ) -> impl Future<Output = AppResult<&'u User>> + Send;
+
+    /// Claims `user.email` for `user`. Fails with `EmailExisted` when another
+    /// user holds it; claiming an email the user already holds is a no-op.
+    fn claim_email(&self, user: &User) -> impl Future<Output = AppResult<()>> + Send;
+
+    /// Gives up `email` if `user_id` holds it.
+    fn release_email(
+        &self,
+        email: &str,
+        user_id: Timeuuid,
+    ) -> impl Future<Output = AppResult<()>> + Send;
+
+    /// Queues a soft deleted user for purging.
+    fn schedule_purge(&self, deletion: &UserDeletion)
+        -> impl Future<Output = AppResult<()>> + Send;
+
+    fn cancel_purge(&self, user_id: Timeuuid) -> impl Future<Output = AppResult<()>> + Send;
+
+    /// Queued users whose `purge_after` is not later than `now`.
+    fn find_due_purges(
+        &self,
+        now: Timestamp,
+    ) -> impl Future<Output = AppResult<Vec<UserDeletion>>> + Send;
+
+    /// Clears the notes on the status history of `user`, frees its email and
+    /// user name, writes `columns` of `tombstone`, its scrubbed form, and
+    /// dequeues it.
+    fn purge_user<'u>(
+        &self,
+        user: &User,
+        tombstone: &'u User,
+        columns: &[UserColumn],
+    ) -> impl Future<Output = AppResult<&'u User>> + Send;
+
+    /// The status history of a user, newest first.
+    fn find_status_history(
+        &self,
+        user_id: Timeuuid,
+        page_size: i32,
+        paging_state: Option<Vec<u8>>,
+    ) -> impl Future<Output = AppResult<StatusHistoryPage>> + Send;
+}
+
+/// Who manages whom. Every change updates `User::owners` and `User::admins`
+/// of the managed user and its `managed_users` row in the same logged batch.
+pub trait DelegationRepository: Clone + Send + Sync + 'static {
+    /// Lists `grant.manager_id` in the list of `grant.role` on `user`, moving
+    /// it out of the other list.
+    fn grant_delegation(
+        &self,
+        user: &User,
+        grant: &ManagedUser,
+    ) -> impl Future<Output = AppResult<()>> + Send;
+
+    /// Takes `manager_id` off both lists of `user`.
+    fn revoke_delegation(
+        &self,
+        user: &User,
+        manager_id: Timeuuid,
+    ) -> impl Future<Output = AppResult<()>> + Send;
+
+    fn find_managed_users(
+        &self,
+        manager_id: Timeuuid,
+    ) -> impl Future<Output = AppResult<Vec<ManagedUser>>> + Send;
+
+    fn add_audit_entry(
+        &self,
+        entry: &DelegationAuditEntry,
+    ) -> impl Future<Output = AppResult<()>> + Send;
+
+    /// The delegation audit of a user, newest first.
+    fn find_audit_entries(
+        &self,
+        user_id: Timeuuid,
+        page_size: i32,
+        paging_state: Option<Vec<u8>>,
+    ) -> impl Future<Output = AppResult<DelegationAuditPage>> + Send;
 }
@@ -0,0 +1,14 @@
+use crate::domain::mail::{Mail, MailSender};
+use uptop_core::common::result::AppResult;
+
+/// Writes mails to the log instead of delivering them. Meant for local
+/// development until a real transport is configured.
+#[derive(Clone, Debug, Default)]
+pub struct LogMailSender;
+
+impl MailSender for LogMailSender {
+    async fn send(&self, mail: Mail) -> AppResult<()> {
+        tracing::info!(to = %mail.to, subject = %mail.subject, body = %mail.body, "Outgoing mail");
+        Ok(())
+    }
+}
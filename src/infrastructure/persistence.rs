@@ -1,21 +1,125 @@
+use chrono::Utc;
+use scylla::{CachingSession, QueryResult};
 use uptop_core::common::{db_types::CassandraCacheSession, result::AppResult};
 
+pub(crate) mod delegation_repository;
+pub(crate) mod invitation_repository;
+pub(crate) mod login_attempt_repository;
+pub(crate) mod mfa_repository;
+pub(crate) mod organization_repository;
+pub(crate) mod refresh_token_repository;
 pub(crate) mod user_repository;
 
 #[derive(Debug)]
 pub struct IDRepositories {
     pub user: user_repository::UserRepo,
+    pub refresh_token: refresh_token_repository::RefreshTokenRepo,
+    pub mfa: mfa_repository::MfaRepo,
+    pub login_attempt: login_attempt_repository::LoginAttemptRepo,
+    pub organization: organization_repository::OrganizationRepo,
+    pub invitation: invitation_repository::InvitationRepo,
+    pub delegation: delegation_repository::DelegationRepo,
 }
 
 impl IDRepositories {
     pub fn new(session: CassandraCacheSession) -> Self {
         Self {
-            user: user_repository::UserRepo::new(session),
+            user: user_repository::UserRepo::new(session.clone()),
+            refresh_token: refresh_token_repository::RefreshTokenRepo::new(session.clone()),
+            mfa: mfa_repository::MfaRepo::new(session.clone()),
+            login_attempt: login_attempt_repository::LoginAttemptRepo::new(session.clone()),
+            organization: organization_repository::OrganizationRepo::new(session.clone()),
+            invitation: invitation_repository::InvitationRepo::new(session.clone()),
+            delegation: delegation_repository::DelegationRepo::new(session),
         }
     }
 
     pub async fn auto_mod_identification_migrate(&self) -> AppResult<()> {
         self.user.migrate_user_table().await?;
+        self.refresh_token.migrate_refresh_token_table().await?;
+        self.mfa.migrate_mfa_tables().await?;
+        self.login_attempt.migrate_login_attempt_table().await?;
+        self.organization.migrate_organization_tables().await?;
+        self.invitation.migrate_invitation_tables().await?;
+        self.delegation.migrate_delegation_tables().await?;
         Ok(())
     }
 }
+
+/// Reads the `[applied]` column of a lightweight transaction result.
+pub(crate) fn is_applied(result: QueryResult) -> bool {
+    result
+        .rows
+        .as_deref()
+        .and_then(|rows| rows.first())
+        .and_then(|row| row.columns.first().cloned().flatten())
+        .and_then(|value| value.as_boolean())
+        .unwrap_or(false)
+}
+
+/// Adds `column` to a table created by an older release, since
+/// `CREATE TABLE IF NOT EXISTS` leaves existing tables untouched.
+pub(crate) async fn add_column_if_missing(
+    session: &CachingSession,
+    table: &str,
+    column: &str,
+    cql_type: &str,
+) -> AppResult<()> {
+    if !column_exists(session, table, column).await? {
+        session
+            .execute_unpaged(
+                format!("ALTER TABLE uptop.{table} ADD {column} {cql_type}"),
+                (),
+            )
+            .await?;
+    }
+    Ok(())
+}
+
+/// Whether the data migration `name` ran to completion before.
+pub(crate) async fn migration_applied(session: &CachingSession, name: &str) -> AppResult<bool> {
+    let applied = session
+        .execute_unpaged(FIND_MIGRATION_QUERY, (name,))
+        .await?;
+    Ok(applied.rows_num()? > 0)
+}
+
+/// Records the data migration `name` as done, so later starts skip it.
+pub(crate) async fn record_migration(session: &CachingSession, name: &str) -> AppResult<()> {
+    session
+        .execute_unpaged(INSERT_MIGRATION_QUERY, (name, Utc::now()))
+        .await?;
+    Ok(())
+}
+
+pub(crate) async fn column_exists(
+    session: &CachingSession,
+    table: &str,
+    column: &str,
+) -> AppResult<bool> {
+    let existing = session
+        .execute_unpaged(FIND_COLUMN_QUERY, ("uptop", table, column))
+        .await?;
+    Ok(existing.rows_num()? > 0)
+}
+
+pub(crate) static CREATE_SCHEMA_MIGRATION_TABLE_QUERY: &str = r#"
+    CREATE TABLE IF NOT EXISTS uptop.schema_migrations (
+        name text,
+        applied_at timestamp,
+        PRIMARY KEY (name)
+    );
+"#;
+
+static FIND_MIGRATION_QUERY: &str = r#"
+    SELECT name FROM uptop.schema_migrations WHERE name = ?;
+"#;
+
+static INSERT_MIGRATION_QUERY: &str = r#"
+    INSERT INTO uptop.schema_migrations (name, applied_at) VALUES (?, ?);
+"#;
+
+static FIND_COLUMN_QUERY: &str = r#"
+    SELECT column_name FROM system_schema.columns
+    WHERE keyspace_name = ? AND table_name = ? AND column_name = ?;
+"#;
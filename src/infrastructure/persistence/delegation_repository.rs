@@ -0,0 +1,206 @@
+use crate::domain::user::{
+    entity::{DelegationAuditEntry, DelegationRole, ManagedUser, User},
+    repository::{DelegationAuditPage, DelegationRepository},
+};
+use anyhow::anyhow;
+use charybdis::{
+    model::{BaseModel, Model},
+    operations::Insert,
+    types::Timeuuid,
+};
+use scylla::{
+    batch::{Batch, BatchType},
+    query::Query,
+    serialize::batch::BatchValues,
+    statement::{PagingState, PagingStateResponse},
+};
+use uptop_core::common::{
+    db_types::CassandraCacheSession,
+    result::{AppError, AppResult},
+};
+
+#[derive(Clone, Debug)]
+pub struct DelegationRepo {
+    db: CassandraCacheSession,
+}
+
+impl DelegationRepo {
+    pub fn new(db: CassandraCacheSession) -> Self {
+        Self { db }
+    }
+
+    pub async fn migrate_delegation_tables(&self) -> AppResult<()> {
+        let session = self.db.lock().await;
+        session
+            .execute_unpaged(CREATE_MANAGED_USER_TABLE_QUERY, ())
+            .await?;
+        session
+            .execute_unpaged(CREATE_DELEGATION_AUDIT_TABLE_QUERY, ())
+            .await?;
+        Ok(())
+    }
+
+    async fn run_batch(&self, batch: &Batch, values: impl BatchValues) -> AppResult<()> {
+        let session = self.db.lock().await;
+        match session.batch(batch, values).await {
+            Ok(_) => Ok(()),
+            Err(err) => {
+                tracing::error!("{err:?}");
+                Err(anyhow!(AppError::InternalServerError))
+            }
+        }
+    }
+}
+
+impl DelegationRepository for DelegationRepo {
+    async fn grant_delegation(&self, user: &User, grant: &ManagedUser) -> AppResult<()> {
+        let update_user = match DelegationRole::parse(Some(&grant.role))? {
+            DelegationRole::Owner => GRANT_OWNER_QUERY,
+            DelegationRole::Admin => GRANT_ADMIN_QUERY,
+        };
+
+        let mut batch = Batch::new(BatchType::Logged);
+        batch.append_statement(update_user);
+        batch.append_statement(ManagedUser::INSERT_QUERY);
+
+        self.run_batch(&batch, (user_values(grant.manager_id, user), grant))
+            .await
+    }
+
+    async fn revoke_delegation(&self, user: &User, manager_id: Timeuuid) -> AppResult<()> {
+        let mut batch = Batch::new(BatchType::Logged);
+        batch.append_statement(REVOKE_QUERY);
+        batch.append_statement(ManagedUser::DELETE_QUERY);
+
+        self.run_batch(
+            &batch,
+            (user_values(manager_id, user), (manager_id, user.user_id)),
+        )
+        .await
+    }
+
+    async fn find_managed_users(&self, manager_id: Timeuuid) -> AppResult<Vec<ManagedUser>> {
+        let session = self.db.lock().await;
+        match session
+            .execute_unpaged(ManagedUser::FIND_BY_PARTITION_KEY_QUERY, (manager_id,))
+            .await
+        {
+            Ok(result) => Ok(result
+                .rows_typed::<ManagedUser>()?
+                .collect::<Result<Vec<_>, _>>()?),
+            Err(err) => {
+                tracing::error!("{err:?}");
+                Err(anyhow!(AppError::InternalServerError))
+            }
+        }
+    }
+
+    async fn add_audit_entry(&self, entry: &DelegationAuditEntry) -> AppResult<()> {
+        let session = self.db.lock().await;
+        match entry.insert().execute(&session).await {
+            Ok(_) => Ok(()),
+            Err(err) => {
+                tracing::error!("{err:?}");
+                Err(anyhow!(AppError::InternalServerError))
+            }
+        }
+    }
+
+    async fn find_audit_entries(
+        &self,
+        user_id: Timeuuid,
+        page_size: i32,
+        paging_state: Option<Vec<u8>>,
+    ) -> AppResult<DelegationAuditPage> {
+        let mut statement = Query::new(DelegationAuditEntry::FIND_BY_PARTITION_KEY_QUERY);
+        statement.set_page_size(page_size);
+
+        let paging_state = match paging_state {
+            Some(raw) => PagingState::new_from_raw_bytes(raw),
+            None => PagingState::start(),
+        };
+
+        let session = self.db.lock().await;
+        let result = session
+            .execute_single_page(statement, (user_id,), paging_state)
+            .await;
+
+        match result {
+            Ok((rows, paging_state_response)) => {
+                let entries = rows
+                    .rows_typed::<DelegationAuditEntry>()?
+                    .collect::<Result<Vec<_>, _>>()?;
+                let paging_state = match paging_state_response {
+                    PagingStateResponse::HasMorePages { state } => {
+                        state.as_bytes_slice().map(|bytes| bytes.to_vec())
+                    }
+                    PagingStateResponse::NoMorePages => None,
+                };
+
+                Ok(DelegationAuditPage {
+                    entries,
+                    paging_state,
+                })
+            }
+            Err(err) => {
+                tracing::error!("{err:?}");
+                Err(anyhow!(AppError::InternalServerError))
+            }
+        }
+    }
+}
+
+/// Values of the `owners` and `admins` list updates on `user`'s row.
+fn user_values(
+    manager_id: Timeuuid,
+    user: &User,
+) -> (Vec<Timeuuid>, Vec<Timeuuid>, &str, &str, &str, Timeuuid) {
+    (
+        vec![manager_id],
+        vec![manager_id],
+        &user.country,
+        &user.region,
+        &user.city,
+        user.user_id,
+    )
+}
+
+static CREATE_MANAGED_USER_TABLE_QUERY: &str = r#"
+    CREATE TABLE IF NOT EXISTS uptop.managed_users (
+        manager_id timeuuid,
+        user_id timeuuid,
+        role text,
+        granted_by timeuuid,
+        granted_at timestamp,
+        PRIMARY KEY ((manager_id), user_id)
+    );
+"#;
+
+static CREATE_DELEGATION_AUDIT_TABLE_QUERY: &str = r#"
+    CREATE TABLE IF NOT EXISTS uptop.delegation_audit (
+        user_id timeuuid,
+        entry_id timeuuid,
+        actor_id timeuuid,
+        action text,
+        manager_id timeuuid,
+        role text,
+        note text,
+        created_at timestamp,
+        PRIMARY KEY ((user_id), entry_id)
+    ) WITH CLUSTERING ORDER BY (entry_id DESC);
+"#;
+
+static GRANT_OWNER_QUERY: &str = r#"
+    UPDATE uptop.users SET owners = owners + ?, admins = admins - ?
+    WHERE country = ? AND region = ? AND city = ? AND user_id = ?;
+"#;
+
+static GRANT_ADMIN_QUERY: &str = r#"
+    UPDATE uptop.users SET admins = admins + ?, owners = owners - ?
+    WHERE country = ? AND region = ? AND city = ? AND user_id = ?;
+"#;
+
+static REVOKE_QUERY: &str = r#"
+    UPDATE uptop.users SET owners = owners - ?, admins = admins - ?
+    WHERE country = ? AND region = ? AND city = ? AND user_id = ?;
+"#;
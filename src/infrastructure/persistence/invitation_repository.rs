@@ -0,0 +1,273 @@
+use crate::{
+    domain::organization::{
+        entity::{InvitationQuota, OrganizationInvitation},
+        repository::InvitationRepository,
+    },
+    infrastructure::persistence::is_applied,
+};
+use anyhow::anyhow;
+use charybdis::{model::BaseModel, operations::Find, types::Timeuuid};
+use chrono::Utc;
+use uptop_core::common::{
+    db_types::CassandraCacheSession,
+    result::{AppError, AppResult},
+};
+
+#[derive(Clone, Debug)]
+pub struct InvitationRepo {
+    db: CassandraCacheSession,
+}
+
+impl InvitationRepo {
+    pub fn new(db: CassandraCacheSession) -> Self {
+        Self { db }
+    }
+
+    pub async fn migrate_invitation_tables(&self) -> AppResult<()> {
+        let session = self.db.lock().await;
+        session
+            .execute_unpaged(CREATE_INVITATION_TABLE_QUERY, ())
+            .await?;
+        session
+            .execute_unpaged(CREATE_INVITATION_QUOTA_TABLE_QUERY, ())
+            .await?;
+        Ok(())
+    }
+}
+
+impl InvitationRepository for InvitationRepo {
+    async fn create_invitation(&self, invitation: &OrganizationInvitation) -> AppResult<()> {
+        let session = self.db.lock().await;
+        match session
+            .execute_unpaged(
+                INSERT_INVITATION_QUERY,
+                (
+                    invitation.organization_id,
+                    invitation.invitation_id,
+                    &invitation.email,
+                    &invitation.role,
+                    invitation.invited_by,
+                    &invitation.token_hash,
+                    invitation.created_at,
+                    invitation.expires_at,
+                    remaining_ttl(invitation),
+                ),
+            )
+            .await
+        {
+            Ok(_) => Ok(()),
+            Err(err) => {
+                tracing::error!("{err:?}");
+                Err(anyhow!(AppError::InternalServerError))
+            }
+        }
+    }
+
+    async fn find_invitation(
+        &self,
+        organization_id: Timeuuid,
+        invitation_id: Timeuuid,
+    ) -> AppResult<Option<OrganizationInvitation>> {
+        let session = self.db.lock().await;
+        let result = OrganizationInvitation {
+            organization_id,
+            invitation_id,
+            ..Default::default()
+        }
+        .maybe_find_by_primary_key()
+        .execute(&session)
+        .await;
+
+        match result {
+            Ok(invitation) => Ok(invitation),
+            Err(err) => {
+                tracing::error!("{err:?}");
+                Err(anyhow!(AppError::InternalServerError))
+            }
+        }
+    }
+
+    async fn find_invitations(
+        &self,
+        organization_id: Timeuuid,
+    ) -> AppResult<Vec<OrganizationInvitation>> {
+        let session = self.db.lock().await;
+        match session
+            .execute_unpaged(
+                OrganizationInvitation::FIND_BY_PARTITION_KEY_QUERY,
+                (organization_id,),
+            )
+            .await
+        {
+            Ok(result) => Ok(result
+                .rows_typed::<OrganizationInvitation>()?
+                .collect::<Result<Vec<_>, _>>()?),
+            Err(err) => {
+                tracing::error!("{err:?}");
+                Err(anyhow!(AppError::InternalServerError))
+            }
+        }
+    }
+
+    async fn take_invitation(
+        &self,
+        organization_id: Timeuuid,
+        invitation_id: Timeuuid,
+    ) -> AppResult<Option<OrganizationInvitation>> {
+        let Some(invitation) = self.find_invitation(organization_id, invitation_id).await? else {
+            return Ok(None);
+        };
+
+        let session = self.db.lock().await;
+        match session
+            .execute_unpaged(DELETE_INVITATION_QUERY, (organization_id, invitation_id))
+            .await
+        {
+            Ok(result) => Ok(is_applied(result).then_some(invitation)),
+            Err(err) => {
+                tracing::error!("{err:?}");
+                Err(anyhow!(AppError::InternalServerError))
+            }
+        }
+    }
+
+    async fn delete_invitations(&self, organization_id: Timeuuid) -> AppResult<()> {
+        let session = self.db.lock().await;
+        match session
+            .execute_unpaged(DELETE_INVITATIONS_QUERY, (organization_id,))
+            .await
+        {
+            Ok(_) => Ok(()),
+            Err(err) => {
+                tracing::error!("{err:?}");
+                Err(anyhow!(AppError::InternalServerError))
+            }
+        }
+    }
+
+    async fn find_invitation_quota(
+        &self,
+        inviter_id: Timeuuid,
+    ) -> AppResult<Option<InvitationQuota>> {
+        let session = self.db.lock().await;
+        let result = InvitationQuota {
+            inviter_id,
+            ..Default::default()
+        }
+        .maybe_find_by_primary_key()
+        .execute(&session)
+        .await;
+
+        match result {
+            Ok(quota) => Ok(quota),
+            Err(err) => {
+                tracing::error!("{err:?}");
+                Err(anyhow!(AppError::InternalServerError))
+            }
+        }
+    }
+
+    async fn save_invitation_quota(
+        &self,
+        previous: Option<&InvitationQuota>,
+        quota: &InvitationQuota,
+        ttl_seconds: i32,
+    ) -> AppResult<bool> {
+        let session = self.db.lock().await;
+        let ttl_seconds = ttl_seconds.max(1);
+        let result = match previous {
+            None => {
+                session
+                    .execute_unpaged(
+                        INSERT_INVITATION_QUOTA_QUERY,
+                        (
+                            quota.inviter_id,
+                            quota.sent,
+                            quota.window_started_at,
+                            ttl_seconds,
+                        ),
+                    )
+                    .await
+            }
+            Some(previous) => {
+                session
+                    .execute_unpaged(
+                        UPDATE_INVITATION_QUOTA_QUERY,
+                        (
+                            ttl_seconds,
+                            quota.sent,
+                            quota.window_started_at,
+                            quota.inviter_id,
+                            previous.sent,
+                            previous.window_started_at,
+                        ),
+                    )
+                    .await
+            }
+        };
+
+        match result {
+            Ok(result) => Ok(is_applied(result)),
+            Err(err) => {
+                tracing::error!("{err:?}");
+                Err(anyhow!(AppError::InternalServerError))
+            }
+        }
+    }
+}
+
+/// Seconds until `invitation` expires, as a Scylla TTL (which must be positive).
+fn remaining_ttl(invitation: &OrganizationInvitation) -> i32 {
+    (invitation.expires_at - Utc::now()).num_seconds().max(1) as i32
+}
+
+static CREATE_INVITATION_TABLE_QUERY: &str = r#"
+    CREATE TABLE IF NOT EXISTS uptop.organization_invitations (
+        organization_id timeuuid,
+        invitation_id timeuuid,
+        email text,
+        role text,
+        invited_by timeuuid,
+        token_hash text,
+        created_at timestamp,
+        expires_at timestamp,
+        PRIMARY KEY ((organization_id), invitation_id)
+    );
+"#;
+
+static CREATE_INVITATION_QUOTA_TABLE_QUERY: &str = r#"
+    CREATE TABLE IF NOT EXISTS uptop.invitation_quotas (
+        inviter_id timeuuid,
+        sent int,
+        window_started_at timestamp,
+        PRIMARY KEY (inviter_id)
+    );
+"#;
+
+static INSERT_INVITATION_QUERY: &str = r#"
+    INSERT INTO uptop.organization_invitations (
+        organization_id, invitation_id, email, role, invited_by, token_hash, created_at, expires_at
+    ) VALUES (?, ?, ?, ?, ?, ?, ?, ?) USING TTL ?;
+"#;
+
+static DELETE_INVITATION_QUERY: &str = r#"
+    DELETE FROM uptop.organization_invitations
+    WHERE organization_id = ? AND invitation_id = ?
+    IF EXISTS;
+"#;
+
+static DELETE_INVITATIONS_QUERY: &str = r#"
+    DELETE FROM uptop.organization_invitations WHERE organization_id = ?;
+"#;
+
+static INSERT_INVITATION_QUOTA_QUERY: &str = r#"
+    INSERT INTO uptop.invitation_quotas (inviter_id, sent, window_started_at)
+    VALUES (?, ?, ?) IF NOT EXISTS USING TTL ?;
+"#;
+
+static UPDATE_INVITATION_QUOTA_QUERY: &str = r#"
+    UPDATE uptop.invitation_quotas USING TTL ?
+    SET sent = ?, window_started_at = ?
+    WHERE inviter_id = ?
+    IF sent = ? AND window_started_at = ?;
+"#;
@@ -0,0 +1,147 @@
+use crate::{
+    domain::auth::{entity::LoginAttempt, repository::LoginAttemptRepository},
+    infrastructure::persistence::is_applied,
+};
+use anyhow::anyhow;
+use charybdis::operations::Find;
+use uptop_core::common::{
+    db_types::CassandraCacheSession,
+    result::{AppError, AppResult},
+};
+
+#[derive(Clone, Debug)]
+pub struct LoginAttemptRepo {
+    db: CassandraCacheSession,
+}
+
+impl LoginAttemptRepo {
+    pub fn new(db: CassandraCacheSession) -> Self {
+        Self { db }
+    }
+
+    pub async fn migrate_login_attempt_table(&self) -> AppResult<()> {
+        let session = self.db.lock().await;
+        session
+            .execute_unpaged(CREATE_LOGIN_ATTEMPT_TABLE_QUERY, ())
+            .await?;
+        Ok(())
+    }
+}
+
+impl LoginAttemptRepository for LoginAttemptRepo {
+    async fn find_login_attempt(
+        &self,
+        scope: &str,
+        subject: &str,
+    ) -> AppResult<Option<LoginAttempt>> {
+        let session = self.db.lock().await;
+        let result = LoginAttempt {
+            scope: scope.to_owned(),
+            subject: subject.to_owned(),
+            ..Default::default()
+        }
+        .maybe_find_by_primary_key()
+        .execute(&session)
+        .await;
+
+        match result {
+            Ok(attempt) => Ok(attempt),
+            Err(err) => {
+                tracing::error!("{err:?}");
+                Err(anyhow!(AppError::InternalServerError))
+            }
+        }
+    }
+
+    async fn save_login_attempt(
+        &self,
+        previous: Option<&LoginAttempt>,
+        attempt: &LoginAttempt,
+        ttl_seconds: i32,
+    ) -> AppResult<bool> {
+        let session = self.db.lock().await;
+        let ttl_seconds = ttl_seconds.max(1);
+        let result = match previous {
+            None => {
+                session
+                    .execute_unpaged(
+                        INSERT_LOGIN_ATTEMPT_QUERY,
+                        (
+                            &attempt.scope,
+                            &attempt.subject,
+                            attempt.failures,
+                            attempt.last_failed_at,
+                            attempt.locked_until,
+                            ttl_seconds,
+                        ),
+                    )
+                    .await
+            }
+            Some(previous) => {
+                session
+                    .execute_unpaged(
+                        UPDATE_LOGIN_ATTEMPT_QUERY,
+                        (
+                            ttl_seconds,
+                            attempt.failures,
+                            attempt.last_failed_at,
+                            attempt.locked_until,
+                            &attempt.scope,
+                            &attempt.subject,
+                            previous.failures,
+                        ),
+                    )
+                    .await
+            }
+        };
+
+        match result {
+            Ok(result) => Ok(is_applied(result)),
+            Err(err) => {
+                tracing::error!("{err:?}");
+                Err(anyhow!(AppError::InternalServerError))
+            }
+        }
+    }
+
+    async fn clear_login_attempt(&self, scope: &str, subject: &str) -> AppResult<()> {
+        let session = self.db.lock().await;
+        match session
+            .execute_unpaged(DELETE_LOGIN_ATTEMPT_QUERY, (scope, subject))
+            .await
+        {
+            Ok(_) => Ok(()),
+            Err(err) => {
+                tracing::error!("{err:?}");
+                Err(anyhow!(AppError::InternalServerError))
+            }
+        }
+    }
+}
+
+static CREATE_LOGIN_ATTEMPT_TABLE_QUERY: &str = r#"
+    CREATE TABLE IF NOT EXISTS uptop.login_attempts (
+        scope text,
+        subject text,
+        failures int,
+        last_failed_at timestamp,
+        locked_until timestamp,
+        PRIMARY KEY ((scope, subject))
+    );
+"#;
+
+static INSERT_LOGIN_ATTEMPT_QUERY: &str = r#"
+    INSERT INTO uptop.login_attempts (scope, subject, failures, last_failed_at, locked_until)
+    VALUES (?, ?, ?, ?, ?) IF NOT EXISTS USING TTL ?;
+"#;
+
+static UPDATE_LOGIN_ATTEMPT_QUERY: &str = r#"
+    UPDATE uptop.login_attempts USING TTL ?
+    SET failures = ?, last_failed_at = ?, locked_until = ?
+    WHERE scope = ? AND subject = ?
+    IF failures = ?;
+"#;
+
+static DELETE_LOGIN_ATTEMPT_QUERY: &str = r#"
+    DELETE FROM uptop.login_attempts WHERE scope = ? AND subject = ?;
+"#;
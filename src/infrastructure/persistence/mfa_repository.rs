@@ -0,0 +1,248 @@
+use crate::{
+    domain::auth::{
+        entity::{MfaChallenge, MfaFactor},
+        repository::MfaRepository,
+    },
+    infrastructure::persistence::is_applied,
+};
+use anyhow::anyhow;
+use charybdis::{
+    operations::{Find, Insert},
+    types::Timeuuid,
+};
+use chrono::Utc;
+use uptop_core::common::{
+    db_types::CassandraCacheSession,
+    result::{AppError, AppResult},
+};
+
+#[derive(Clone, Debug)]
+pub struct MfaRepo {
+    db: CassandraCacheSession,
+}
+
+impl MfaRepo {
+    pub fn new(db: CassandraCacheSession) -> Self {
+        Self { db }
+    }
+
+    pub async fn migrate_mfa_tables(&self) -> AppResult<()> {
+        let session = self.db.lock().await;
+        session
+            .execute_unpaged(CREATE_MFA_FACTOR_TABLE_QUERY, ())
+            .await?;
+        session
+            .execute_unpaged(CREATE_MFA_CHALLENGE_TABLE_QUERY, ())
+            .await?;
+        Ok(())
+    }
+}
+
+impl MfaRepository for MfaRepo {
+    async fn save_mfa_factor(&self, factor: &MfaFactor) -> AppResult<()> {
+        let session = self.db.lock().await;
+        match factor.insert().execute(&session).await {
+            Ok(_) => Ok(()),
+            Err(err) => {
+                tracing::error!("{err:?}");
+                Err(anyhow!(AppError::InternalServerError))
+            }
+        }
+    }
+
+    async fn find_mfa_factor(&self, user_id: Timeuuid) -> AppResult<Option<MfaFactor>> {
+        let session = self.db.lock().await;
+        let result = MfaFactor {
+            user_id,
+            ..Default::default()
+        }
+        .maybe_find_by_primary_key()
+        .execute(&session)
+        .await;
+
+        match result {
+            Ok(factor) => Ok(factor),
+            Err(err) => {
+                tracing::error!("{err:?}");
+                Err(anyhow!(AppError::InternalServerError))
+            }
+        }
+    }
+
+    async fn delete_mfa_factor(&self, user_id: Timeuuid) -> AppResult<()> {
+        let session = self.db.lock().await;
+        match session
+            .execute_unpaged(DELETE_MFA_FACTOR_QUERY, (user_id,))
+            .await
+        {
+            Ok(_) => Ok(()),
+            Err(err) => {
+                tracing::error!("{err:?}");
+                Err(anyhow!(AppError::InternalServerError))
+            }
+        }
+    }
+
+    async fn mark_totp_step_used(&self, factor: &MfaFactor, step: i64) -> AppResult<bool> {
+        let session = self.db.lock().await;
+        match session
+            .execute_unpaged(
+                MARK_TOTP_STEP_USED_QUERY,
+                (step, Utc::now(), factor.user_id, factor.last_used_step),
+            )
+            .await
+        {
+            Ok(result) => Ok(is_applied(result)),
+            Err(err) => {
+                tracing::error!("{err:?}");
+                Err(anyhow!(AppError::InternalServerError))
+            }
+        }
+    }
+
+    async fn replace_backup_codes(
+        &self,
+        factor: &MfaFactor,
+        backup_code_hashes: Vec<String>,
+    ) -> AppResult<bool> {
+        let session = self.db.lock().await;
+        match session
+            .execute_unpaged(
+                REPLACE_BACKUP_CODES_QUERY,
+                (
+                    backup_code_hashes,
+                    Utc::now(),
+                    factor.user_id,
+                    &factor.backup_code_hashes,
+                ),
+            )
+            .await
+        {
+            Ok(result) => Ok(is_applied(result)),
+            Err(err) => {
+                tracing::error!("{err:?}");
+                Err(anyhow!(AppError::InternalServerError))
+            }
+        }
+    }
+
+    async fn create_mfa_challenge(&self, challenge: &MfaChallenge) -> AppResult<()> {
+        let session = self.db.lock().await;
+        match session
+            .execute_unpaged(
+                INSERT_MFA_CHALLENGE_QUERY,
+                (
+                    challenge.user_id,
+                    challenge.challenge_id,
+                    &challenge.challenge_hash,
+                    &challenge.country,
+                    &challenge.region,
+                    &challenge.city,
+                    challenge.created_at,
+                    challenge.expires_at,
+                    remaining_ttl(challenge),
+                ),
+            )
+            .await
+        {
+            Ok(_) => Ok(()),
+            Err(err) => {
+                tracing::error!("{err:?}");
+                Err(anyhow!(AppError::InternalServerError))
+            }
+        }
+    }
+
+    async fn take_mfa_challenge(
+        &self,
+        user_id: Timeuuid,
+        challenge_id: Timeuuid,
+    ) -> AppResult<Option<MfaChallenge>> {
+        let session = self.db.lock().await;
+        let challenge = match (MfaChallenge {
+            user_id,
+            challenge_id,
+            ..Default::default()
+        })
+        .maybe_find_by_primary_key()
+        .execute(&session)
+        .await
+        {
+            Ok(Some(challenge)) => challenge,
+            Ok(None) => return Ok(None),
+            Err(err) => {
+                tracing::error!("{err:?}");
+                return Err(anyhow!(AppError::InternalServerError));
+            }
+        };
+
+        match session
+            .execute_unpaged(DELETE_MFA_CHALLENGE_QUERY, (user_id, challenge_id))
+            .await
+        {
+            Ok(result) => Ok(is_applied(result).then_some(challenge)),
+            Err(err) => {
+                tracing::error!("{err:?}");
+                Err(anyhow!(AppError::InternalServerError))
+            }
+        }
+    }
+}
+
+/// Seconds until `challenge` expires, as a Scylla TTL (which must be positive).
+fn remaining_ttl(challenge: &MfaChallenge) -> i32 {
+    (challenge.expires_at - Utc::now()).num_seconds().max(1) as i32
+}
+
+static CREATE_MFA_FACTOR_TABLE_QUERY: &str = r#"
+    CREATE TABLE IF NOT EXISTS uptop.mfa_factors (
+        user_id timeuuid,
+        secret_ciphertext text,
+        backup_code_hashes list<text>,
+        last_used_step bigint,
+        confirmed_at timestamp,
+        created_at timestamp,
+        updated_at timestamp,
+        PRIMARY KEY (user_id)
+    );
+"#;
+
+static CREATE_MFA_CHALLENGE_TABLE_QUERY: &str = r#"
+    CREATE TABLE IF NOT EXISTS uptop.mfa_challenges (
+        user_id timeuuid,
+        challenge_id timeuuid,
+        challenge_hash text,
+        country text,
+        region text,
+        city text,
+        created_at timestamp,
+        expires_at timestamp,
+        PRIMARY KEY ((user_id), challenge_id)
+    );
+"#;
+
+static DELETE_MFA_FACTOR_QUERY: &str = r#"
+    DELETE FROM uptop.mfa_factors WHERE user_id = ?;
+"#;
+
+static MARK_TOTP_STEP_USED_QUERY: &str = r#"
+    UPDATE uptop.mfa_factors SET last_used_step = ?, updated_at = ?
+    WHERE user_id = ?
+    IF last_used_step = ?;
+"#;
+
+static REPLACE_BACKUP_CODES_QUERY: &str = r#"
+    UPDATE uptop.mfa_factors SET backup_code_hashes = ?, updated_at = ?
+    WHERE user_id = ?
+    IF backup_code_hashes = ?;
+"#;
+
+static INSERT_MFA_CHALLENGE_QUERY: &str = r#"
+    INSERT INTO uptop.mfa_challenges (
+        user_id, challenge_id, challenge_hash, country, region, city, created_at, expires_at
+    ) VALUES (?, ?, ?, ?, ?, ?, ?, ?) USING TTL ?;
+"#;
+
+static DELETE_MFA_CHALLENGE_QUERY: &str = r#"
+    DELETE FROM uptop.mfa_challenges WHERE user_id = ? AND challenge_id = ? IF EXISTS;
+"#;
@@ -0,0 +1,331 @@
+use crate::domain::{
+    organization::{
+        entity::{Organization, OrganizationMember, OrganizationRole},
+        repository::{MemberPage, OrganizationRepository},
+    },
+    user::entity::User,
+};
+use anyhow::anyhow;
+use charybdis::{
+    model::{BaseModel, Model},
+    operations::{Find, Insert, Update},
+    types::Timeuuid,
+};
+use chrono::Utc;
+use scylla::{
+    batch::{Batch, BatchType},
+    query::Query,
+    serialize::batch::BatchValues,
+    statement::{PagingState, PagingStateResponse},
+};
+use uptop_core::common::{
+    db_types::CassandraCacheSession,
+    result::{AppError, AppResult},
+};
+
+#[derive(Clone, Debug)]
+pub struct OrganizationRepo {
+    db: CassandraCacheSession,
+}
+
+impl OrganizationRepo {
+    pub fn new(db: CassandraCacheSession) -> Self {
+        Self { db }
+    }
+
+    pub async fn migrate_organization_tables(&self) -> AppResult<()> {
+        let session = self.db.lock().await;
+        session
+            .execute_unpaged(CREATE_ORGANIZATION_TABLE_QUERY, ())
+            .await?;
+        session
+            .execute_unpaged(CREATE_ORGANIZATION_MEMBER_TABLE_QUERY, ())
+            .await?;
+        Ok(())
+    }
+
+    async fn run_batch(&self, batch: &Batch, values: impl BatchValues) -> AppResult<()> {
+        let session = self.db.lock().await;
+        match session.batch(batch, values).await {
+            Ok(_) => Ok(()),
+            Err(err) => {
+                tracing::error!("{err:?}");
+                Err(anyhow!(AppError::InternalServerError))
+            }
+        }
+    }
+}
+
+impl OrganizationRepository for OrganizationRepo {
+    async fn create_organization(
+        &self,
+        organization: &Organization,
+        owner: &User,
+    ) -> AppResult<()> {
+        let member = OrganizationMember {
+            organization_id: organization.organization_id,
+            user_id: owner.user_id,
+            role: OrganizationRole::Owner.to_string(),
+            joined_at: organization.created_at,
+        };
+
+        let mut batch = Batch::new(BatchType::Logged);
+        batch.append_statement(Organization::INSERT_QUERY);
+        batch.append_statement(OrganizationMember::INSERT_QUERY);
+        batch.append_statement(ADD_USER_ORGANIZATION_QUERY);
+
+        self.run_batch(
+            &batch,
+            (
+                organization,
+                &member,
+                user_values(organization.organization_id, owner),
+            ),
+        )
+        .await
+    }
+
+    async fn find_organization(
+        &self,
+        organization_id: Timeuuid,
+    ) -> AppResult<Option<Organization>> {
+        let session = self.db.lock().await;
+        let result = Organization {
+            organization_id,
+            ..Default::default()
+        }
+        .maybe_find_by_primary_key()
+        .execute(&session)
+        .await;
+
+        match result {
+            Ok(organization) => Ok(organization),
+            Err(err) => {
+                tracing::error!("{err:?}");
+                Err(anyhow!(AppError::InternalServerError))
+            }
+        }
+    }
+
+    async fn update_organization(&self, organization: &Organization) -> AppResult<()> {
+        let session = self.db.lock().await;
+        match organization.update().execute(&session).await {
+            Ok(_) => Ok(()),
+            Err(err) => {
+                tracing::error!("{err:?}");
+                Err(anyhow!(AppError::InternalServerError))
+            }
+        }
+    }
+
+    async fn delete_organization(&self, organization_id: Timeuuid) -> AppResult<()> {
+        let mut batch = Batch::new(BatchType::Logged);
+        batch.append_statement(Organization::DELETE_QUERY);
+        batch.append_statement(DELETE_ORGANIZATION_MEMBERS_QUERY);
+
+        self.run_batch(&batch, ((organization_id,), (organization_id,)))
+            .await
+    }
+
+    async fn find_member(
+        &self,
+        organization_id: Timeuuid,
+        user_id: Timeuuid,
+    ) -> AppResult<Option<OrganizationMember>> {
+        let session = self.db.lock().await;
+        let result = OrganizationMember {
+            organization_id,
+            user_id,
+            ..Default::default()
+        }
+        .maybe_find_by_primary_key()
+        .execute(&session)
+        .await;
+
+        match result {
+            Ok(member) => Ok(member),
+            Err(err) => {
+                tracing::error!("{err:?}");
+                Err(anyhow!(AppError::InternalServerError))
+            }
+        }
+    }
+
+    async fn find_members(
+        &self,
+        organization_id: Timeuuid,
+        page_size: i32,
+        paging_state: Option<Vec<u8>>,
+    ) -> AppResult<MemberPage> {
+        let mut statement = Query::new(OrganizationMember::FIND_BY_PARTITION_KEY_QUERY);
+        statement.set_page_size(page_size);
+
+        let paging_state = match paging_state {
+            Some(raw) => PagingState::new_from_raw_bytes(raw),
+            None => PagingState::start(),
+        };
+
+        let session = self.db.lock().await;
+        let result = session
+            .execute_single_page(statement, (organization_id,), paging_state)
+            .await;
+
+        match result {
+            Ok((rows, paging_state_response)) => {
+                let members = rows
+                    .rows_typed::<OrganizationMember>()?
+                    .collect::<Result<Vec<_>, _>>()?;
+                let paging_state = match paging_state_response {
+                    PagingStateResponse::HasMorePages { state } => {
+                        state.as_bytes_slice().map(|bytes| bytes.to_vec())
+                    }
+                    PagingStateResponse::NoMorePages => None,
+                };
+
+                Ok(MemberPage {
+                    members,
+                    paging_state,
+                })
+            }
+            Err(err) => {
+                tracing::error!("{err:?}");
+                Err(anyhow!(AppError::InternalServerError))
+            }
+        }
+    }
+
+    async fn add_member(&self, member: &OrganizationMember, user: &User) -> AppResult<()> {
+        // Appending to a list is not idempotent, a role change only rewrites the row.
+        let listed = user
+            .organizations
+            .as_deref()
+            .unwrap_or_default()
+            .contains(&member.organization_id);
+        if listed {
+            let session = self.db.lock().await;
+            return match member.insert().execute(&session).await {
+                Ok(_) => Ok(()),
+                Err(err) => {
+                    tracing::error!("{err:?}");
+                    Err(anyhow!(AppError::InternalServerError))
+                }
+            };
+        }
+
+        let mut batch = Batch::new(BatchType::Logged);
+        batch.append_statement(OrganizationMember::INSERT_QUERY);
+        batch.append_statement(ADD_USER_ORGANIZATION_QUERY);
+
+        self.run_batch(&batch, (member, user_values(member.organization_id, user)))
+            .await
+    }
+
+    async fn remove_member(&self, organization_id: Timeuuid, user: &User) -> AppResult<()> {
+        let remove_from_user = match user.active_organization == Some(organization_id) {
+            true => REMOVE_ACTIVE_USER_ORGANIZATION_QUERY,
+            false => REMOVE_USER_ORGANIZATION_QUERY,
+        };
+
+        let mut batch = Batch::new(BatchType::Logged);
+        batch.append_statement(OrganizationMember::DELETE_QUERY);
+        batch.append_statement(remove_from_user);
+
+        self.run_batch(
+            &batch,
+            (
+                (organization_id, user.user_id),
+                user_values(organization_id, user),
+            ),
+        )
+        .await
+    }
+
+    async fn set_active_organization(
+        &self,
+        user: &User,
+        organization_id: Option<Timeuuid>,
+    ) -> AppResult<()> {
+        let session = self.db.lock().await;
+        let result = session
+            .execute_unpaged(
+                SET_ACTIVE_ORGANIZATION_QUERY,
+                (
+                    organization_id,
+                    Utc::now(),
+                    &user.country,
+                    &user.region,
+                    &user.city,
+                    user.user_id,
+                ),
+            )
+            .await;
+
+        match result {
+            Ok(_) => Ok(()),
+            Err(err) => {
+                tracing::error!("{err:?}");
+                Err(anyhow!(AppError::InternalServerError))
+            }
+        }
+    }
+}
+
+/// Values of the `organizations` list updates on `user`'s row.
+fn user_values(
+    organization_id: Timeuuid,
+    user: &User,
+) -> (Vec<Timeuuid>, &str, &str, &str, Timeuuid) {
+    (
+        vec![organization_id],
+        &user.country,
+        &user.region,
+        &user.city,
+        user.user_id,
+    )
+}
+
+static CREATE_ORGANIZATION_TABLE_QUERY: &str = r#"
+    CREATE TABLE IF NOT EXISTS uptop.organizations (
+        organization_id timeuuid,
+        name text,
+        description text,
+        owner_id timeuuid,
+        created_at timestamp,
+        updated_at timestamp,
+        PRIMARY KEY (organization_id)
+    );
+"#;
+
+static CREATE_ORGANIZATION_MEMBER_TABLE_QUERY: &str = r#"
+    CREATE TABLE IF NOT EXISTS uptop.organization_members (
+        organization_id timeuuid,
+        user_id timeuuid,
+        role text,
+        joined_at timestamp,
+        PRIMARY KEY ((organization_id), user_id)
+    );
+"#;
+
+static DELETE_ORGANIZATION_MEMBERS_QUERY: &str = r#"
+    DELETE FROM uptop.organization_members WHERE organization_id = ?;
+"#;
+
+static ADD_USER_ORGANIZATION_QUERY: &str = r#"
+    UPDATE uptop.users SET organizations = organizations + ?
+    WHERE country = ? AND region = ? AND city = ? AND user_id = ?;
+"#;
+
+static REMOVE_USER_ORGANIZATION_QUERY: &str = r#"
+    UPDATE uptop.users SET organizations = organizations - ?
+    WHERE country = ? AND region = ? AND city = ? AND user_id = ?;
+"#;
+
+static REMOVE_ACTIVE_USER_ORGANIZATION_QUERY: &str = r#"
+    UPDATE uptop.users SET organizations = organizations - ?, active_organization = null
+    WHERE country = ? AND region = ? AND city = ? AND user_id = ?;
+"#;
+
+static SET_ACTIVE_ORGANIZATION_QUERY: &str = r#"
+    UPDATE uptop.users SET active_organization = ?, updated_at = ?
+    WHERE country = ? AND region = ? AND city = ? AND user_id = ?;
+"#;
@@ -0,0 +1,222 @@
+use crate::{
+    domain::auth::{entity::RefreshToken, repository::RefreshTokenRepository},
+    infrastructure::persistence::is_applied,
+};
+use anyhow::anyhow;
+use charybdis::{model::BaseModel, operations::Find, types::Timeuuid};
+use chrono::Utc;
+use uptop_core::common::{
+    db_types::CassandraCacheSession,
+    result::{AppError, AppResult},
+};
+
+#[derive(Clone, Debug)]
+pub struct RefreshTokenRepo {
+    db: CassandraCacheSession,
+}
+
+impl RefreshTokenRepo {
+    pub fn new(db: CassandraCacheSession) -> Self {
+        Self { db }
+    }
+
+    pub async fn migrate_refresh_token_table(&self) -> AppResult<()> {
+        let session = self.db.lock().await;
+        session
+            .execute_unpaged(CREATE_REFRESH_TOKEN_TABLE_QUERY, ())
+            .await?;
+        Ok(())
+    }
+
+    /// Deletes the tokens of `user_id` whose family matches `revoke`.
+    async fn revoke_families_where(
+        &self,
+        user_id: Timeuuid,
+        revoke: impl Fn(Timeuuid) -> bool,
+    ) -> AppResult<()> {
+        let session = self.db.lock().await;
+        let tokens = match session
+            .execute_unpaged(RefreshToken::FIND_BY_PARTITION_KEY_QUERY, (user_id,))
+            .await
+        {
+            Ok(result) => result
+                .rows_typed::<RefreshToken>()?
+                .collect::<Result<Vec<_>, _>>()?,
+            Err(err) => {
+                tracing::error!("{err:?}");
+                return Err(anyhow!(AppError::InternalServerError));
+            }
+        };
+
+        let token_ids: Vec<Timeuuid> = tokens
+            .into_iter()
+            .filter(|token| revoke(token.family_id))
+            .map(|token| token.token_id)
+            .collect();
+
+        if token_ids.is_empty() {
+            return Ok(());
+        }
+
+        match session
+            .execute_unpaged(DELETE_REFRESH_TOKENS_QUERY, (user_id, token_ids))
+            .await
+        {
+            Ok(_) => Ok(()),
+            Err(err) => {
+                tracing::error!("{err:?}");
+                Err(anyhow!(AppError::InternalServerError))
+            }
+        }
+    }
+}
+
+impl RefreshTokenRepository for RefreshTokenRepo {
+    async fn create_refresh_token(&self, token: &RefreshToken) -> AppResult<()> {
+        let session = self.db.lock().await;
+        match session
+            .execute_unpaged(
+                INSERT_REFRESH_TOKEN_QUERY,
+                (
+                    token.user_id,
+                    token.token_id,
+                    token.family_id,
+                    &token.token_hash,
+                    &token.country,
+                    &token.region,
+                    &token.city,
+                    token.created_at,
+                    token.expires_at,
+                    remaining_ttl(token),
+                ),
+            )
+            .await
+        {
+            Ok(_) => Ok(()),
+            Err(err) => {
+                tracing::error!("{err:?}");
+                Err(anyhow!(AppError::InternalServerError))
+            }
+        }
+    }
+
+    async fn find_refresh_token(
+        &self,
+        user_id: Timeuuid,
+        token_id: Timeuuid,
+    ) -> AppResult<Option<RefreshToken>> {
+        let session = self.db.lock().await;
+        let result = RefreshToken {
+            user_id,
+            token_id,
+            ..Default::default()
+        }
+        .maybe_find_by_primary_key()
+        .execute(&session)
+        .await;
+
+        match result {
+            Ok(token) => Ok(token),
+            Err(err) => {
+                tracing::error!("{err:?}");
+                Err(anyhow!(AppError::InternalServerError))
+            }
+        }
+    }
+
+    async fn mark_refresh_token_rotated(&self, token: &RefreshToken) -> AppResult<bool> {
+        let session = self.db.lock().await;
+        match session
+            .execute_unpaged(
+                ROTATE_REFRESH_TOKEN_QUERY,
+                (
+                    remaining_ttl(token),
+                    Utc::now(),
+                    token.user_id,
+                    token.token_id,
+                    &token.token_hash,
+                ),
+            )
+            .await
+        {
+            Ok(result) => Ok(is_applied(result)),
+            Err(err) => {
+                tracing::error!("{err:?}");
+                Err(anyhow!(AppError::InternalServerError))
+            }
+        }
+    }
+
+    async fn revoke_refresh_token_family(
+        &self,
+        user_id: Timeuuid,
+        family_id: Timeuuid,
+    ) -> AppResult<()> {
+        self.revoke_families_where(user_id, |family| family == family_id)
+            .await
+    }
+
+    async fn revoke_other_refresh_token_families(
+        &self,
+        user_id: Timeuuid,
+        keep_family_id: Timeuuid,
+    ) -> AppResult<()> {
+        self.revoke_families_where(user_id, |family| family != keep_family_id)
+            .await
+    }
+
+    async fn revoke_refresh_tokens(&self, user_id: Timeuuid) -> AppResult<()> {
+        let session = self.db.lock().await;
+        match session
+            .execute_unpaged(DELETE_USER_REFRESH_TOKENS_QUERY, (user_id,))
+            .await
+        {
+            Ok(_) => Ok(()),
+            Err(err) => {
+                tracing::error!("{err:?}");
+                Err(anyhow!(AppError::InternalServerError))
+            }
+        }
+    }
+}
+
+/// Seconds until `token` expires, as a Scylla TTL (which must be positive).
+fn remaining_ttl(token: &RefreshToken) -> i32 {
+    (token.expires_at - Utc::now()).num_seconds().max(1) as i32
+}
+
+static CREATE_REFRESH_TOKEN_TABLE_QUERY: &str = r#"
+    CREATE TABLE IF NOT EXISTS uptop.refresh_tokens (
+        user_id timeuuid,
+        token_id timeuuid,
+        family_id timeuuid,
+        token_hash text,
+        country text,
+        region text,
+        city text,
+        rotated_at timestamp,
+        created_at timestamp,
+        expires_at timestamp,
+        PRIMARY KEY ((user_id), token_id)
+    ) WITH CLUSTERING ORDER BY (token_id DESC);
+"#;
+
+static INSERT_REFRESH_TOKEN_QUERY: &str = r#"
+    INSERT INTO uptop.refresh_tokens (
+        user_id, token_id, family_id, token_hash, country, region, city, created_at, expires_at
+    ) VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?) USING TTL ?;
+"#;
+
+static ROTATE_REFRESH_TOKEN_QUERY: &str = r#"
+    UPDATE uptop.refresh_tokens USING TTL ? SET rotated_at = ?
+    WHERE user_id = ? AND token_id = ?
+    IF token_hash = ? AND rotated_at = null;
+"#;
+
+static DELETE_REFRESH_TOKENS_QUERY: &str = r#"
+    DELETE FROM uptop.refresh_tokens WHERE user_id = ? AND token_id IN ?;
+"#;
+
+static DELETE_USER_REFRESH_TOKENS_QUERY: &str = r#"
+    DELETE FROM uptop.refresh_tokens WHERE user_id = ?;
+"#;
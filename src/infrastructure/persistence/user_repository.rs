@@ -1,19 +1,43 @@
 use crate::{
-    application::user::request::{
-        RequestFindUserError, RequestGetUser, RequestGetUserByPartitionKey,
-        RequestGetUserByPrimaryKey, RequestUpdateUserStatus,
+    application::{
+        id::parse_id,
+        user::request::{
+            RequestCreateUserError, RequestFindUserError, RequestGetUser,
+            RequestGetUserByPartitionKey, RequestGetUserByPrimaryKey, RequestUpdateUserStatus,
+        },
+    },
+    domain::user::{
+        entity::{
+            ReasonOfStatus, User, UserByEmail, UserById, UserByUserName, UserColumn, UserDeletion,
+            UserStatus, UserStatusChange,
+        },
+        identifier::{canonical_email, canonical_user_name},
+        repository::{StatusHistoryPage, UserPage, UserRepository},
+    },
+    infrastructure::persistence::{
+        add_column_if_missing, column_exists, is_applied, migration_applied, record_migration,
+        CREATE_SCHEMA_MIGRATION_TABLE_QUERY,
     },
-    domain::user::{entity::User, repository::UserRepository},
 };
-use anyhow::anyhow;
+use anyhow::{anyhow, bail};
 use charybdis::{
-    operations::{Find, Insert, Update},
-    types::Timeuuid,
+    model::{BaseModel, Model},
+    operations::{Delete, Find, Insert},
+    types::{Timestamp, Timeuuid, Uuid},
+};
+use chrono::Utc;
+use scylla::{
+    batch::{Batch, BatchType},
+    frame::{response::result::CqlValue, value::CqlTimestamp},
+    query::Query,
+    statement::{PagingState, PagingStateResponse},
+    CachingSession,
 };
-use std::{str::FromStr, vec};
+use std::vec;
 use uptop_core::common::{
     db_types::CassandraCacheSession,
     result::{AppError, AppResult},
+    utils::now_timeuuid,
 };
 
 #[derive(Clone, Debug)]
@@ -28,44 +52,612 @@ impl UserRepo {
 
     pub async fn migrate_user_table(&self) -> AppResult<()> {
         let session = self.db.lock().await;
+        session
+            .execute_unpaged(CREATE_SCHEMA_MIGRATION_TABLE_QUERY, ())
+            .await?;
         session.execute_unpaged(CREATE_USER_TABLE_QUERY, ()).await?;
         session.execute_unpaged(CREATE_USER_ID_INDEX, ()).await?;
-        session.execute_unpaged(CREATE_USER_EMAIL_INDEX, ()).await?;
-        session.execute_unpaged(CREATE_USER_NAME_INDEX, ()).await?;
+        session
+            .execute_unpaged(CREATE_USER_BY_EMAIL_TABLE_QUERY, ())
+            .await?;
+        session
+            .execute_unpaged(CREATE_USER_BY_USER_NAME_TABLE_QUERY, ())
+            .await?;
+        session
+            .execute_unpaged(CREATE_USER_BY_ID_TABLE_QUERY, ())
+            .await?;
+        session
+            .execute_unpaged(CREATE_USER_DELETION_TABLE_QUERY, ())
+            .await?;
+        session
+            .execute_unpaged(CREATE_USER_STATUS_HISTORY_TABLE_QUERY, ())
+            .await?;
+        add_column_if_missing(
+            &session,
+            "users",
+            "email_verify_code_expires_at",
+            "timestamp",
+        )
+        .await?;
+        add_column_if_missing(
+            &session,
+            "users",
+            "password_recovery_code_expires_at",
+            "timestamp",
+        )
+        .await?;
+        add_column_if_missing(&session, "users", "deleted_at", "timestamp").await?;
+        add_column_if_missing(&session, "users", "purged_at", "timestamp").await?;
+        add_column_if_missing(&session, "users", "latest_status", "text").await?;
+        add_column_if_missing(&session, "users", "latest_status_reason", "text").await?;
+        add_column_if_missing(&session, "users", "latest_status_at", "timestamp").await?;
+        if !migration_applied(&session, LEGACY_STATUSES_MIGRATION).await? {
+            if column_exists(&session, "users", "status").await? {
+                migrate_legacy_statuses(&session).await?;
+            }
+            record_migration(&session, LEGACY_STATUSES_MIGRATION).await?;
+        }
+        if !migration_applied(&session, LOOKUP_TABLES_MIGRATION).await? {
+            backfill_lookup_tables(&session).await?;
+            record_migration(&session, LOOKUP_TABLES_MIGRATION).await?;
+        }
+        session.execute_unpaged(DROP_USER_EMAIL_INDEX, ()).await?;
+        session.execute_unpaged(DROP_USER_NAME_INDEX, ()).await?;
+        Ok(())
+    }
+}
+
+/// Moves `status list<text>` entries of older releases, `Status:Reason:<timeuuid>`,
+/// into the status history and the `latest_status` columns. Rows that already
+/// have a `latest_status` are skipped, so an interrupted run can start over.
+/// Runs once, as the `LEGACY_STATUSES_MIGRATION`. The legacy column is left in place and no longer written.
+async fn migrate_legacy_statuses(session: &CachingSession) -> AppResult<()> {
+    let mut statement = Query::new(FIND_ALL_LEGACY_STATUSES_QUERY);
+    statement.set_page_size(SCAN_PAGE_SIZE);
+    let mut paging_state = PagingState::start();
+
+    loop {
+        let (rows, paging_state_response) = session
+            .execute_single_page(statement.clone(), (), paging_state)
+            .await?;
+
+        for row in rows.rows_typed::<LegacyStatuses>()? {
+            let (country, region, city, user_id, entries, latest_status) = row?;
+            if latest_status.is_some() {
+                continue;
+            }
+
+            let mut latest = None;
+            for entry in entries.unwrap_or_default() {
+                let Ok((status, change_id)) = UserStatus::parse_legacy(&entry) else {
+                    tracing::warn!(%user_id, "Skipping unreadable status {entry}");
+                    continue;
+                };
+                let change = UserStatusChange {
+                    change_id: change_id.unwrap_or_else(now_timeuuid),
+                    changed_at: change_id
+                        .and_then(|id| timeuuid_timestamp(&id.to_string()))
+                        .unwrap_or_else(Utc::now),
+                    ..UserStatusChange::new(user_id, &status, None, None)
+                };
+                change.insert().execute(session).await?;
+                latest = Some(change);
+            }
+
+            if let Some(change) = latest {
+                session
+                    .execute_unpaged(
+                        SET_LATEST_STATUS_QUERY,
+                        (
+                            &change.status,
+                            &change.reason,
+                            change.changed_at,
+                            &country,
+                            &region,
+                            &city,
+                            user_id,
+                        ),
+                    )
+                    .await?;
+            }
+        }
+
+        match paging_state_response {
+            PagingStateResponse::HasMorePages { state } => paging_state = state,
+            PagingStateResponse::NoMorePages => return Ok(()),
+        }
+    }
+}
+
+type LegacyStatuses = (
+    String,
+    String,
+    String,
+    Timeuuid,
+    Option<Vec<String>>,
+    Option<String>,
+);
+
+/// The time a version 1 UUID was generated at, read from its string form.
+fn timeuuid_timestamp(timeuuid: &str) -> Option<Timestamp> {
+    // 100 ns intervals between the UUID epoch (1582-10-15) and the Unix epoch.
+    const UUID_EPOCH_OFFSET: u64 = 0x01B2_1DD2_1381_4000;
+
+    let hex = timeuuid.replace('-', "");
+    if hex.len() != 32 || !hex.is_ascii() || &hex[12..13] != "1" {
+        return None;
+    }
+    let time_low = u64::from_str_radix(&hex[0..8], 16).ok()?;
+    let time_mid = u64::from_str_radix(&hex[8..12], 16).ok()?;
+    let time_high = u64::from_str_radix(&hex[13..16], 16).ok()?;
+    let intervals = (time_high << 48) | (time_mid << 32) | time_low;
+
+    let micros = intervals.checked_sub(UUID_EPOCH_OFFSET)? / 10;
+    Timestamp::from_timestamp_micros(i64::try_from(micros).ok()?)
+}
+
+/// Fills the lookup tables and the id mapping from users stored before they
+/// existed. Both writes are idempotent, so an interrupted run can start over.
+/// Runs once, as the `LOOKUP_TABLES_MIGRATION`.
+/// Purged users keep their id mapping but gave up their email and user name.
+async fn backfill_lookup_tables(session: &CachingSession) -> AppResult<()> {
+    let mut statement = Query::new(FIND_ALL_USER_KEYS_QUERY);
+    statement.set_page_size(SCAN_PAGE_SIZE);
+    let mut paging_state = PagingState::start();
+
+    loop {
+        let (rows, paging_state_response) = session
+            .execute_single_page(statement.clone(), (), paging_state)
+            .await?;
+
+        for row in rows.rows_typed::<UserKeys>()? {
+            let (user_id, user_name, email, country, region, city, purged_at) = row?;
+            let user = User {
+                user_id,
+                user_name,
+                email,
+                country,
+                region,
+                city,
+                ..Default::default()
+            };
+
+            UserById::from(&user).insert().execute(session).await?;
+            if purged_at.is_some() || user.email.is_empty() {
+                continue;
+            }
+            for lookup in [
+                Lookup::user_name(&user.user_name),
+                Lookup::email(&user.email),
+            ] {
+                if !lookup.claim(session, &user).await? {
+                    tracing::warn!(
+                        "{lookup:?} of user {} is already held by another user",
+                        user.user_id
+                    );
+                }
+            }
+        }
+
+        match paging_state_response {
+            PagingStateResponse::HasMorePages { state } => paging_state = state,
+            PagingStateResponse::NoMorePages => return Ok(()),
+        }
+    }
+}
+
+type UserKeys = (
+    Timeuuid,
+    String,
+    String,
+    String,
+    String,
+    String,
+    Option<Timestamp>,
+);
+
+/// A unique key of a user in canonical form, backed by its own lookup table.
+#[derive(Clone, Debug)]
+enum Lookup {
+    Email(String),
+    UserName(String),
+}
+
+impl Lookup {
+    fn email(email: &str) -> Self {
+        Self::Email(canonical_email(email))
+    }
+
+    fn user_name(user_name: &str) -> Self {
+        Self::UserName(canonical_user_name(user_name))
+    }
+
+    /// Inserts the lookup row for `user` unless the key is taken. Returns
+    /// whether `user` holds the key afterwards.
+    async fn claim(&self, session: &CachingSession, user: &User) -> AppResult<bool> {
+        let (query, key) = match self {
+            Lookup::Email(email) => (INSERT_USER_BY_EMAIL_QUERY, email),
+            Lookup::UserName(user_name) => (INSERT_USER_BY_USER_NAME_QUERY, user_name),
+        };
+        let result = session
+            .execute_unpaged(
+                query,
+                (key, user.user_id, &user.country, &user.region, &user.city),
+            )
+            .await?;
+        if is_applied(result) {
+            return Ok(true);
+        }
+
+        // Retried claims by the same user are not conflicts.
+        Ok(self.owner(session).await? == Some(user.user_id))
+    }
+
+    /// Points the lookup row at the location of `user` if `user` holds the
+    /// key. Returns whether it did.
+    async fn relocate(&self, session: &CachingSession, user: &User) -> AppResult<bool> {
+        let (query, key) = match self {
+            Lookup::Email(email) => (MOVE_USER_BY_EMAIL_QUERY, email),
+            Lookup::UserName(user_name) => (MOVE_USER_BY_USER_NAME_QUERY, user_name),
+        };
+        let result = session
+            .execute_unpaged(
+                query,
+                (&user.country, &user.region, &user.city, key, user.user_id),
+            )
+            .await?;
+        Ok(is_applied(result))
+    }
+
+    /// Deletes the lookup row if `user_id` still holds it.
+    async fn release(&self, session: &CachingSession, user_id: Timeuuid) -> AppResult<()> {
+        let (query, key) = match self {
+            Lookup::Email(email) => (DELETE_USER_BY_EMAIL_QUERY, email),
+            Lookup::UserName(user_name) => (DELETE_USER_BY_USER_NAME_QUERY, user_name),
+        };
+        session.execute_unpaged(query, (key, user_id)).await?;
         Ok(())
     }
+
+    async fn owner(&self, session: &CachingSession) -> AppResult<Option<Timeuuid>> {
+        Ok(self.location(session).await?.map(|user| user.user_id))
+    }
+
+    /// The primary key of the user holding the key, as a bare [`User`].
+    async fn location(&self, session: &CachingSession) -> AppResult<Option<User>> {
+        let location = match self {
+            Lookup::Email(email) => UserByEmail {
+                email: email.to_owned(),
+                ..Default::default()
+            }
+            .maybe_find_by_primary_key()
+            .execute(session)
+            .await?
+            .map(|row| (row.country, row.region, row.city, row.user_id)),
+            Lookup::UserName(user_name) => UserByUserName {
+                user_name: user_name.to_owned(),
+                ..Default::default()
+            }
+            .maybe_find_by_primary_key()
+            .execute(session)
+            .await?
+            .map(|row| (row.country, row.region, row.city, row.user_id)),
+        };
+
+        Ok(location.map(|(country, region, city, user_id)| User {
+            country,
+            region,
+            city,
+            user_id,
+            ..Default::default()
+        }))
+    }
 }
 
 impl UserRepository for UserRepo {
     async fn create_user<'c>(&self, user: &'c User) -> AppResult<&'c User> {
         let session = self.db.lock().await;
-        match user.insert().execute(&session).await {
-            Ok(_) => Ok(user),
+        let user_name = Lookup::user_name(&user.user_name);
+        let email = Lookup::email(&user.email);
+
+        let result: AppResult<&User> = async {
+            if !user_name.claim(&session, user).await? {
+                bail!(RequestCreateUserError::UserNameExisted {
+                    name: (*user.user_name).to_string()
+                });
+            }
+            if !email.claim(&session, user).await? {
+                user_name.release(&session, user.user_id).await?;
+                bail!(RequestCreateUserError::EmailExisted {
+                    email: (*user.email).to_string()
+                });
+            }
+            let history = match user.current_status() {
+                Ok(status) => UserStatusChange {
+                    changed_at: user.created_at,
+                    ..UserStatusChange::new(user.user_id, &status, None, None)
+                },
+                Err(err) => {
+                    user_name.release(&session, user.user_id).await?;
+                    email.release(&session, user.user_id).await?;
+                    return Err(err);
+                }
+            };
+
+            // The mapping goes before the user so a stored user can always be
+            // found by id.
+            let location = UserById::from(user);
+            let inserted = async {
+                history.insert().execute(&session).await?;
+                location.insert().execute(&session).await?;
+                user.insert().execute(&session).await
+            }
+            .await;
+            if let Err(err) = inserted {
+                history.delete().execute(&session).await?;
+                location.delete().execute(&session).await?;
+                user_name.release(&session, user.user_id).await?;
+                email.release(&session, user.user_id).await?;
+                return Err(err.into());
+            }
+            Ok(user)
+        }
+        .await;
+
+        result.map_err(|err| {
+            if err.is::<RequestCreateUserError>() {
+                return err;
+            }
+            tracing::error!("{err:?}");
+            anyhow!(AppError::InternalServerError)
+        })
+    }
+
+    async fn remove_user(&self, user: &User) -> AppResult<()> {
+        let session = self.db.lock().await;
+        let result: AppResult<()> = async {
+            user.delete().execute(&session).await?;
+            UserById::from(user).delete().execute(&session).await?;
+            session
+                .execute_unpaged(DELETE_USER_STATUS_HISTORY_QUERY, (user.user_id,))
+                .await?;
+            Lookup::user_name(&user.user_name)
+                .release(&session, user.user_id)
+                .await?;
+            Lookup::email(&user.email)
+                .release(&session, user.user_id)
+                .await?;
+            Ok(())
+        }
+        .await;
+
+        result.map_err(|err| {
+            tracing::error!("{err:?}");
+            anyhow!(AppError::InternalServerError)
+        })
+    }
+
+    async fn move_user<'u>(&self, from: &User, to: &'u User) -> AppResult<&'u User> {
+        let session = self.db.lock().await;
+        // Purged users gave up their keys.
+        let lookups = match to.purged_at {
+            Some(_) => vec![],
+            None => vec![Lookup::user_name(&to.user_name), Lookup::email(&to.email)],
+        };
+
+        let result: AppResult<()> = async {
+            // The lookup rows are only written with lightweight transactions,
+            // which can not share a batch across partitions, so the keys
+            // follow the user one by one before its row moves.
+            for lookup in &lookups {
+                if !lookup.relocate(&session, to).await? {
+                    bail!("{lookup:?} is not held by user {}", to.user_id);
+                }
+            }
+
+            let mut batch = Batch::new(BatchType::Logged);
+            batch.append_statement(User::INSERT_QUERY);
+            batch.append_statement(User::DELETE_QUERY);
+            batch.append_statement(UserById::INSERT_QUERY);
+            session
+                .batch(&batch, (to, from.primary_key_values(), UserById::from(to)))
+                .await?;
+            Ok(())
+        }
+        .await;
+
+        match result {
+            Ok(()) => Ok(to),
             Err(err) => {
                 tracing::error!("{err:?}");
+                // The user row stayed, so its keys point back at it.
+                for lookup in &lookups {
+                    if let Err(err) = lookup.relocate(&session, from).await {
+                        tracing::error!("{err:?}");
+                    }
+                }
                 Err(anyhow!(AppError::InternalServerError))
             }
         }
     }
 
-    async fn find_user_by_id(&self, query: &RequestGetUserByPrimaryKey) -> AppResult<User> {
+    async fn schedule_purge(&self, deletion: &UserDeletion) -> AppResult<()> {
         let session = self.db.lock().await;
-        let result = User {
-            country: (*query.country).to_string(),
-            region: (*query.region).to_string(),
-            city: (*query.city).to_string(),
-            user_id: Timeuuid::from_str(&query.user_id)?,
+        match deletion.insert().execute(&session).await {
+            Ok(_) => Ok(()),
+            Err(err) => {
+                tracing::error!("{err:?}");
+                Err(anyhow!(AppError::InternalServerError))
+            }
+        }
+    }
+
+    async fn cancel_purge(&self, user_id: Timeuuid) -> AppResult<()> {
+        let session = self.db.lock().await;
+        let deletion = UserDeletion {
+            user_id,
             ..Default::default()
+        };
+        match deletion.delete().execute(&session).await {
+            Ok(_) => Ok(()),
+            Err(err) => {
+                tracing::error!("{err:?}");
+                Err(anyhow!(AppError::InternalServerError))
+            }
+        }
+    }
+
+    async fn find_due_purges(&self, now: Timestamp) -> AppResult<Vec<UserDeletion>> {
+        let mut statement = Query::new(FIND_ALL_USER_DELETIONS_QUERY);
+        statement.set_page_size(SCAN_PAGE_SIZE);
+        let mut paging_state = PagingState::start();
+        let mut due = vec![];
+
+        let session = self.db.lock().await;
+        let result: AppResult<()> = async {
+            loop {
+                let (rows, paging_state_response) = session
+                    .execute_single_page(statement.clone(), (), paging_state)
+                    .await?;
+                for deletion in rows.rows_typed::<UserDeletion>()? {
+                    let deletion = deletion?;
+                    if deletion.purge_after <= now {
+                        due.push(deletion);
+                    }
+                }
+
+                match paging_state_response {
+                    PagingStateResponse::HasMorePages { state } => paging_state = state,
+                    PagingStateResponse::NoMorePages => return Ok(()),
+                }
+            }
         }
-        .find_by_primary_key()
-        .execute(&session)
         .await;
 
         match result {
-            Ok(user) => Ok(user),
+            Ok(_) => Ok(due),
             Err(err) => {
                 tracing::error!("{err:?}");
-                Err(anyhow!(RequestFindUserError::UserNotFound))
+                Err(anyhow!(AppError::InternalServerError))
+            }
+        }
+    }
+
+    async fn purge_user<'u>(
+        &self,
+        user: &User,
+        tombstone: &'u User,
+        columns: &[UserColumn],
+    ) -> AppResult<&'u User> {
+        let session = self.db.lock().await;
+        // The tombstone, which marks the user purged, goes last so a failed
+        // purge is retried in full.
+        let result: AppResult<()> = async {
+            clear_status_notes(&session, user.user_id).await?;
+            Lookup::email(&user.email)
+                .release(&session, user.user_id)
+                .await?;
+            Lookup::user_name(&user.user_name)
+                .release(&session, user.user_id)
+                .await?;
+            update_columns(&session, tombstone, columns).await?;
+            UserDeletion {
+                user_id: user.user_id,
+                ..Default::default()
+            }
+            .delete()
+            .execute(&session)
+            .await?;
+            Ok(())
+        }
+        .await;
+
+        match result {
+            Ok(_) => Ok(tombstone),
+            Err(err) => {
+                tracing::error!("{err:?}");
+                Err(anyhow!(AppError::InternalServerError))
+            }
+        }
+    }
+
+    async fn claim_email(&self, user: &User) -> AppResult<()> {
+        let session = self.db.lock().await;
+        match Lookup::email(&user.email).claim(&session, user).await {
+            Ok(true) => Ok(()),
+            Ok(false) => Err(anyhow!(RequestCreateUserError::EmailExisted {
+                email: (*user.email).to_string()
+            })),
+            Err(err) => {
+                tracing::error!("{err:?}");
+                Err(anyhow!(AppError::InternalServerError))
+            }
+        }
+    }
+
+    async fn release_email(&self, email: &str, user_id: Timeuuid) -> AppResult<()> {
+        let session = self.db.lock().await;
+        match Lookup::email(email).release(&session, user_id).await {
+            Ok(_) => Ok(()),
+            Err(err) => {
+                tracing::error!("{err:?}");
+                Err(anyhow!(AppError::InternalServerError))
+            }
+        }
+    }
+
+    async fn find_user_by_id(&self, query: &RequestGetUserByPrimaryKey) -> AppResult<User> {
+        let user_id = parse_id("user_id", &query.user_id)?;
+        let session = self.db.lock().await;
+
+        let result: AppResult<Option<User>> = async {
+            if query.has_location() {
+                let user = User {
+                    country: (*query.country).to_string(),
+                    region: (*query.region).to_string(),
+                    city: (*query.city).to_string(),
+                    user_id,
+                    ..Default::default()
+                }
+                .maybe_find_by_primary_key()
+                .execute(&session)
+                .await?;
+                if user.is_some() {
+                    return Ok(user);
+                }
+            }
+
+            // No or a stale location: resolve it from the id mapping.
+            let location = UserById {
+                user_id,
+                ..Default::default()
+            }
+            .maybe_find_by_primary_key()
+            .execute(&session)
+            .await?;
+            let Some(location) = location else {
+                return Ok(None);
+            };
+
+            Ok(User {
+                country: location.country,
+                region: location.region,
+                city: location.city,
+                user_id,
+                ..Default::default()
+            }
+            .maybe_find_by_primary_key()
+            .execute(&session)
+            .await?)
+        }
+        .await;
+
+        match result {
+            Ok(Some(user)) => Ok(user),
+            Ok(None) => Err(anyhow!(RequestFindUserError::UserNotFound)),
+            Err(err) => {
+                tracing::error!("{err:?}");
+                Err(anyhow!(AppError::InternalServerError))
             }
         }
     }
@@ -74,15 +666,21 @@ impl UserRepository for UserRepo {
         &self,
         request_user_by_username_or_email: &RequestGetUser,
     ) -> AppResult<User> {
-        let command = match request_user_by_username_or_email.to_owned().email {
-            Some(email) => User::maybe_find_first_by_email(email),
-            None => User::maybe_find_first_by_user_name(
-                request_user_by_username_or_email.to_owned().user_name,
-            ),
+        let lookup = match &request_user_by_username_or_email.email {
+            Some(email) => Lookup::email(email),
+            None => Lookup::user_name(&request_user_by_username_or_email.user_name),
         };
 
         let session = self.db.lock().await;
-        let result = command.execute(&session).await;
+        let result = match lookup.location(&session).await {
+            Ok(Some(location)) => location
+                .maybe_find_by_primary_key()
+                .execute(&session)
+                .await
+                .map_err(Into::into),
+            Ok(None) => Ok(None),
+            Err(err) => Err(err),
+        };
 
         match result {
             Ok(user) => match user {
@@ -96,26 +694,54 @@ impl UserRepository for UserRepo {
         }
     }
 
-    async fn find_users(&self, query: &RequestGetUserByPartitionKey) -> AppResult<Vec<User>> {
+    async fn find_users(
+        &self,
+        query: &RequestGetUserByPartitionKey,
+        page_size: i32,
+        paging_state: Option<Vec<u8>>,
+    ) -> AppResult<UserPage> {
+        let mut statement = Query::new(User::FIND_BY_PARTITION_KEY_QUERY);
+        statement.set_page_size(page_size);
+
+        let paging_state = match paging_state {
+            Some(raw) => PagingState::new_from_raw_bytes(raw),
+            None => PagingState::start(),
+        };
+
         let session = self.db.lock().await;
-        let results = User {
-            country: (*query.country).to_string(),
-            region: (*query.region).to_string(),
-            city: (*query.city).to_string(),
-            ..Default::default()
-        }
-        .find_by_partition_key()
-        .execute(&session)
-        .await;
+        let result = session
+            .execute_single_page(
+                statement,
+                (&query.country, &query.region, &query.city),
+                paging_state,
+            )
+            .await;
 
-        match results {
-            Ok(value) => match value.try_collect().await {
-                Ok(val) => Ok(val),
-                Err(err) => {
-                    tracing::error!("{err:?}");
-                    Err(anyhow!(AppError::InternalServerError))
-                }
-            },
+        match result {
+            Ok((rows, paging_state_response)) => {
+                let users = rows
+                    .rows_typed::<User>()
+                    .map_err(anyhow::Error::from)
+                    .and_then(|rows| Ok(rows.collect::<Result<Vec<_>, _>>()?));
+                let users = match users {
+                    Ok(users) => users,
+                    Err(err) => {
+                        tracing::error!("{err:?}");
+                        return Err(anyhow!(AppError::InternalServerError));
+                    }
+                };
+                let paging_state = match paging_state_response {
+                    PagingStateResponse::HasMorePages { state } => {
+                        state.as_bytes_slice().map(|bytes| bytes.to_vec())
+                    }
+                    PagingStateResponse::NoMorePages => None,
+                };
+
+                Ok(UserPage {
+                    users,
+                    paging_state,
+                })
+            }
             Err(err) => {
                 tracing::error!("{err:?}");
                 Err(anyhow!(AppError::InternalServerError))
@@ -123,22 +749,89 @@ impl UserRepository for UserRepo {
         }
     }
 
-    async fn push_new_user_status(&self, payload: &RequestUpdateUserStatus) -> AppResult<bool> {
+    async fn push_new_user_status(
+        &self,
+        payload: &RequestUpdateUserStatus,
+        from: &UserStatus,
+    ) -> AppResult<bool> {
+        let status = UserStatus::parse(Some(&payload.status))?;
+        let user_id = parse_id("user_id", &payload.user_id)?;
+        let actor_id = payload
+            .actor_id
+            .as_deref()
+            .map(|actor_id| parse_id("actor_id", actor_id))
+            .transpose()?;
+        let change = UserStatusChange::new(user_id, &status, actor_id, payload.note.clone());
+
         let session = self.db.lock().await;
-        match session
-            .execute_unpaged(
-                User::PUSH_STATUS_IF_EXISTS_QUERY,
-                (
-                    vec![&payload.status.to_string()],
-                    &payload.country,
-                    &payload.region,
-                    &payload.city,
-                    Timeuuid::from_str(&payload.user_id)?,
-                ),
-            )
-            .await
-        {
-            Ok(_) => Ok(true),
+        let result: AppResult<bool> = async {
+            let result = session
+                .execute_unpaged(
+                    SET_LATEST_STATUS_IF_UNCHANGED_QUERY,
+                    (
+                        &change.status,
+                        &change.reason,
+                        change.changed_at,
+                        &payload.country,
+                        &payload.region,
+                        &payload.city,
+                        user_id,
+                        from.name(),
+                        ReasonOfStatus::transform(from.reason()),
+                    ),
+                )
+                .await?;
+            if !is_applied(result) {
+                return Ok(false);
+            }
+
+            change.insert().execute(&session).await?;
+            Ok(true)
+        }
+        .await;
+
+        result.map_err(|err| {
+            tracing::error!("{err:?}");
+            anyhow!(AppError::InternalServerError)
+        })
+    }
+
+    async fn find_status_history(
+        &self,
+        user_id: Timeuuid,
+        page_size: i32,
+        paging_state: Option<Vec<u8>>,
+    ) -> AppResult<StatusHistoryPage> {
+        let mut statement = Query::new(UserStatusChange::FIND_BY_PARTITION_KEY_QUERY);
+        statement.set_page_size(page_size);
+
+        let paging_state = match paging_state {
+            Some(raw) => PagingState::new_from_raw_bytes(raw),
+            None => PagingState::start(),
+        };
+
+        let session = self.db.lock().await;
+        let result = session
+            .execute_single_page(statement, (user_id,), paging_state)
+            .await;
+
+        match result {
+            Ok((rows, paging_state_response)) => {
+                let changes = rows
+                    .rows_typed::<UserStatusChange>()?
+                    .collect::<Result<Vec<_>, _>>()?;
+                let paging_state = match paging_state_response {
+                    PagingStateResponse::HasMorePages { state } => {
+                        state.as_bytes_slice().map(|bytes| bytes.to_vec())
+                    }
+                    PagingStateResponse::NoMorePages => None,
+                };
+
+                Ok(StatusHistoryPage {
+                    changes,
+                    paging_state,
+                })
+            }
             Err(err) => {
                 tracing::error!("{err:?}");
                 Err(anyhow!(AppError::InternalServerError))
@@ -146,9 +839,13 @@ impl UserRepository for UserRepo {
         }
     }
 
-    async fn update_user<'u>(&self, user: &'u User) -> AppResult<&'u User> {
+    async fn update_user_columns<'u>(
+        &self,
+        user: &'u User,
+        columns: &[UserColumn],
+    ) -> AppResult<&'u User> {
         let session = self.db.lock().await;
-        match user.update().execute(&session).await {
+        match update_columns(&session, user, columns).await {
             Ok(_) => Ok(user),
             Err(err) => {
                 tracing::error!("{err:?}");
@@ -158,6 +855,102 @@ impl UserRepository for UserRepo {
     }
 }
 
+/// Writes `columns` of `user` in one UPDATE. Every other column, the status
+/// and the lists among them, keeps its stored value.
+async fn update_columns(
+    session: &CachingSession,
+    user: &User,
+    columns: &[UserColumn],
+) -> AppResult<()> {
+    let mut assignments = vec![];
+    let mut values = vec![];
+    for column in columns {
+        let assignment = format!("{} = ?", column.name());
+        if !assignments.contains(&assignment) {
+            assignments.push(assignment);
+            values.push(column_value(user, *column));
+        }
+    }
+    if assignments.is_empty() {
+        return Ok(());
+    }
+
+    values.extend([
+        Some(CqlValue::Text((*user.country).to_string())),
+        Some(CqlValue::Text((*user.region).to_string())),
+        Some(CqlValue::Text((*user.city).to_string())),
+        Some(CqlValue::Timeuuid(Uuid::from(user.user_id).into())),
+    ]);
+    let query = format!(
+        "UPDATE uptop.users SET {} WHERE country = ? AND region = ? AND city = ? AND user_id = ?;",
+        assignments.join(", ")
+    );
+    session.execute_unpaged(query, values).await?;
+    Ok(())
+}
+
+/// Clears the notes on the status history of `user_id`, which are free text
+/// and may hold personal data. The statuses themselves are kept.
+async fn clear_status_notes(session: &CachingSession, user_id: Timeuuid) -> AppResult<()> {
+    let mut statement = Query::new(FIND_STATUS_NOTES_QUERY);
+    statement.set_page_size(SCAN_PAGE_SIZE);
+    let mut paging_state = PagingState::start();
+
+    loop {
+        let (rows, paging_state_response) = session
+            .execute_single_page(statement.clone(), (user_id,), paging_state)
+            .await?;
+
+        for row in rows.rows_typed::<(Timeuuid, Option<String>)>()? {
+            let (change_id, note) = row?;
+            if note.is_some() {
+                session
+                    .execute_unpaged(CLEAR_STATUS_NOTE_QUERY, (user_id, change_id))
+                    .await?;
+            }
+        }
+
+        match paging_state_response {
+            PagingStateResponse::HasMorePages { state } => paging_state = state,
+            PagingStateResponse::NoMorePages => return Ok(()),
+        }
+    }
+}
+
+/// The value `column` has in `user`, `None` for null.
+fn column_value(user: &User, column: UserColumn) -> Option<CqlValue> {
+    let text = |value: &str| CqlValue::Text(value.to_owned());
+    let timestamp = |value: &Timestamp| CqlValue::Timestamp(CqlTimestamp(value.timestamp_millis()));
+
+    match column {
+        UserColumn::DisplayName => user.display_name.as_deref().map(text),
+        UserColumn::Email => Some(text(&user.email)),
+        UserColumn::Password => Some(text(&user.password)),
+        UserColumn::Role => Some(text(&user.role)),
+        UserColumn::PhoneNumber => user.phone_number.as_deref().map(text),
+        UserColumn::Language => user.language.as_deref().map(text),
+        UserColumn::Address => user.address.as_deref().map(text),
+        UserColumn::OtherEmails => user
+            .other_emails
+            .as_ref()
+            .map(|emails| CqlValue::List(emails.iter().map(|email| text(email)).collect())),
+        UserColumn::EmailVerifyCode => user.email_verify_code.as_deref().map(text),
+        UserColumn::EmailVerifyCodeExpiresAt => {
+            user.email_verify_code_expires_at.as_ref().map(timestamp)
+        }
+        UserColumn::EmailVerifiedAt => user.email_verified_at.as_ref().map(timestamp),
+        UserColumn::PasswordRecoveryCode => user.password_recovery_code.as_deref().map(text),
+        UserColumn::PasswordRecoveryCodeExpiresAt => user
+            .password_recovery_code_expires_at
+            .as_ref()
+            .map(timestamp),
+        UserColumn::PasswordRecoveredAt => user.password_recovered_at.as_ref().map(timestamp),
+        UserColumn::DeletedAt => user.deleted_at.as_ref().map(timestamp),
+        UserColumn::PurgedAt => user.purged_at.as_ref().map(timestamp),
+        UserColumn::UpdatedAt => Some(timestamp(&user.updated_at)),
+    }
+}
+
 static CREATE_USER_TABLE_QUERY: &str = r#"
     CREATE TABLE IF NOT EXISTS uptop.users (
         user_id timeuuid,
@@ -165,7 +958,9 @@ static CREATE_USER_TABLE_QUERY: &str = r#"
         display_name text,
         email text,
         password text,
-        status list<text>,
+        latest_status text,
+        latest_status_reason text,
+        latest_status_at timestamp,
         role text,
         phone_number text,
         language text,
@@ -180,9 +975,13 @@ static CREATE_USER_TABLE_QUERY: &str = r#"
         active_organization timeuuid,
         other_emails list<text>,
         email_verify_code text,
+        email_verify_code_expires_at timestamp,
         email_verified_at timestamp,
         password_recovery_code text,
+        password_recovery_code_expires_at timestamp,
         password_recovered_at timestamp,
+        deleted_at timestamp,
+        purged_at timestamp,
         created_at timestamp,
         updated_at timestamp,
         PRIMARY KEY ((country, region, city), user_id)
@@ -193,10 +992,169 @@ static CREATE_USER_ID_INDEX: &str = r#"
     CREATE INDEX IF NOT EXISTS uptop_user_id_index ON uptop.users (user_id);
 "#;
 
-static CREATE_USER_EMAIL_INDEX: &str = r#"
-    CREATE INDEX IF NOT EXISTS uptop_email_index ON uptop.users (email);
+static DROP_USER_EMAIL_INDEX: &str = r#"
+    DROP INDEX IF EXISTS uptop.uptop_email_index;
+"#;
+
+static DROP_USER_NAME_INDEX: &str = r#"
+    DROP INDEX IF EXISTS uptop.uptop_user_name_index;
+"#;
+
+static CREATE_USER_BY_EMAIL_TABLE_QUERY: &str = r#"
+    CREATE TABLE IF NOT EXISTS uptop.users_by_email (
+        email text,
+        user_id timeuuid,
+        country text,
+        region text,
+        city text,
+        PRIMARY KEY (email)
+    );
+"#;
+
+static CREATE_USER_BY_USER_NAME_TABLE_QUERY: &str = r#"
+    CREATE TABLE IF NOT EXISTS uptop.users_by_user_name (
+        user_name text,
+        user_id timeuuid,
+        country text,
+        region text,
+        city text,
+        PRIMARY KEY (user_name)
+    );
+"#;
+
+const INSERT_USER_BY_EMAIL_QUERY: &str = r#"
+    INSERT INTO uptop.users_by_email (email, user_id, country, region, city)
+    VALUES (?, ?, ?, ?, ?) IF NOT EXISTS;
+"#;
+
+const INSERT_USER_BY_USER_NAME_QUERY: &str = r#"
+    INSERT INTO uptop.users_by_user_name (user_name, user_id, country, region, city)
+    VALUES (?, ?, ?, ?, ?) IF NOT EXISTS;
+"#;
+
+static DELETE_USER_BY_EMAIL_QUERY: &str = r#"
+    DELETE FROM uptop.users_by_email WHERE email = ? IF user_id = ?;
+"#;
+
+static DELETE_USER_BY_USER_NAME_QUERY: &str = r#"
+    DELETE FROM uptop.users_by_user_name WHERE user_name = ? IF user_id = ?;
+"#;
+
+static CREATE_USER_BY_ID_TABLE_QUERY: &str = r#"
+    CREATE TABLE IF NOT EXISTS uptop.users_by_id (
+        user_id timeuuid,
+        country text,
+        region text,
+        city text,
+        PRIMARY KEY (user_id)
+    );
 "#;
 
-static CREATE_USER_NAME_INDEX: &str = r#"
-    CREATE INDEX IF NOT EXISTS uptop_user_name_index ON uptop.users (user_name);
+static MOVE_USER_BY_EMAIL_QUERY: &str = r#"
+    UPDATE uptop.users_by_email SET country = ?, region = ?, city = ?
+    WHERE email = ? IF user_id = ?;
 "#;
+
+static MOVE_USER_BY_USER_NAME_QUERY: &str = r#"
+    UPDATE uptop.users_by_user_name SET country = ?, region = ?, city = ?
+    WHERE user_name = ? IF user_id = ?;
+"#;
+
+static CREATE_USER_DELETION_TABLE_QUERY: &str = r#"
+    CREATE TABLE IF NOT EXISTS uptop.user_deletions (
+        user_id timeuuid,
+        country text,
+        region text,
+        city text,
+        deleted_at timestamp,
+        purge_after timestamp,
+        PRIMARY KEY (user_id)
+    );
+"#;
+
+static FIND_ALL_USER_DELETIONS_QUERY: &str = r#"
+    SELECT user_id, country, region, city, deleted_at, purge_after FROM uptop.user_deletions;
+"#;
+
+static CREATE_USER_STATUS_HISTORY_TABLE_QUERY: &str = r#"
+    CREATE TABLE IF NOT EXISTS uptop.user_status_history (
+        user_id timeuuid,
+        change_id timeuuid,
+        status text,
+        reason text,
+        actor_id timeuuid,
+        note text,
+        changed_at timestamp,
+        PRIMARY KEY ((user_id), change_id)
+    ) WITH CLUSTERING ORDER BY (change_id DESC);
+"#;
+
+static FIND_STATUS_NOTES_QUERY: &str = r#"
+    SELECT change_id, note FROM uptop.user_status_history WHERE user_id = ?;
+"#;
+
+static CLEAR_STATUS_NOTE_QUERY: &str = r#"
+    DELETE note FROM uptop.user_status_history WHERE user_id = ? AND change_id = ?;
+"#;
+
+static DELETE_USER_STATUS_HISTORY_QUERY: &str = r#"
+    DELETE FROM uptop.user_status_history WHERE user_id = ?;
+"#;
+
+static SET_LATEST_STATUS_QUERY: &str = r#"
+    UPDATE uptop.users SET latest_status = ?, latest_status_reason = ?, latest_status_at = ?
+    WHERE country = ? AND region = ? AND city = ? AND user_id = ?;
+"#;
+
+static SET_LATEST_STATUS_IF_UNCHANGED_QUERY: &str = r#"
+    UPDATE uptop.users SET latest_status = ?, latest_status_reason = ?, latest_status_at = ?
+    WHERE country = ? AND region = ? AND city = ? AND user_id = ?
+    IF latest_status = ? AND latest_status_reason = ?;
+"#;
+
+static FIND_ALL_LEGACY_STATUSES_QUERY: &str = r#"
+    SELECT country, region, city, user_id, status, latest_status FROM uptop.users;
+"#;
+
+static FIND_ALL_USER_KEYS_QUERY: &str = r#"
+    SELECT user_id, user_name, email, country, region, city, purged_at FROM uptop.users;
+"#;
+
+/// Page size of full table scans.
+const SCAN_PAGE_SIZE: i32 = 500;
+
+/// `uptop.schema_migrations` names of the one-off data migrations.
+const LEGACY_STATUSES_MIGRATION: &str = "users_legacy_statuses";
+const LOOKUP_TABLES_MIGRATION: &str = "users_lookup_tables";
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn timeuuid_timestamp_reads_version_1_uuids() {
+        let expected: Timestamp = "2024-01-01T00:00:00.123456Z".parse().unwrap();
+
+        assert_eq!(
+            timeuuid_timestamp("b4df5680-a838-11ee-8000-1234567890ab"),
+            Some(expected)
+        );
+        assert_eq!(
+            timeuuid_timestamp("B4DF5680A83811EE80001234567890AB"),
+            Some(expected)
+        );
+    }
+
+    #[test]
+    fn timeuuid_timestamp_rejects_other_input() {
+        for input in [
+            "b4df5680-a838-41ee-8000-1234567890ab",
+            "b4df5680-a838-11ee-8000-1234567890",
+            "zzdf5680-a838-11ee-8000-1234567890ab",
+            "00000000-0000-1000-8000-000000000000",
+            "",
+        ] {
+            assert_eq!(timeuuid_timestamp(input), None, "{input}");
+        }
+    }
+}
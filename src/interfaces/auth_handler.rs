@@ -0,0 +1,147 @@
+use crate::application::{
+    access::{
+        app::{authorize, authorize_user, AccessAppInterface, Actor, Resource},
+        permission::{Permission, PermissionError},
+    },
+    auth::{
+        app::AuthAppInterface,
+        request::{
+            RequestConfirmPasswordReset, RequestConfirmTotp, RequestEnrollTotp, RequestLogin,
+            RequestPasswordReset, RequestRefreshToken, RequestResetMfa, RequestUnlockUser,
+            RequestVerifyMfa,
+        },
+        response::{AuthenticatedUser, LoginOutcome, TotpEnrollment},
+        token::AccessClaims,
+    },
+};
+use anyhow::bail;
+use std::sync::Arc;
+use uptop_core::common::result::AppResult;
+
+/// Signing in and recovering a password are public. Second factors are
+/// enrolled by the user alone and reset by whoever may update the user, and
+/// unlocking a user needs a global role.
+#[derive(Clone, Debug)]
+pub struct AuthHandler<AA: AuthAppInterface, AC: AccessAppInterface> {
+    pub auth_app: Arc<AA>,
+    pub access_app: Arc<AC>,
+}
+
+pub async fn on_login<AA: AuthAppInterface, AC: AccessAppInterface>(
+    handler: AuthHandler<AA, AC>,
+    body: RequestLogin,
+) -> AppResult<LoginOutcome> {
+    let req = body.try_into_domain()?;
+    handler.auth_app.login(req).await
+}
+
+pub async fn on_validate_token<AA: AuthAppInterface, AC: AccessAppInterface>(
+    handler: AuthHandler<AA, AC>,
+    token: String,
+) -> AppResult<AccessClaims> {
+    handler.auth_app.validate_token(&token)
+}
+
+pub async fn on_refresh<AA: AuthAppInterface, AC: AccessAppInterface>(
+    handler: AuthHandler<AA, AC>,
+    body: RequestRefreshToken,
+) -> AppResult<AuthenticatedUser> {
+    let req = body.try_into_domain()?;
+    handler.auth_app.refresh(req).await
+}
+
+pub async fn on_logout<AA: AuthAppInterface, AC: AccessAppInterface>(
+    handler: AuthHandler<AA, AC>,
+    body: RequestRefreshToken,
+) -> AppResult<bool> {
+    let req = body.try_into_domain()?;
+    handler.auth_app.logout(req).await
+}
+
+pub async fn on_request_password_reset<AA: AuthAppInterface, AC: AccessAppInterface>(
+    handler: AuthHandler<AA, AC>,
+    body: RequestPasswordReset,
+) -> AppResult<()> {
+    let req = body.try_into_domain()?;
+    handler.auth_app.request_password_reset(req).await
+}
+
+pub async fn on_confirm_password_reset<AA: AuthAppInterface, AC: AccessAppInterface>(
+    handler: AuthHandler<AA, AC>,
+    body: RequestConfirmPasswordReset,
+) -> AppResult<()> {
+    let req = body.try_into_domain()?;
+    handler.auth_app.confirm_password_reset(req).await
+}
+
+pub async fn on_enroll_totp<AA: AuthAppInterface, AC: AccessAppInterface>(
+    handler: AuthHandler<AA, AC>,
+    caller: Option<Actor>,
+    body: RequestEnrollTotp,
+) -> AppResult<TotpEnrollment> {
+    authorize_owner(caller.as_ref(), &body.user_id)?;
+    let req = body.try_into_domain()?;
+    handler.auth_app.enroll_totp(req).await
+}
+
+pub async fn on_confirm_totp<AA: AuthAppInterface, AC: AccessAppInterface>(
+    handler: AuthHandler<AA, AC>,
+    caller: Option<Actor>,
+    body: RequestConfirmTotp,
+) -> AppResult<Vec<String>> {
+    authorize_owner(caller.as_ref(), &body.user_id)?;
+    let req = body.try_into_domain()?;
+    handler.auth_app.confirm_totp(req).await
+}
+
+pub async fn on_verify_mfa<AA: AuthAppInterface, AC: AccessAppInterface>(
+    handler: AuthHandler<AA, AC>,
+    body: RequestVerifyMfa,
+) -> AppResult<AuthenticatedUser> {
+    let req = body.try_into_domain()?;
+    handler.auth_app.verify_mfa(req).await
+}
+
+pub async fn on_reset_mfa<AA: AuthAppInterface, AC: AccessAppInterface>(
+    handler: AuthHandler<AA, AC>,
+    caller: Option<Actor>,
+    body: RequestResetMfa,
+) -> AppResult<bool> {
+    authorize_user(
+        handler.access_app.as_ref(),
+        caller.as_ref(),
+        Permission::UserUpdate,
+        &body.user_id,
+    )
+    .await?;
+    let req = body.try_into_domain()?;
+    handler.auth_app.reset_mfa(req).await
+}
+
+pub async fn on_unlock_user<AA: AuthAppInterface, AC: AccessAppInterface>(
+    handler: AuthHandler<AA, AC>,
+    caller: Option<Actor>,
+    body: RequestUnlockUser,
+) -> AppResult<bool> {
+    authorize(
+        handler.access_app.as_ref(),
+        caller.as_ref(),
+        Permission::UserUpdateStatus,
+        Resource::AllUsers,
+    )
+    .await?;
+    let req = body.try_into_domain()?;
+    handler.auth_app.unlock_user(req).await
+}
+
+/// Fails unless `caller` is the user `user_id`. A factor enrolled by anyone
+/// else would let them pass the second step as the user.
+fn authorize_owner(caller: Option<&Actor>, user_id: &str) -> AppResult<()> {
+    match caller {
+        None => bail!(PermissionError::Unauthenticated),
+        Some(caller) if caller.is_user(user_id) => Ok(()),
+        Some(_) => bail!(PermissionError::Denied {
+            permission: Permission::UserUpdate
+        }),
+    }
+}
@@ -0,0 +1,160 @@
+use crate::application::{
+    access::{
+        app::{authorize, authorize_user, AccessAppInterface, Actor, Resource},
+        permission::{Permission, PermissionError},
+    },
+    delegation::{
+        app::DelegationAppInterface,
+        request::{
+            RequestDisableManagedUser, RequestEnableManagedUser, RequestGetDelegationAudit,
+            RequestGrantDelegation, RequestListManagedUsers, RequestResetManagedUserPassword,
+            RequestRevokeDelegation,
+        },
+        response::{PublicDelegationAuditPage, PublicManagedUser},
+    },
+    topic::response::PublicUser,
+};
+use anyhow::bail;
+use std::sync::Arc;
+use uptop_core::common::result::AppResult;
+
+/// Every call needs a caller. Granting and revoking managers needs
+/// `user.delegate` on the managed user, which its owners have; managers can
+/// always step down and list the users they manage themselves.
+#[derive(Clone, Debug)]
+pub struct DelegationHandler<DA: DelegationAppInterface, AA: AccessAppInterface> {
+    pub delegation_app: Arc<DA>,
+    pub access_app: Arc<AA>,
+}
+
+pub async fn on_grant_delegation<DA: DelegationAppInterface, AA: AccessAppInterface>(
+    handler: DelegationHandler<DA, AA>,
+    caller: Option<Actor>,
+    body: RequestGrantDelegation,
+) -> AppResult<PublicUser> {
+    let actor = authorize_user(
+        handler.access_app.as_ref(),
+        caller.as_ref(),
+        Permission::UserDelegate,
+        &body.user_id,
+    )
+    .await?;
+    let req = body.try_into_domain()?;
+    handler.delegation_app.grant_delegation(&actor, req).await
+}
+
+pub async fn on_revoke_delegation<DA: DelegationAppInterface, AA: AccessAppInterface>(
+    handler: DelegationHandler<DA, AA>,
+    caller: Option<Actor>,
+    body: RequestRevokeDelegation,
+) -> AppResult<PublicUser> {
+    let actor = match &caller {
+        Some(actor) if actor.is_user(&body.manager_id) => actor.clone(),
+        _ => {
+            authorize_user(
+                handler.access_app.as_ref(),
+                caller.as_ref(),
+                Permission::UserDelegate,
+                &body.user_id,
+            )
+            .await?
+        }
+    };
+    let req = body.try_into_domain()?;
+    handler.delegation_app.revoke_delegation(&actor, req).await
+}
+
+pub async fn on_list_managed_users<DA: DelegationAppInterface, AA: AccessAppInterface>(
+    handler: DelegationHandler<DA, AA>,
+    caller: Option<Actor>,
+    query: RequestListManagedUsers,
+) -> AppResult<Vec<PublicManagedUser>> {
+    match &caller {
+        None => bail!(PermissionError::Unauthenticated),
+        Some(actor) if actor.is_user(&query.manager_id) => {}
+        Some(_) => {
+            authorize(
+                handler.access_app.as_ref(),
+                caller.as_ref(),
+                Permission::UserRead,
+                Resource::AllUsers,
+            )
+            .await?;
+        }
+    }
+    let query = query.try_into_domain()?;
+    handler.delegation_app.list_managed_users(query).await
+}
+
+pub async fn on_reset_managed_user_password<DA: DelegationAppInterface, AA: AccessAppInterface>(
+    handler: DelegationHandler<DA, AA>,
+    caller: Option<Actor>,
+    body: RequestResetManagedUserPassword,
+) -> AppResult<PublicUser> {
+    let actor = authorize_user(
+        handler.access_app.as_ref(),
+        caller.as_ref(),
+        Permission::UserResetPassword,
+        &body.user_id,
+    )
+    .await?;
+    let req = body.try_into_domain()?;
+    handler
+        .delegation_app
+        .reset_managed_user_password(&actor, req)
+        .await
+}
+
+pub async fn on_disable_managed_user<DA: DelegationAppInterface, AA: AccessAppInterface>(
+    handler: DelegationHandler<DA, AA>,
+    caller: Option<Actor>,
+    body: RequestDisableManagedUser,
+) -> AppResult<PublicUser> {
+    let actor = authorize_user(
+        handler.access_app.as_ref(),
+        caller.as_ref(),
+        Permission::UserDisable,
+        &body.user_id,
+    )
+    .await?;
+    let req = body.try_into_domain()?;
+    handler
+        .delegation_app
+        .disable_managed_user(&actor, req)
+        .await
+}
+
+pub async fn on_enable_managed_user<DA: DelegationAppInterface, AA: AccessAppInterface>(
+    handler: DelegationHandler<DA, AA>,
+    caller: Option<Actor>,
+    body: RequestEnableManagedUser,
+) -> AppResult<PublicUser> {
+    let actor = authorize_user(
+        handler.access_app.as_ref(),
+        caller.as_ref(),
+        Permission::UserDisable,
+        &body.user_id,
+    )
+    .await?;
+    let req = body.try_into_domain()?;
+    handler
+        .delegation_app
+        .enable_managed_user(&actor, req)
+        .await
+}
+
+pub async fn on_get_delegation_audit<DA: DelegationAppInterface, AA: AccessAppInterface>(
+    handler: DelegationHandler<DA, AA>,
+    caller: Option<Actor>,
+    query: RequestGetDelegationAudit,
+) -> AppResult<PublicDelegationAuditPage> {
+    authorize_user(
+        handler.access_app.as_ref(),
+        caller.as_ref(),
+        Permission::UserRead,
+        &query.user_id,
+    )
+    .await?;
+    let query = query.try_into_domain()?;
+    handler.delegation_app.get_delegation_audit(query).await
+}
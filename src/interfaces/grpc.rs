@@ -0,0 +1,22 @@
+pub mod auth_service;
+mod convert;
+pub mod delegation_service;
+pub mod interceptor;
+pub mod message_service;
+mod metadata;
+pub mod organization_service;
+mod status;
+pub mod user_service;
+
+pub mod message {
+    tonic::include_proto!("message");
+}
+
+pub mod identity {
+    pub mod v1 {
+        tonic::include_proto!("identity.v1");
+    }
+}
+
+pub const FILE_DESCRIPTOR_SET: &[u8] =
+    tonic::include_file_descriptor_set!("identification_descriptor");
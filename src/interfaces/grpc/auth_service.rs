@@ -0,0 +1,171 @@
+use super::{
+    identity::v1::{
+        auth_service_server::AuthService, ConfirmPasswordResetRequest,
+        ConfirmPasswordResetResponse, ConfirmTotpRequest, ConfirmTotpResponse, EnrollTotpRequest,
+        EnrollTotpResponse, LoginRequest, LoginResponse, LogoutRequest, LogoutResponse,
+        RefreshRequest, RequestPasswordResetRequest, RequestPasswordResetResponse, ResetMfaRequest,
+        ResetMfaResponse, UnlockUserRequest, UnlockUserResponse, ValidateTokenRequest,
+        ValidateTokenResponse, VerifyMfaRequest,
+    },
+    metadata::{caller, client_ip},
+    status::into_status,
+};
+use crate::{
+    application::{
+        access::app::AccessAppInterface,
+        auth::{app::AuthAppInterface, request::RequestLogin},
+    },
+    interfaces::auth_handler::{
+        on_confirm_password_reset, on_confirm_totp, on_enroll_totp, on_login, on_logout,
+        on_refresh, on_request_password_reset, on_reset_mfa, on_unlock_user, on_validate_token,
+        on_verify_mfa, AuthHandler,
+    },
+};
+use tonic::{Request, Response, Status};
+
+#[derive(Clone, Debug)]
+pub struct AuthGrpcService<AA: AuthAppInterface, AC: AccessAppInterface> {
+    handler: AuthHandler<AA, AC>,
+}
+
+impl<AA: AuthAppInterface, AC: AccessAppInterface> AuthGrpcService<AA, AC> {
+    pub fn new(handler: AuthHandler<AA, AC>) -> Self {
+        Self { handler }
+    }
+}
+
+#[tonic::async_trait]
+impl<AA: AuthAppInterface, AC: AccessAppInterface> AuthService for AuthGrpcService<AA, AC> {
+    async fn login(
+        &self,
+        request: Request<LoginRequest>,
+    ) -> Result<Response<LoginResponse>, Status> {
+        let client_ip = client_ip(&request);
+        let body = RequestLogin {
+            client_ip,
+            ..request.into_inner().into()
+        };
+        let authenticated = on_login(self.handler.clone(), body)
+            .await
+            .map_err(into_status)?;
+
+        Ok(Response::new(authenticated.into()))
+    }
+
+    async fn validate_token(
+        &self,
+        request: Request<ValidateTokenRequest>,
+    ) -> Result<Response<ValidateTokenResponse>, Status> {
+        let claims = on_validate_token(self.handler.clone(), request.into_inner().access_token)
+            .await
+            .map_err(into_status)?;
+
+        Ok(Response::new(claims.into()))
+    }
+
+    async fn refresh(
+        &self,
+        request: Request<RefreshRequest>,
+    ) -> Result<Response<LoginResponse>, Status> {
+        let authenticated = on_refresh(self.handler.clone(), request.into_inner().into())
+            .await
+            .map_err(into_status)?;
+
+        Ok(Response::new(authenticated.into()))
+    }
+
+    async fn logout(
+        &self,
+        request: Request<LogoutRequest>,
+    ) -> Result<Response<LogoutResponse>, Status> {
+        let logged_out = on_logout(self.handler.clone(), request.into_inner().into())
+            .await
+            .map_err(into_status)?;
+
+        Ok(Response::new(LogoutResponse { logged_out }))
+    }
+
+    async fn request_password_reset(
+        &self,
+        request: Request<RequestPasswordResetRequest>,
+    ) -> Result<Response<RequestPasswordResetResponse>, Status> {
+        on_request_password_reset(self.handler.clone(), request.into_inner().into())
+            .await
+            .map_err(into_status)?;
+
+        Ok(Response::new(RequestPasswordResetResponse {
+            accepted: true,
+        }))
+    }
+
+    async fn confirm_password_reset(
+        &self,
+        request: Request<ConfirmPasswordResetRequest>,
+    ) -> Result<Response<ConfirmPasswordResetResponse>, Status> {
+        on_confirm_password_reset(self.handler.clone(), request.into_inner().into())
+            .await
+            .map_err(into_status)?;
+
+        Ok(Response::new(ConfirmPasswordResetResponse { reset: true }))
+    }
+
+    async fn enroll_totp(
+        &self,
+        request: Request<EnrollTotpRequest>,
+    ) -> Result<Response<EnrollTotpResponse>, Status> {
+        let caller = caller(&request);
+        let enrollment = on_enroll_totp(self.handler.clone(), caller, request.into_inner().into())
+            .await
+            .map_err(into_status)?;
+
+        Ok(Response::new(enrollment.into()))
+    }
+
+    async fn confirm_totp(
+        &self,
+        request: Request<ConfirmTotpRequest>,
+    ) -> Result<Response<ConfirmTotpResponse>, Status> {
+        let caller = caller(&request);
+        let backup_codes =
+            on_confirm_totp(self.handler.clone(), caller, request.into_inner().into())
+                .await
+                .map_err(into_status)?;
+
+        Ok(Response::new(ConfirmTotpResponse { backup_codes }))
+    }
+
+    async fn verify_mfa(
+        &self,
+        request: Request<VerifyMfaRequest>,
+    ) -> Result<Response<LoginResponse>, Status> {
+        let authenticated = on_verify_mfa(self.handler.clone(), request.into_inner().into())
+            .await
+            .map_err(into_status)?;
+
+        Ok(Response::new(authenticated.into()))
+    }
+
+    async fn reset_mfa(
+        &self,
+        request: Request<ResetMfaRequest>,
+    ) -> Result<Response<ResetMfaResponse>, Status> {
+        let caller = caller(&request);
+        let reset = on_reset_mfa(self.handler.clone(), caller, request.into_inner().into())
+            .await
+            .map_err(into_status)?;
+
+        Ok(Response::new(ResetMfaResponse { reset }))
+    }
+
+    async fn unlock_user(
+        &self,
+        request: Request<UnlockUserRequest>,
+    ) -> Result<Response<UnlockUserResponse>, Status> {
+        let caller = caller(&request);
+        let unlocked = on_unlock_user(self.handler.clone(), caller, request.into_inner().into())
+            .await
+            .map_err(into_status)?;
+
+        Ok(Response::new(UnlockUserResponse { unlocked }))
+    }
+}
@@ -0,0 +1,690 @@
+use super::identity::v1 as proto;
+use crate::application::{
+    auth::{
+        request::{
+            RequestConfirmPasswordReset, RequestConfirmTotp, RequestEnrollTotp, RequestLogin,
+            RequestPasswordReset, RequestRefreshToken, RequestResetMfa, RequestUnlockUser,
+            RequestVerifyMfa,
+        },
+        response::{AuthenticatedUser, LoginOutcome, TotpEnrollment},
+        token::AccessClaims,
+    },
+    delegation::{
+        request::{
+            RequestDisableManagedUser, RequestEnableManagedUser, RequestGetDelegationAudit,
+            RequestGrantDelegation, RequestListManagedUsers, RequestResetManagedUserPassword,
+            RequestRevokeDelegation,
+        },
+        response::{PublicDelegationAuditEntry, PublicDelegationAuditPage, PublicManagedUser},
+    },
+    organization::{
+        request::{
+            RequestAcceptInvitation, RequestAddOrganizationMember, RequestCreateInvitation,
+            RequestCreateOrganization, RequestDeleteOrganization, RequestGetOrganization,
+            RequestListInvitations, RequestListOrganizationMembers, RequestListUserOrganizations,
+            RequestRemoveOrganizationMember, RequestRevokeInvitation,
+            RequestSwitchActiveOrganization, RequestUpdateOrganization,
+        },
+        response::{
+            PublicOrganization, PublicOrganizationInvitation, PublicOrganizationMember,
+            PublicOrganizationMemberPage,
+        },
+    },
+    topic::{
+        request::{
+            RequestChangePassword, RequestCreateUser, RequestDeleteUser, RequestGetUser,
+            RequestGetUserByPrimaryKey, RequestGetUserStatusHistory, RequestListUsers,
+            RequestMoveUserLocation, RequestPurgeUser, RequestRestoreUser, RequestUpdateUser,
+            RequestUpdateUserStatus, RequestVerifyEmail,
+        },
+        response::{PublicUser, PublicUserPage, PublicUserStatus, PublicUserStatusPage},
+    },
+};
+
+impl From<proto::CreateUserRequest> for RequestCreateUser {
+    fn from(value: proto::CreateUserRequest) -> Self {
+        Self {
+            company_id: (!value.company_id.is_empty()).then_some(value.company_id),
+            invitation_token: value.invitation_token,
+            user_name: value.user_name,
+            email: value.email,
+            password: value.password,
+            status: value.status,
+            role: value.role,
+            display_name: value.display_name,
+            phone_number: value.phone_number,
+            language: value.language,
+            address: value.address,
+            country: value.country,
+            region: value.region,
+            city: value.city,
+            post_code: value.post_code,
+        }
+    }
+}
+
+impl From<proto::GetUserRequest> for RequestGetUser {
+    fn from(value: proto::GetUserRequest) -> Self {
+        Self {
+            user_name: value.user_name,
+            email: value.email,
+        }
+    }
+}
+
+impl From<proto::GetUserByIdRequest> for RequestGetUserByPrimaryKey {
+    fn from(value: proto::GetUserByIdRequest) -> Self {
+        Self {
+            country: value.country,
+            region: value.region,
+            city: value.city,
+            user_id: value.user_id,
+        }
+    }
+}
+
+impl From<proto::ListUsersRequest> for RequestListUsers {
+    fn from(value: proto::ListUsersRequest) -> Self {
+        Self {
+            country: value.country,
+            region: value.region,
+            city: value.city,
+            page_size: value.page_size,
+            page_token: value.page_token,
+        }
+    }
+}
+
+impl From<proto::UpdateUserRequest> for RequestUpdateUser {
+    fn from(value: proto::UpdateUserRequest) -> Self {
+        Self {
+            country: value.country,
+            region: value.region,
+            city: value.city,
+            user_id: value.user_id,
+            email: value.email,
+            role: value.role,
+            display_name: value.display_name,
+            phone_number: value.phone_number,
+            language: value.language,
+            address: value.address,
+            other_emails: value.other_emails.map(|emails| emails.values),
+        }
+    }
+}
+
+impl From<proto::UpdateUserStatusRequest> for RequestUpdateUserStatus {
+    fn from(value: proto::UpdateUserStatusRequest) -> Self {
+        Self {
+            status: value.status,
+            country: value.country,
+            region: value.region,
+            city: value.city,
+            user_id: value.user_id,
+            actor_id: value.actor_id,
+            note: value.note,
+        }
+    }
+}
+
+impl From<proto::VerifyEmailRequest> for RequestVerifyEmail {
+    fn from(value: proto::VerifyEmailRequest) -> Self {
+        Self {
+            email: value.email,
+            code: value.code,
+        }
+    }
+}
+
+impl From<proto::MoveUserLocationRequest> for RequestMoveUserLocation {
+    fn from(value: proto::MoveUserLocationRequest) -> Self {
+        Self {
+            country: value.country,
+            region: value.region,
+            city: value.city,
+            user_id: value.user_id,
+            new_country: value.new_country,
+            new_region: value.new_region,
+            new_city: value.new_city,
+            new_post_code: value.new_post_code,
+        }
+    }
+}
+
+impl From<proto::DeleteUserRequest> for RequestDeleteUser {
+    fn from(value: proto::DeleteUserRequest) -> Self {
+        Self {
+            country: value.country,
+            region: value.region,
+            city: value.city,
+            user_id: value.user_id,
+            reason: value.reason,
+        }
+    }
+}
+
+impl From<proto::RestoreUserRequest> for RequestRestoreUser {
+    fn from(value: proto::RestoreUserRequest) -> Self {
+        Self {
+            country: value.country,
+            region: value.region,
+            city: value.city,
+            user_id: value.user_id,
+        }
+    }
+}
+
+impl From<proto::PurgeUserRequest> for RequestPurgeUser {
+    fn from(value: proto::PurgeUserRequest) -> Self {
+        Self {
+            country: value.country,
+            region: value.region,
+            city: value.city,
+            user_id: value.user_id,
+        }
+    }
+}
+
+impl From<proto::ChangePasswordRequest> for RequestChangePassword {
+    fn from(value: proto::ChangePasswordRequest) -> Self {
+        Self {
+            country: value.country,
+            region: value.region,
+            city: value.city,
+            user_id: value.user_id,
+            current_password: value.current_password,
+            new_password: value.new_password,
+            revoke_other_sessions: value.revoke_other_sessions,
+            refresh_token: value.refresh_token,
+        }
+    }
+}
+
+impl From<PublicUser> for proto::User {
+    fn from(value: PublicUser) -> Self {
+        Self {
+            user_id: value.user_id,
+            user_name: value.user_name,
+            display_name: value.display_name,
+            email: value.email,
+            phone_number: value.phone_number,
+            role: value.role,
+            language: value.language,
+            address: value.address,
+            country: value.country,
+            region: value.region,
+            city: value.city,
+            post_code: value.post_code,
+            status: Some(value.status.into()),
+            organizations: value.organizations,
+            active_organization: value.active_organization,
+            owners: value.owners,
+            admins: value.admins,
+            other_emails: value.other_emails.unwrap_or_default(),
+            email_verified_at: value.email_verified_at,
+            password_recovered_at: value.password_recovered_at,
+            created_at: value.created_at,
+            updated_at: value.updated_at,
+        }
+    }
+}
+
+impl From<PublicUserStatus> for proto::UserStatus {
+    fn from(value: PublicUserStatus) -> Self {
+        Self {
+            status: value.status,
+            reason: value.reason,
+            changed_at: value.changed_at,
+            actor_id: value.actor_id,
+            note: value.note,
+        }
+    }
+}
+
+impl From<proto::GetUserStatusHistoryRequest> for RequestGetUserStatusHistory {
+    fn from(value: proto::GetUserStatusHistoryRequest) -> Self {
+        Self {
+            country: value.country,
+            region: value.region,
+            city: value.city,
+            user_id: value.user_id,
+            page_size: value.page_size,
+            page_token: value.page_token,
+        }
+    }
+}
+
+impl From<PublicUserStatusPage> for proto::GetUserStatusHistoryResponse {
+    fn from(value: PublicUserStatusPage) -> Self {
+        Self {
+            changes: value.changes.into_iter().map(Into::into).collect(),
+            next_page_token: value.next_page_token,
+        }
+    }
+}
+
+impl From<PublicUserPage> for proto::ListUsersResponse {
+    fn from(value: PublicUserPage) -> Self {
+        Self {
+            users: value.users.into_iter().map(Into::into).collect(),
+            next_page_token: value.next_page_token,
+        }
+    }
+}
+
+impl From<proto::LoginRequest> for RequestLogin {
+    fn from(value: proto::LoginRequest) -> Self {
+        Self {
+            login: value.login,
+            password: value.password,
+            client_ip: None,
+        }
+    }
+}
+
+impl From<AuthenticatedUser> for proto::LoginResponse {
+    fn from(value: AuthenticatedUser) -> Self {
+        Self {
+            user: Some(value.user.into()),
+            access_token: value.access_token,
+            access_token_expires_at: value.access_token_expires_at,
+            refresh_token: value.refresh_token,
+            refresh_token_expires_at: value.refresh_token_expires_at,
+            mfa_token: None,
+            mfa_token_expires_at: None,
+        }
+    }
+}
+
+impl From<LoginOutcome> for proto::LoginResponse {
+    fn from(value: LoginOutcome) -> Self {
+        match value {
+            LoginOutcome::Authenticated(authenticated) => (*authenticated).into(),
+            LoginOutcome::MfaRequired(pending) => Self {
+                mfa_token: Some(pending.mfa_token),
+                mfa_token_expires_at: Some(pending.mfa_token_expires_at),
+                ..Default::default()
+            },
+        }
+    }
+}
+
+impl From<TotpEnrollment> for proto::EnrollTotpResponse {
+    fn from(value: TotpEnrollment) -> Self {
+        Self {
+            secret: value.secret,
+            otpauth_uri: value.otpauth_uri,
+        }
+    }
+}
+
+impl From<AccessClaims> for proto::ValidateTokenResponse {
+    fn from(value: AccessClaims) -> Self {
+        Self {
+            user_id: value.user_id,
+            role: value.role,
+            country: value.country,
+            region: value.region,
+            city: value.city,
+            active_organization: value.active_organization,
+            expires_at: value.exp,
+        }
+    }
+}
+
+impl From<proto::RefreshRequest> for RequestRefreshToken {
+    fn from(value: proto::RefreshRequest) -> Self {
+        Self {
+            refresh_token: value.refresh_token,
+        }
+    }
+}
+
+impl From<proto::LogoutRequest> for RequestRefreshToken {
+    fn from(value: proto::LogoutRequest) -> Self {
+        Self {
+            refresh_token: value.refresh_token,
+        }
+    }
+}
+
+impl From<proto::RequestPasswordResetRequest> for RequestPasswordReset {
+    fn from(value: proto::RequestPasswordResetRequest) -> Self {
+        Self { email: value.email }
+    }
+}
+
+impl From<proto::UnlockUserRequest> for RequestUnlockUser {
+    fn from(value: proto::UnlockUserRequest) -> Self {
+        Self {
+            country: value.country,
+            region: value.region,
+            city: value.city,
+            user_id: value.user_id,
+        }
+    }
+}
+
+impl From<proto::ResetMfaRequest> for RequestResetMfa {
+    fn from(value: proto::ResetMfaRequest) -> Self {
+        Self {
+            country: value.country,
+            region: value.region,
+            city: value.city,
+            user_id: value.user_id,
+        }
+    }
+}
+
+impl From<proto::EnrollTotpRequest> for RequestEnrollTotp {
+    fn from(value: proto::EnrollTotpRequest) -> Self {
+        Self {
+            country: value.country,
+            region: value.region,
+            city: value.city,
+            user_id: value.user_id,
+        }
+    }
+}
+
+impl From<proto::ConfirmTotpRequest> for RequestConfirmTotp {
+    fn from(value: proto::ConfirmTotpRequest) -> Self {
+        Self {
+            country: value.country,
+            region: value.region,
+            city: value.city,
+            user_id: value.user_id,
+            code: value.code,
+        }
+    }
+}
+
+impl From<proto::VerifyMfaRequest> for RequestVerifyMfa {
+    fn from(value: proto::VerifyMfaRequest) -> Self {
+        Self {
+            mfa_token: value.mfa_token,
+            code: value.code,
+        }
+    }
+}
+
+impl From<proto::ConfirmPasswordResetRequest> for RequestConfirmPasswordReset {
+    fn from(value: proto::ConfirmPasswordResetRequest) -> Self {
+        Self {
+            email: value.email,
+            code: value.code,
+            new_password: value.new_password,
+        }
+    }
+}
+
+impl From<proto::CreateOrganizationRequest> for RequestCreateOrganization {
+    fn from(value: proto::CreateOrganizationRequest) -> Self {
+        Self {
+            name: value.name,
+            description: value.description,
+            owner_id: value.owner_id,
+        }
+    }
+}
+
+impl From<proto::GetOrganizationRequest> for RequestGetOrganization {
+    fn from(value: proto::GetOrganizationRequest) -> Self {
+        Self {
+            organization_id: value.organization_id,
+        }
+    }
+}
+
+impl From<proto::UpdateOrganizationRequest> for RequestUpdateOrganization {
+    fn from(value: proto::UpdateOrganizationRequest) -> Self {
+        Self {
+            organization_id: value.organization_id,
+            name: value.name,
+            description: value.description,
+        }
+    }
+}
+
+impl From<proto::DeleteOrganizationRequest> for RequestDeleteOrganization {
+    fn from(value: proto::DeleteOrganizationRequest) -> Self {
+        Self {
+            organization_id: value.organization_id,
+        }
+    }
+}
+
+impl From<proto::AddOrganizationMemberRequest> for RequestAddOrganizationMember {
+    fn from(value: proto::AddOrganizationMemberRequest) -> Self {
+        Self {
+            organization_id: value.organization_id,
+            user_id: value.user_id,
+            role: value.role,
+        }
+    }
+}
+
+impl From<proto::RemoveOrganizationMemberRequest> for RequestRemoveOrganizationMember {
+    fn from(value: proto::RemoveOrganizationMemberRequest) -> Self {
+        Self {
+            organization_id: value.organization_id,
+            user_id: value.user_id,
+        }
+    }
+}
+
+impl From<proto::ListOrganizationMembersRequest> for RequestListOrganizationMembers {
+    fn from(value: proto::ListOrganizationMembersRequest) -> Self {
+        Self {
+            organization_id: value.organization_id,
+            page_size: value.page_size,
+            page_token: value.page_token,
+        }
+    }
+}
+
+impl From<proto::ListUserOrganizationsRequest> for RequestListUserOrganizations {
+    fn from(value: proto::ListUserOrganizationsRequest) -> Self {
+        Self {
+            user_id: value.user_id,
+        }
+    }
+}
+
+impl From<proto::SwitchActiveOrganizationRequest> for RequestSwitchActiveOrganization {
+    fn from(value: proto::SwitchActiveOrganizationRequest) -> Self {
+        Self {
+            country: value.country,
+            region: value.region,
+            city: value.city,
+            user_id: value.user_id,
+            organization_id: value.organization_id,
+        }
+    }
+}
+
+impl From<proto::CreateInvitationRequest> for RequestCreateInvitation {
+    fn from(value: proto::CreateInvitationRequest) -> Self {
+        Self {
+            organization_id: value.organization_id,
+            email: value.email,
+            role: value.role,
+            expires_in_hours: value.expires_in_hours,
+        }
+    }
+}
+
+impl From<proto::ListInvitationsRequest> for RequestListInvitations {
+    fn from(value: proto::ListInvitationsRequest) -> Self {
+        Self {
+            organization_id: value.organization_id,
+        }
+    }
+}
+
+impl From<proto::RevokeInvitationRequest> for RequestRevokeInvitation {
+    fn from(value: proto::RevokeInvitationRequest) -> Self {
+        Self {
+            organization_id: value.organization_id,
+            invitation_id: value.invitation_id,
+        }
+    }
+}
+
+impl From<proto::AcceptInvitationRequest> for RequestAcceptInvitation {
+    fn from(value: proto::AcceptInvitationRequest) -> Self {
+        Self {
+            token: value.token,
+            user_id: value.user_id,
+        }
+    }
+}
+
+impl From<PublicOrganization> for proto::Organization {
+    fn from(value: PublicOrganization) -> Self {
+        Self {
+            organization_id: value.organization_id,
+            name: value.name,
+            description: value.description,
+            owner_id: value.owner_id,
+            created_at: value.created_at,
+            updated_at: value.updated_at,
+        }
+    }
+}
+
+impl From<PublicOrganizationMember> for proto::OrganizationMember {
+    fn from(value: PublicOrganizationMember) -> Self {
+        Self {
+            organization_id: value.organization_id,
+            user_id: value.user_id,
+            role: value.role,
+            joined_at: value.joined_at,
+        }
+    }
+}
+
+impl From<PublicOrganizationInvitation> for proto::Invitation {
+    fn from(value: PublicOrganizationInvitation) -> Self {
+        Self {
+            organization_id: value.organization_id,
+            invitation_id: value.invitation_id,
+            email: value.email,
+            role: value.role,
+            invited_by: value.invited_by,
+            created_at: value.created_at,
+            expires_at: value.expires_at,
+        }
+    }
+}
+
+impl From<PublicOrganizationMemberPage> for proto::ListOrganizationMembersResponse {
+    fn from(value: PublicOrganizationMemberPage) -> Self {
+        Self {
+            members: value.members.into_iter().map(Into::into).collect(),
+            next_page_token: value.next_page_token,
+        }
+    }
+}
+
+impl From<proto::GrantDelegationRequest> for RequestGrantDelegation {
+    fn from(value: proto::GrantDelegationRequest) -> Self {
+        Self {
+            user_id: value.user_id,
+            manager_id: value.manager_id,
+            role: value.role,
+            note: value.note,
+        }
+    }
+}
+
+impl From<proto::RevokeDelegationRequest> for RequestRevokeDelegation {
+    fn from(value: proto::RevokeDelegationRequest) -> Self {
+        Self {
+            user_id: value.user_id,
+            manager_id: value.manager_id,
+            note: value.note,
+        }
+    }
+}
+
+impl From<proto::ListManagedUsersRequest> for RequestListManagedUsers {
+    fn from(value: proto::ListManagedUsersRequest) -> Self {
+        Self {
+            manager_id: value.manager_id,
+        }
+    }
+}
+
+impl From<proto::ResetManagedUserPasswordRequest> for RequestResetManagedUserPassword {
+    fn from(value: proto::ResetManagedUserPasswordRequest) -> Self {
+        Self {
+            user_id: value.user_id,
+            new_password: value.new_password,
+            note: value.note,
+        }
+    }
+}
+
+impl From<proto::DisableManagedUserRequest> for RequestDisableManagedUser {
+    fn from(value: proto::DisableManagedUserRequest) -> Self {
+        Self {
+            user_id: value.user_id,
+            note: value.note,
+        }
+    }
+}
+
+impl From<proto::EnableManagedUserRequest> for RequestEnableManagedUser {
+    fn from(value: proto::EnableManagedUserRequest) -> Self {
+        Self {
+            user_id: value.user_id,
+            note: value.note,
+        }
+    }
+}
+
+impl From<proto::GetDelegationAuditRequest> for RequestGetDelegationAudit {
+    fn from(value: proto::GetDelegationAuditRequest) -> Self {
+        Self {
+            user_id: value.user_id,
+            page_size: value.page_size,
+            page_token: value.page_token,
+        }
+    }
+}
+
+impl From<PublicManagedUser> for proto::ManagedUser {
+    fn from(value: PublicManagedUser) -> Self {
+        Self {
+            user: Some(value.user.into()),
+            role: value.role,
+            granted_by: value.granted_by,
+            granted_at: value.granted_at,
+        }
+    }
+}
+
+impl From<PublicDelegationAuditEntry> for proto::DelegationAuditEntry {
+    fn from(value: PublicDelegationAuditEntry) -> Self {
+        Self {
+            user_id: value.user_id,
+            entry_id: value.entry_id,
+            actor_id: value.actor_id,
+            action: value.action,
+            manager_id: value.manager_id,
+            role: value.role,
+            note: value.note,
+            created_at: value.created_at,
+        }
+    }
+}
+
+impl From<PublicDelegationAuditPage> for proto::GetDelegationAuditResponse {
+    fn from(value: PublicDelegationAuditPage) -> Self {
+        Self {
+            entries: value.entries.into_iter().map(Into::into).collect(),
+            next_page_token: value.next_page_token,
+        }
+    }
+}
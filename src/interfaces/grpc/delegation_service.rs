@@ -0,0 +1,139 @@
+use super::{
+    identity::v1::{
+        delegation_service_server::DelegationService, DisableManagedUserRequest,
+        EnableManagedUserRequest, GetDelegationAuditRequest, GetDelegationAuditResponse,
+        GrantDelegationRequest, ListManagedUsersRequest, ListManagedUsersResponse,
+        ResetManagedUserPasswordRequest, RevokeDelegationRequest, UserResponse,
+    },
+    metadata::caller,
+    status::into_status,
+};
+use crate::{
+    application::{access::app::AccessAppInterface, delegation::app::DelegationAppInterface},
+    interfaces::delegation_handler::{
+        on_disable_managed_user, on_enable_managed_user, on_get_delegation_audit,
+        on_grant_delegation, on_list_managed_users, on_reset_managed_user_password,
+        on_revoke_delegation, DelegationHandler,
+    },
+};
+use tonic::{Request, Response, Status};
+
+#[derive(Clone, Debug)]
+pub struct DelegationGrpcService<DA: DelegationAppInterface, AA: AccessAppInterface> {
+    handler: DelegationHandler<DA, AA>,
+}
+
+impl<DA: DelegationAppInterface, AA: AccessAppInterface> DelegationGrpcService<DA, AA> {
+    pub fn new(handler: DelegationHandler<DA, AA>) -> Self {
+        Self { handler }
+    }
+}
+
+#[tonic::async_trait]
+impl<DA: DelegationAppInterface, AA: AccessAppInterface> DelegationService
+    for DelegationGrpcService<DA, AA>
+{
+    async fn grant_delegation(
+        &self,
+        request: Request<GrantDelegationRequest>,
+    ) -> Result<Response<UserResponse>, Status> {
+        let caller = caller(&request);
+        let user = on_grant_delegation(self.handler.clone(), caller, request.into_inner().into())
+            .await
+            .map_err(into_status)?;
+
+        Ok(Response::new(UserResponse {
+            user: Some(user.into()),
+        }))
+    }
+
+    async fn revoke_delegation(
+        &self,
+        request: Request<RevokeDelegationRequest>,
+    ) -> Result<Response<UserResponse>, Status> {
+        let caller = caller(&request);
+        let user = on_revoke_delegation(self.handler.clone(), caller, request.into_inner().into())
+            .await
+            .map_err(into_status)?;
+
+        Ok(Response::new(UserResponse {
+            user: Some(user.into()),
+        }))
+    }
+
+    async fn list_managed_users(
+        &self,
+        request: Request<ListManagedUsersRequest>,
+    ) -> Result<Response<ListManagedUsersResponse>, Status> {
+        let caller = caller(&request);
+        let users =
+            on_list_managed_users(self.handler.clone(), caller, request.into_inner().into())
+                .await
+                .map_err(into_status)?;
+
+        Ok(Response::new(ListManagedUsersResponse {
+            users: users.into_iter().map(Into::into).collect(),
+        }))
+    }
+
+    async fn reset_managed_user_password(
+        &self,
+        request: Request<ResetManagedUserPasswordRequest>,
+    ) -> Result<Response<UserResponse>, Status> {
+        let caller = caller(&request);
+        let user = on_reset_managed_user_password(
+            self.handler.clone(),
+            caller,
+            request.into_inner().into(),
+        )
+        .await
+        .map_err(into_status)?;
+
+        Ok(Response::new(UserResponse {
+            user: Some(user.into()),
+        }))
+    }
+
+    async fn disable_managed_user(
+        &self,
+        request: Request<DisableManagedUserRequest>,
+    ) -> Result<Response<UserResponse>, Status> {
+        let caller = caller(&request);
+        let user =
+            on_disable_managed_user(self.handler.clone(), caller, request.into_inner().into())
+                .await
+                .map_err(into_status)?;
+
+        Ok(Response::new(UserResponse {
+            user: Some(user.into()),
+        }))
+    }
+
+    async fn enable_managed_user(
+        &self,
+        request: Request<EnableManagedUserRequest>,
+    ) -> Result<Response<UserResponse>, Status> {
+        let caller = caller(&request);
+        let user =
+            on_enable_managed_user(self.handler.clone(), caller, request.into_inner().into())
+                .await
+                .map_err(into_status)?;
+
+        Ok(Response::new(UserResponse {
+            user: Some(user.into()),
+        }))
+    }
+
+    async fn get_delegation_audit(
+        &self,
+        request: Request<GetDelegationAuditRequest>,
+    ) -> Result<Response<GetDelegationAuditResponse>, Status> {
+        let caller = caller(&request);
+        let page =
+            on_get_delegation_audit(self.handler.clone(), caller, request.into_inner().into())
+                .await
+                .map_err(into_status)?;
+
+        Ok(Response::new(page.into()))
+    }
+}
@@ -0,0 +1,119 @@
+use super::status::into_status;
+use crate::application::{
+    access::app::Actor,
+    auth::token::{TokenError, TokenVerifier},
+};
+use anyhow::anyhow;
+use tonic::{service::Interceptor, Request, Status};
+
+/// Resolves the caller from `authorization: Bearer <access token>` metadata
+/// and attaches it to the request extensions as an [`Actor`]. Requests
+/// without a token pass on anonymously and the handlers decide what they may
+/// do; a token that is invalid or expired is rejected right away.
+#[derive(Clone, Debug)]
+pub struct AuthInterceptor {
+    verifier: TokenVerifier,
+}
+
+impl AuthInterceptor {
+    pub fn new(verifier: TokenVerifier) -> Self {
+        Self { verifier }
+    }
+}
+
+impl Interceptor for AuthInterceptor {
+    fn call(&mut self, mut request: Request<()>) -> Result<Request<()>, Status> {
+        let Some(value) = request.metadata().get("authorization") else {
+            return Ok(request);
+        };
+
+        let token = value
+            .to_str()
+            .ok()
+            .and_then(|value| value.strip_prefix("Bearer "))
+            .ok_or_else(|| into_status(anyhow!(TokenError::Invalid)))?;
+        let claims = self.verifier.verify(token.trim()).map_err(into_status)?;
+        let actor =
+            Actor::try_from(&claims).map_err(|_| into_status(anyhow!(TokenError::Invalid)))?;
+
+        request.extensions_mut().insert(actor);
+        Ok(request)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::application::auth::token::{
+        tests::{issuer, verifier, PUBLIC_KEY},
+        AccessClaims, DEFAULT_ISSUER,
+    };
+    use chrono::Duration;
+    use tonic::Code;
+    use uptop_core::common::utils::now_timeuuid;
+
+    fn interceptor() -> AuthInterceptor {
+        AuthInterceptor::new(verifier(PUBLIC_KEY, DEFAULT_ISSUER))
+    }
+
+    fn token(user_id: &str, ttl: Duration) -> String {
+        let claims = AccessClaims {
+            user_id: user_id.to_owned(),
+            role: "User".to_owned(),
+            ..Default::default()
+        };
+        issuer(DEFAULT_ISSUER, ttl).issue(claims).unwrap().token
+    }
+
+    fn request(authorization: &str) -> Request<()> {
+        let mut request = Request::new(());
+        request
+            .metadata_mut()
+            .insert("authorization", authorization.parse().unwrap());
+        request
+    }
+
+    #[test]
+    fn requests_without_a_token_pass_anonymously() {
+        let request = interceptor().call(Request::new(())).unwrap();
+
+        assert!(request.extensions().get::<Actor>().is_none());
+    }
+
+    #[test]
+    fn a_valid_token_attaches_the_caller() {
+        let user_id = now_timeuuid();
+        let bearer = format!(
+            "Bearer {}",
+            token(&user_id.to_string(), Duration::minutes(15))
+        );
+
+        let request = interceptor().call(request(&bearer)).unwrap();
+
+        assert_eq!(
+            request.extensions().get::<Actor>(),
+            Some(&Actor {
+                user_id,
+                role: "User".to_owned(),
+            })
+        );
+    }
+
+    #[test]
+    fn an_invalid_or_expired_token_is_rejected_not_ignored() {
+        let user_id = now_timeuuid().to_string();
+        let valid = token(&user_id, Duration::minutes(15));
+
+        for authorization in [
+            format!("Bearer {}", token(&user_id, Duration::minutes(-5))),
+            format!("Bearer {}", token("not a user id", Duration::minutes(15))),
+            format!("Bearer {valid}x"),
+            format!("Basic {valid}"),
+            valid,
+        ] {
+            let status = interceptor().call(request(&authorization)).unwrap_err();
+
+            assert_eq!(status.code(), Code::Unauthenticated, "{authorization}");
+        }
+    }
+}
@@ -0,0 +1,107 @@
+use super::{
+    identity::v1::user_service_server::UserService,
+    message::{message_server::Message, MessageRequest, MessageResponse},
+    metadata::caller,
+    status::malformed_payload,
+    user_service::UserGrpcService,
+};
+use crate::{
+    application::{
+        access::app::{AccessAppInterface, Actor},
+        topic::app::UserAppInterface,
+    },
+    interfaces::actions::IdentificationModuleServices,
+};
+use serde::{de::DeserializeOwned, Serialize};
+use tonic::{Request, Response, Status};
+
+/// Legacy JSON envelope kept for older clients. Every command is decoded into the
+/// typed `identity.v1` request and forwarded to [`UserGrpcService`]; failures and
+/// unknown commands are answered with an `ERROR` reply carrying the status
+/// message, as older clients expect. The caller of the envelope is passed on, so
+/// the same authorization rules apply.
+#[derive(Clone, Debug)]
+pub struct MessageService<UA: UserAppInterface, AA: AccessAppInterface> {
+    users: UserGrpcService<UA, AA>,
+}
+
+impl<UA: UserAppInterface, AA: AccessAppInterface> MessageService<UA, AA> {
+    pub fn new(users: UserGrpcService<UA, AA>) -> Self {
+        Self { users }
+    }
+
+    async fn forward(
+        &self,
+        action: IdentificationModuleServices,
+        caller: Option<Actor>,
+        message: &str,
+    ) -> Result<String, Box<Status>> {
+        match action {
+            IdentificationModuleServices::CreateUser => {
+                let response = self.users.create_user(decode(message, &caller)?).await?;
+                encode(&response.into_inner().user)
+            }
+            IdentificationModuleServices::GetUser => {
+                let response = self.users.get_user(decode(message, &caller)?).await?;
+                encode(&response.into_inner().user)
+            }
+            IdentificationModuleServices::GetUsers => {
+                let response = self.users.list_users(decode(message, &caller)?).await?;
+                encode(&response.into_inner())
+            }
+            IdentificationModuleServices::UpdateUser => {
+                let response = self.users.update_user(decode(message, &caller)?).await?;
+                encode(&response.into_inner().user)
+            }
+        }
+    }
+}
+
+#[tonic::async_trait]
+impl<UA: UserAppInterface, AA: AccessAppInterface> Message for MessageService<UA, AA> {
+    async fn send_message(
+        &self,
+        request: Request<MessageRequest>,
+    ) -> Result<Response<MessageResponse>, Status> {
+        let caller = caller(&request);
+        let payload = request.into_inner();
+
+        let response = match IdentificationModuleServices::action(&payload.id) {
+            Some(action) => match self.forward(action, caller, &payload.message).await {
+                Ok(message) => MessageResponse {
+                    id: "OK".to_owned(),
+                    message,
+                },
+                Err(status) => error_reply(status.message()),
+            },
+            None => error_reply(&format!("Unknown command {}", payload.id)),
+        };
+
+        Ok(Response::new(response))
+    }
+}
+
+fn error_reply(message: &str) -> MessageResponse {
+    MessageResponse {
+        id: "ERROR".to_owned(),
+        message: message.to_owned(),
+    }
+}
+
+// Statuses are boxed on the way through `forward`, they are large.
+fn decode<T: DeserializeOwned>(
+    message: &str,
+    caller: &Option<Actor>,
+) -> Result<Request<T>, Box<Status>> {
+    let mut request = serde_json::from_str(message)
+        .map(Request::new)
+        .map_err(|err| Box::new(malformed_payload(err)))?;
+    if let Some(caller) = caller {
+        request.extensions_mut().insert(caller.clone());
+    }
+    Ok(request)
+}
+
+fn encode<T: Serialize>(value: &T) -> Result<String, Box<Status>> {
+    serde_json::to_string(value).map_err(|err| Box::new(Status::internal(err.to_string())))
+}
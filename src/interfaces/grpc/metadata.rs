@@ -0,0 +1,32 @@
+use crate::application::access::app::Actor;
+use std::net::IpAddr;
+use tonic::Request;
+
+/// The caller [`AuthInterceptor`](super::interceptor::AuthInterceptor)
+/// resolved from the access token, if the request carried one.
+pub(crate) fn caller<T>(request: &Request<T>) -> Option<Actor> {
+    request.extensions().get::<Actor>().cloned()
+}
+
+/// The client address of `request`. When the peer is a local or private
+/// address it is taken to be a proxy, and the address it appended to
+/// `x-forwarded-for` is used instead.
+pub(crate) fn client_ip<T>(request: &Request<T>) -> Option<String> {
+    let peer = request.remote_addr().map(|addr| addr.ip());
+
+    let behind_proxy = peer.is_none_or(|ip| match ip {
+        IpAddr::V4(ip) => ip.is_loopback() || ip.is_private(),
+        IpAddr::V6(ip) => ip.is_loopback(),
+    });
+    let forwarded = request
+        .metadata()
+        .get("x-forwarded-for")
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.rsplit(',').next())
+        .and_then(|value| value.trim().parse::<IpAddr>().ok());
+
+    match (behind_proxy, forwarded) {
+        (true, Some(ip)) => Some(ip.to_string()),
+        _ => peer.map(|ip| ip.to_string()),
+    }
+}
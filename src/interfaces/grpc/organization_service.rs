@@ -0,0 +1,235 @@
+use super::{
+    identity::v1::{
+        organization_service_server::OrganizationService, AcceptInvitationRequest,
+        AddOrganizationMemberRequest, CreateInvitationRequest, CreateOrganizationRequest,
+        DeleteOrganizationRequest, DeleteOrganizationResponse, GetOrganizationRequest,
+        InvitationResponse, ListInvitationsRequest, ListInvitationsResponse,
+        ListOrganizationMembersRequest, ListOrganizationMembersResponse,
+        ListUserOrganizationsRequest, ListUserOrganizationsResponse, OrganizationMemberResponse,
+        OrganizationResponse, RemoveOrganizationMemberRequest, RemoveOrganizationMemberResponse,
+        RevokeInvitationRequest, RevokeInvitationResponse, SwitchActiveOrganizationRequest,
+        UpdateOrganizationRequest, UserResponse,
+    },
+    metadata::caller,
+    status::into_status,
+};
+use crate::{
+    application::{access::app::AccessAppInterface, organization::app::OrganizationAppInterface},
+    interfaces::organization_handler::{
+        on_accept_invitation, on_add_organization_member, on_create_invitation,
+        on_create_organization, on_delete_organization, on_get_organization, on_list_invitations,
+        on_list_organization_members, on_list_user_organizations, on_remove_organization_member,
+        on_revoke_invitation, on_switch_active_organization, on_update_organization,
+        OrganizationHandler,
+    },
+};
+use tonic::{Request, Response, Status};
+
+#[derive(Clone, Debug)]
+pub struct OrganizationGrpcService<OA: OrganizationAppInterface, AA: AccessAppInterface> {
+    handler: OrganizationHandler<OA, AA>,
+}
+
+impl<OA: OrganizationAppInterface, AA: AccessAppInterface> OrganizationGrpcService<OA, AA> {
+    pub fn new(handler: OrganizationHandler<OA, AA>) -> Self {
+        Self { handler }
+    }
+}
+
+#[tonic::async_trait]
+impl<OA: OrganizationAppInterface, AA: AccessAppInterface> OrganizationService
+    for OrganizationGrpcService<OA, AA>
+{
+    async fn create_organization(
+        &self,
+        request: Request<CreateOrganizationRequest>,
+    ) -> Result<Response<OrganizationResponse>, Status> {
+        let caller = caller(&request);
+        let organization =
+            on_create_organization(self.handler.clone(), caller, request.into_inner().into())
+                .await
+                .map_err(into_status)?;
+
+        Ok(Response::new(OrganizationResponse {
+            organization: Some(organization.into()),
+        }))
+    }
+
+    async fn get_organization(
+        &self,
+        request: Request<GetOrganizationRequest>,
+    ) -> Result<Response<OrganizationResponse>, Status> {
+        let caller = caller(&request);
+        let organization =
+            on_get_organization(self.handler.clone(), caller, request.into_inner().into())
+                .await
+                .map_err(into_status)?;
+
+        Ok(Response::new(OrganizationResponse {
+            organization: Some(organization.into()),
+        }))
+    }
+
+    async fn update_organization(
+        &self,
+        request: Request<UpdateOrganizationRequest>,
+    ) -> Result<Response<OrganizationResponse>, Status> {
+        let caller = caller(&request);
+        let organization =
+            on_update_organization(self.handler.clone(), caller, request.into_inner().into())
+                .await
+                .map_err(into_status)?;
+
+        Ok(Response::new(OrganizationResponse {
+            organization: Some(organization.into()),
+        }))
+    }
+
+    async fn delete_organization(
+        &self,
+        request: Request<DeleteOrganizationRequest>,
+    ) -> Result<Response<DeleteOrganizationResponse>, Status> {
+        let caller = caller(&request);
+        let deleted =
+            on_delete_organization(self.handler.clone(), caller, request.into_inner().into())
+                .await
+                .map_err(into_status)?;
+
+        Ok(Response::new(DeleteOrganizationResponse { deleted }))
+    }
+
+    async fn add_organization_member(
+        &self,
+        request: Request<AddOrganizationMemberRequest>,
+    ) -> Result<Response<OrganizationMemberResponse>, Status> {
+        let caller = caller(&request);
+        let member =
+            on_add_organization_member(self.handler.clone(), caller, request.into_inner().into())
+                .await
+                .map_err(into_status)?;
+
+        Ok(Response::new(OrganizationMemberResponse {
+            member: Some(member.into()),
+        }))
+    }
+
+    async fn remove_organization_member(
+        &self,
+        request: Request<RemoveOrganizationMemberRequest>,
+    ) -> Result<Response<RemoveOrganizationMemberResponse>, Status> {
+        let caller = caller(&request);
+        let removed = on_remove_organization_member(
+            self.handler.clone(),
+            caller,
+            request.into_inner().into(),
+        )
+        .await
+        .map_err(into_status)?;
+
+        Ok(Response::new(RemoveOrganizationMemberResponse { removed }))
+    }
+
+    async fn list_organization_members(
+        &self,
+        request: Request<ListOrganizationMembersRequest>,
+    ) -> Result<Response<ListOrganizationMembersResponse>, Status> {
+        let caller = caller(&request);
+        let page =
+            on_list_organization_members(self.handler.clone(), caller, request.into_inner().into())
+                .await
+                .map_err(into_status)?;
+
+        Ok(Response::new(page.into()))
+    }
+
+    async fn list_user_organizations(
+        &self,
+        request: Request<ListUserOrganizationsRequest>,
+    ) -> Result<Response<ListUserOrganizationsResponse>, Status> {
+        let caller = caller(&request);
+        let organizations =
+            on_list_user_organizations(self.handler.clone(), caller, request.into_inner().into())
+                .await
+                .map_err(into_status)?;
+
+        Ok(Response::new(ListUserOrganizationsResponse {
+            organizations: organizations.into_iter().map(Into::into).collect(),
+        }))
+    }
+
+    async fn switch_active_organization(
+        &self,
+        request: Request<SwitchActiveOrganizationRequest>,
+    ) -> Result<Response<UserResponse>, Status> {
+        let caller = caller(&request);
+        let user = on_switch_active_organization(
+            self.handler.clone(),
+            caller,
+            request.into_inner().into(),
+        )
+        .await
+        .map_err(into_status)?;
+
+        Ok(Response::new(UserResponse {
+            user: Some(user.into()),
+        }))
+    }
+
+    async fn create_invitation(
+        &self,
+        request: Request<CreateInvitationRequest>,
+    ) -> Result<Response<InvitationResponse>, Status> {
+        let caller = caller(&request);
+        let invitation =
+            on_create_invitation(self.handler.clone(), caller, request.into_inner().into())
+                .await
+                .map_err(into_status)?;
+
+        Ok(Response::new(InvitationResponse {
+            invitation: Some(invitation.into()),
+        }))
+    }
+
+    async fn list_invitations(
+        &self,
+        request: Request<ListInvitationsRequest>,
+    ) -> Result<Response<ListInvitationsResponse>, Status> {
+        let caller = caller(&request);
+        let invitations =
+            on_list_invitations(self.handler.clone(), caller, request.into_inner().into())
+                .await
+                .map_err(into_status)?;
+
+        Ok(Response::new(ListInvitationsResponse {
+            invitations: invitations.into_iter().map(Into::into).collect(),
+        }))
+    }
+
+    async fn revoke_invitation(
+        &self,
+        request: Request<RevokeInvitationRequest>,
+    ) -> Result<Response<RevokeInvitationResponse>, Status> {
+        let caller = caller(&request);
+        let revoked =
+            on_revoke_invitation(self.handler.clone(), caller, request.into_inner().into())
+                .await
+                .map_err(into_status)?;
+
+        Ok(Response::new(RevokeInvitationResponse { revoked }))
+    }
+
+    async fn accept_invitation(
+        &self,
+        request: Request<AcceptInvitationRequest>,
+    ) -> Result<Response<OrganizationMemberResponse>, Status> {
+        let caller = caller(&request);
+        let member =
+            on_accept_invitation(self.handler.clone(), caller, request.into_inner().into())
+                .await
+                .map_err(into_status)?;
+
+        Ok(Response::new(OrganizationMemberResponse {
+            member: Some(member.into()),
+        }))
+    }
+}
@@ -0,0 +1,328 @@
+use crate::{
+    application::{
+        access::permission::PermissionError,
+        auth::{
+            request::{
+                RequestLoginError, RequestMfaError, RequestPasswordResetError,
+                RequestRefreshTokenError,
+            },
+            token::TokenError,
+        },
+        delegation::request::RequestDelegationError,
+        organization::request::{RequestInvitationError, RequestOrganizationError},
+        topic::request::{
+            RequestChangePasswordError, RequestCreateUserError, RequestDeleteUserError,
+            RequestFindUserError, RequestListUsersError, RequestMoveUserLocationError,
+            RequestVerifyEmailError,
+        },
+    },
+    domain::{
+        organization::entity::OrganizationEntityError,
+        user::entity::{UserEntityError, UserStatusError},
+    },
+};
+use std::{collections::HashMap, time::Duration};
+use tonic::{Code, Status};
+use tonic_types::{ErrorDetails, StatusExt};
+use uptop_core::common::result::AppError;
+use validator::ValidationErrors;
+
+const ERROR_DOMAIN: &str = "identification.uptop";
+
+/// Maps application errors onto gRPC status codes. Every status carries a
+/// `google.rpc.ErrorInfo` with a machine-readable reason, and invalid input also
+/// carries a `google.rpc.BadRequest` listing the offending fields.
+pub(crate) fn into_status(err: anyhow::Error) -> Status {
+    if let Some(err) = err.downcast_ref::<RequestCreateUserError>() {
+        let (reason, field) = match err {
+            RequestCreateUserError::UserNameExisted { .. } => {
+                ("USER_NAME_ALREADY_EXISTS", "user_name")
+            }
+            RequestCreateUserError::EmailExisted { .. } => ("EMAIL_ALREADY_EXISTS", "email"),
+        };
+        let mut details = error_info(reason);
+        details.add_bad_request_violation(field, err.to_string());
+        return Status::with_error_details(Code::AlreadyExists, err.to_string(), details);
+    }
+
+    if let Some(err) = err.downcast_ref::<RequestFindUserError>() {
+        let reason = match err {
+            RequestFindUserError::UserNotFound => "USER_NOT_FOUND",
+        };
+        return Status::with_error_details(Code::NotFound, err.to_string(), error_info(reason));
+    }
+
+    if let Some(err) = err.downcast_ref::<RequestListUsersError>() {
+        let reason = match err {
+            RequestListUsersError::InvalidPageToken => "INVALID_PAGE_TOKEN",
+        };
+        let mut details = error_info(reason);
+        details.add_bad_request_violation("page_token", err.to_string());
+        return Status::with_error_details(Code::InvalidArgument, err.to_string(), details);
+    }
+
+    if let Some(err) = err.downcast_ref::<RequestChangePasswordError>() {
+        let (field, reason) = match err {
+            RequestChangePasswordError::IncorrectPassword => {
+                ("current_password", "CURRENT_PASSWORD_INCORRECT")
+            }
+            RequestChangePasswordError::SamePassword => ("new_password", "PASSWORD_UNCHANGED"),
+        };
+        let mut details = error_info(reason);
+        details.add_bad_request_violation(field, err.to_string());
+        return Status::with_error_details(Code::InvalidArgument, err.to_string(), details);
+    }
+
+    if let Some(err) = err.downcast_ref::<RequestDeleteUserError>() {
+        let reason = match err {
+            RequestDeleteUserError::AlreadyDeleted => "USER_ALREADY_DELETED",
+            RequestDeleteUserError::NotDeleted => "USER_NOT_DELETED",
+            RequestDeleteUserError::RestorePeriodEnded => "RESTORE_PERIOD_ENDED",
+        };
+        return Status::with_error_details(
+            Code::FailedPrecondition,
+            err.to_string(),
+            error_info(reason),
+        );
+    }
+
+    if let Some(err) = err.downcast_ref::<RequestMoveUserLocationError>() {
+        let mut details = error_info("LOCATION_UNCHANGED");
+        details.add_bad_request_violation("new_city", err.to_string());
+        return Status::with_error_details(Code::InvalidArgument, err.to_string(), details);
+    }
+
+    if let Some(err) = err.downcast_ref::<RequestVerifyEmailError>() {
+        let (code, reason) = match err {
+            RequestVerifyEmailError::InvalidCode => {
+                (Code::InvalidArgument, "EMAIL_VERIFY_CODE_INVALID")
+            }
+            RequestVerifyEmailError::CodeExpired => {
+                (Code::FailedPrecondition, "EMAIL_VERIFY_CODE_EXPIRED")
+            }
+            RequestVerifyEmailError::AlreadyVerified => {
+                (Code::FailedPrecondition, "EMAIL_ALREADY_VERIFIED")
+            }
+        };
+        return Status::with_error_details(code, err.to_string(), error_info(reason));
+    }
+
+    if let Some(err) = err.downcast_ref::<RequestLoginError>() {
+        let (code, reason, retry_after) = match err {
+            RequestLoginError::InvalidCredentials => {
+                (Code::Unauthenticated, "INVALID_CREDENTIALS", None)
+            }
+            RequestLoginError::AccountDisabled => {
+                (Code::PermissionDenied, "ACCOUNT_DISABLED", None)
+            }
+            RequestLoginError::AccountDeleted => (Code::PermissionDenied, "ACCOUNT_DELETED", None),
+            RequestLoginError::AccountLocked {
+                retry_after_seconds,
+            } => (
+                Code::PermissionDenied,
+                "ACCOUNT_LOCKED",
+                Some(*retry_after_seconds),
+            ),
+            RequestLoginError::TooManyAttempts {
+                retry_after_seconds,
+            } => (
+                Code::ResourceExhausted,
+                "TOO_MANY_LOGIN_ATTEMPTS",
+                Some(*retry_after_seconds),
+            ),
+        };
+        let mut details = error_info(reason);
+        if let Some(seconds) = retry_after {
+            details.set_retry_info(Some(Duration::from_secs(seconds.max(0) as u64)));
+        }
+        return Status::with_error_details(code, err.to_string(), details);
+    }
+
+    if let Some(err) = err.downcast_ref::<RequestRefreshTokenError>() {
+        let reason = match err {
+            RequestRefreshTokenError::InvalidRefreshToken => "REFRESH_TOKEN_INVALID",
+            RequestRefreshTokenError::RefreshTokenReused => "REFRESH_TOKEN_REUSED",
+        };
+        return Status::with_error_details(
+            Code::Unauthenticated,
+            err.to_string(),
+            error_info(reason),
+        );
+    }
+
+    if let Some(err) = err.downcast_ref::<RequestMfaError>() {
+        let (code, reason) = match err {
+            RequestMfaError::AlreadyEnrolled => (Code::AlreadyExists, "MFA_ALREADY_ENROLLED"),
+            RequestMfaError::NotEnrolled => (Code::FailedPrecondition, "MFA_NOT_ENROLLED"),
+            RequestMfaError::InvalidCode => (Code::Unauthenticated, "MFA_CODE_INVALID"),
+            RequestMfaError::InvalidChallenge => (Code::Unauthenticated, "MFA_TOKEN_INVALID"),
+        };
+        return Status::with_error_details(code, err.to_string(), error_info(reason));
+    }
+
+    if let Some(err) = err.downcast_ref::<RequestPasswordResetError>() {
+        let (code, reason) = match err {
+            RequestPasswordResetError::InvalidCode => {
+                (Code::InvalidArgument, "PASSWORD_RESET_CODE_INVALID")
+            }
+            RequestPasswordResetError::CodeExpired => {
+                (Code::FailedPrecondition, "PASSWORD_RESET_CODE_EXPIRED")
+            }
+        };
+        return Status::with_error_details(code, err.to_string(), error_info(reason));
+    }
+
+    if let Some(err) = err.downcast_ref::<TokenError>() {
+        let reason = match err {
+            TokenError::Expired => "TOKEN_EXPIRED",
+            TokenError::Invalid => "TOKEN_INVALID",
+        };
+        return Status::with_error_details(
+            Code::Unauthenticated,
+            err.to_string(),
+            error_info(reason),
+        );
+    }
+
+    if let Some(errors) = err.downcast_ref::<ValidationErrors>() {
+        let mut details = error_info("VALIDATION_FAILED");
+        for (field, field_errors) in errors.field_errors() {
+            for field_error in field_errors {
+                let description = match &field_error.message {
+                    Some(message) => message.to_string(),
+                    None => field_error.code.to_string(),
+                };
+                details.add_bad_request_violation(field.to_string(), description);
+            }
+        }
+        return Status::with_error_details(Code::InvalidArgument, "Invalid request", details);
+    }
+
+    if let Some(err) = err.downcast_ref::<UserEntityError>() {
+        let (reason, field) = match err {
+            UserEntityError::Status { .. } => ("UNKNOWN_STATUS", "status"),
+            UserEntityError::Reason { .. } => ("UNKNOWN_STATUS_REASON", "status"),
+            UserEntityError::Role { .. } => ("UNKNOWN_ROLE", "role"),
+            UserEntityError::DelegationRole { .. } => ("UNKNOWN_DELEGATION_ROLE", "role"),
+        };
+        let mut details = error_info(reason);
+        details.add_bad_request_violation(field, err.to_string());
+        return Status::with_error_details(Code::InvalidArgument, err.to_string(), details);
+    }
+
+    if let Some(err) = err.downcast_ref::<RequestOrganizationError>() {
+        let (code, reason) = match err {
+            RequestOrganizationError::OrganizationNotFound => {
+                (Code::NotFound, "ORGANIZATION_NOT_FOUND")
+            }
+            RequestOrganizationError::NotAMember => {
+                (Code::FailedPrecondition, "NOT_ORGANIZATION_MEMBER")
+            }
+            RequestOrganizationError::OwnerUnchangeable => {
+                (Code::FailedPrecondition, "ORGANIZATION_OWNER_UNCHANGEABLE")
+            }
+            RequestOrganizationError::OwnerRoleTaken => {
+                (Code::InvalidArgument, "ORGANIZATION_OWNER_ROLE_TAKEN")
+            }
+        };
+        return Status::with_error_details(code, err.to_string(), error_info(reason));
+    }
+
+    if let Some(err) = err.downcast_ref::<RequestDelegationError>() {
+        let (code, reason) = match err {
+            RequestDelegationError::SelfDelegation => (Code::InvalidArgument, "SELF_DELEGATION"),
+            RequestDelegationError::ManagerNotFound => (Code::NotFound, "MANAGER_NOT_FOUND"),
+            RequestDelegationError::NotAManager => (Code::FailedPrecondition, "NOT_A_MANAGER"),
+        };
+        return Status::with_error_details(code, err.to_string(), error_info(reason));
+    }
+
+    if let Some(err) = err.downcast_ref::<RequestInvitationError>() {
+        let (code, reason, retry_after) = match err {
+            RequestInvitationError::InvitationNotFound => {
+                (Code::NotFound, "INVITATION_NOT_FOUND", None)
+            }
+            RequestInvitationError::InvalidToken => {
+                (Code::InvalidArgument, "INVITATION_TOKEN_INVALID", None)
+            }
+            RequestInvitationError::Expired => {
+                (Code::FailedPrecondition, "INVITATION_EXPIRED", None)
+            }
+            RequestInvitationError::EmailMismatch => {
+                (Code::PermissionDenied, "INVITATION_EMAIL_MISMATCH", None)
+            }
+            RequestInvitationError::AlreadyAMember => {
+                (Code::AlreadyExists, "ALREADY_ORGANIZATION_MEMBER", None)
+            }
+            RequestInvitationError::TooManyInvitations {
+                retry_after_seconds,
+            } => (
+                Code::ResourceExhausted,
+                "TOO_MANY_INVITATIONS",
+                Some(*retry_after_seconds),
+            ),
+        };
+        let mut details = error_info(reason);
+        if let Some(seconds) = retry_after {
+            details.set_retry_info(Some(Duration::from_secs(seconds.max(0) as u64)));
+        }
+        return Status::with_error_details(code, err.to_string(), details);
+    }
+
+    if let Some(err) = err.downcast_ref::<OrganizationEntityError>() {
+        let mut details = error_info("UNKNOWN_ORGANIZATION_ROLE");
+        details.add_bad_request_violation("role", err.to_string());
+        return Status::with_error_details(Code::InvalidArgument, err.to_string(), details);
+    }
+
+    if let Some(err) = err.downcast_ref::<UserStatusError>() {
+        let (code, reason) = match err {
+            UserStatusError::InvalidReason { .. } => {
+                (Code::InvalidArgument, "INVALID_STATUS_REASON")
+            }
+            UserStatusError::IllegalTransition { .. } => {
+                (Code::FailedPrecondition, "ILLEGAL_STATUS_TRANSITION")
+            }
+            UserStatusError::NotPermitted { .. } => {
+                (Code::PermissionDenied, "STATUS_CHANGE_NOT_PERMITTED")
+            }
+            UserStatusError::Changed => (Code::Aborted, "STATUS_CHANGED_CONCURRENTLY"),
+        };
+        let mut details = error_info(reason);
+        if let UserStatusError::InvalidReason { .. } = err {
+            details.add_bad_request_violation("status", err.to_string());
+        }
+        return Status::with_error_details(code, err.to_string(), details);
+    }
+
+    if let Some(err) = err.downcast_ref::<PermissionError>() {
+        let (code, reason) = match err {
+            PermissionError::Unauthenticated => (Code::Unauthenticated, "UNAUTHENTICATED"),
+            PermissionError::Unknown { .. } => (Code::InvalidArgument, "UNKNOWN_PERMISSION"),
+            PermissionError::Denied { .. } => (Code::PermissionDenied, "PERMISSION_DENIED"),
+        };
+        return Status::with_error_details(code, err.to_string(), error_info(reason));
+    }
+
+    if let Some(AppError::BadRequest { msg }) = err.downcast_ref::<AppError>() {
+        return Status::with_error_details(Code::InvalidArgument, msg, error_info("BAD_REQUEST"));
+    }
+
+    tracing::error!("{err:?}");
+    Status::with_error_details(
+        Code::Internal,
+        "Internal server error",
+        error_info("INTERNAL"),
+    )
+}
+
+/// Builds the status for a payload that could not be decoded at all.
+pub(crate) fn malformed_payload(err: impl ToString) -> Status {
+    let mut details = error_info("MALFORMED_PAYLOAD");
+    details.add_bad_request_violation("message", err.to_string());
+    Status::with_error_details(Code::InvalidArgument, "Malformed payload", details)
+}
+
+fn error_info(reason: &str) -> ErrorDetails {
+    ErrorDetails::with_error_info(reason, ERROR_DOMAIN, HashMap::<String, String>::new())
+}
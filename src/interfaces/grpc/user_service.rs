@@ -0,0 +1,211 @@
+use super::{
+    identity::v1::{
+        user_service_server::UserService, ChangePasswordRequest, CreateUserRequest,
+        DeleteUserRequest, GetUserByIdRequest, GetUserRequest, GetUserStatusHistoryRequest,
+        GetUserStatusHistoryResponse, ListUsersRequest, ListUsersResponse, MoveUserLocationRequest,
+        PurgeUserRequest, RestoreUserRequest, UpdateUserRequest, UpdateUserStatusRequest,
+        UpdateUserStatusResponse, UserResponse, VerifyEmailRequest,
+    },
+    metadata::caller,
+    status::into_status,
+};
+use crate::{
+    application::{access::app::AccessAppInterface, topic::app::UserAppInterface},
+    interfaces::user_handler::{
+        on_change_password, on_create_new_user, on_delete_user, on_find_user, on_find_user_by_id,
+        on_find_users, on_get_user_status_history, on_move_user_location, on_purge_user,
+        on_restore_user, on_update_user, on_update_user_status, on_verify_email, UserHandler,
+    },
+};
+use tonic::{Request, Response, Status};
+
+#[derive(Clone, Debug)]
+pub struct UserGrpcService<UA: UserAppInterface, AA: AccessAppInterface> {
+    handler: UserHandler<UA, AA>,
+}
+
+impl<UA: UserAppInterface, AA: AccessAppInterface> UserGrpcService<UA, AA> {
+    pub fn new(handler: UserHandler<UA, AA>) -> Self {
+        Self { handler }
+    }
+}
+
+#[tonic::async_trait]
+impl<UA: UserAppInterface, AA: AccessAppInterface> UserService for UserGrpcService<UA, AA> {
+    async fn create_user(
+        &self,
+        request: Request<CreateUserRequest>,
+    ) -> Result<Response<UserResponse>, Status> {
+        let caller = caller(&request);
+        let user = on_create_new_user(self.handler.clone(), caller, request.into_inner().into())
+            .await
+            .map_err(into_status)?;
+
+        Ok(Response::new(UserResponse {
+            user: Some(user.into()),
+        }))
+    }
+
+    async fn get_user(
+        &self,
+        request: Request<GetUserRequest>,
+    ) -> Result<Response<UserResponse>, Status> {
+        let caller = caller(&request);
+        let user = on_find_user(self.handler.clone(), caller, request.into_inner().into())
+            .await
+            .map_err(into_status)?;
+
+        Ok(Response::new(UserResponse {
+            user: Some(user.into()),
+        }))
+    }
+
+    async fn get_user_by_id(
+        &self,
+        request: Request<GetUserByIdRequest>,
+    ) -> Result<Response<UserResponse>, Status> {
+        let caller = caller(&request);
+        let user = on_find_user_by_id(self.handler.clone(), caller, request.into_inner().into())
+            .await
+            .map_err(into_status)?;
+
+        Ok(Response::new(UserResponse {
+            user: Some(user.into()),
+        }))
+    }
+
+    async fn list_users(
+        &self,
+        request: Request<ListUsersRequest>,
+    ) -> Result<Response<ListUsersResponse>, Status> {
+        let caller = caller(&request);
+        let page = on_find_users(self.handler.clone(), caller, request.into_inner().into())
+            .await
+            .map_err(into_status)?;
+
+        Ok(Response::new(page.into()))
+    }
+
+    async fn update_user(
+        &self,
+        request: Request<UpdateUserRequest>,
+    ) -> Result<Response<UserResponse>, Status> {
+        let caller = caller(&request);
+        let user = on_update_user(self.handler.clone(), caller, request.into_inner().into())
+            .await
+            .map_err(into_status)?;
+
+        Ok(Response::new(UserResponse {
+            user: Some(user.into()),
+        }))
+    }
+
+    async fn update_user_status(
+        &self,
+        request: Request<UpdateUserStatusRequest>,
+    ) -> Result<Response<UpdateUserStatusResponse>, Status> {
+        let caller = caller(&request);
+        let updated =
+            on_update_user_status(self.handler.clone(), caller, request.into_inner().into())
+                .await
+                .map_err(into_status)?;
+
+        Ok(Response::new(UpdateUserStatusResponse { updated }))
+    }
+
+    async fn verify_email(
+        &self,
+        request: Request<VerifyEmailRequest>,
+    ) -> Result<Response<UserResponse>, Status> {
+        let user = on_verify_email(self.handler.clone(), request.into_inner().into())
+            .await
+            .map_err(into_status)?;
+
+        Ok(Response::new(UserResponse {
+            user: Some(user.into()),
+        }))
+    }
+
+    async fn change_password(
+        &self,
+        request: Request<ChangePasswordRequest>,
+    ) -> Result<Response<UserResponse>, Status> {
+        let caller = caller(&request);
+        let user = on_change_password(self.handler.clone(), caller, request.into_inner().into())
+            .await
+            .map_err(into_status)?;
+
+        Ok(Response::new(UserResponse {
+            user: Some(user.into()),
+        }))
+    }
+
+    async fn move_user_location(
+        &self,
+        request: Request<MoveUserLocationRequest>,
+    ) -> Result<Response<UserResponse>, Status> {
+        let caller = caller(&request);
+        let user = on_move_user_location(self.handler.clone(), caller, request.into_inner().into())
+            .await
+            .map_err(into_status)?;
+
+        Ok(Response::new(UserResponse {
+            user: Some(user.into()),
+        }))
+    }
+
+    async fn delete_user(
+        &self,
+        request: Request<DeleteUserRequest>,
+    ) -> Result<Response<UserResponse>, Status> {
+        let caller = caller(&request);
+        let user = on_delete_user(self.handler.clone(), caller, request.into_inner().into())
+            .await
+            .map_err(into_status)?;
+
+        Ok(Response::new(UserResponse {
+            user: Some(user.into()),
+        }))
+    }
+
+    async fn restore_user(
+        &self,
+        request: Request<RestoreUserRequest>,
+    ) -> Result<Response<UserResponse>, Status> {
+        let caller = caller(&request);
+        let user = on_restore_user(self.handler.clone(), caller, request.into_inner().into())
+            .await
+            .map_err(into_status)?;
+
+        Ok(Response::new(UserResponse {
+            user: Some(user.into()),
+        }))
+    }
+
+    async fn purge_user(
+        &self,
+        request: Request<PurgeUserRequest>,
+    ) -> Result<Response<UserResponse>, Status> {
+        let caller = caller(&request);
+        let user = on_purge_user(self.handler.clone(), caller, request.into_inner().into())
+            .await
+            .map_err(into_status)?;
+
+        Ok(Response::new(UserResponse {
+            user: Some(user.into()),
+        }))
+    }
+
+    async fn get_user_status_history(
+        &self,
+        request: Request<GetUserStatusHistoryRequest>,
+    ) -> Result<Response<GetUserStatusHistoryResponse>, Status> {
+        let caller = caller(&request);
+        let page =
+            on_get_user_status_history(self.handler.clone(), caller, request.into_inner().into())
+                .await
+                .map_err(into_status)?;
+
+        Ok(Response::new(page.into()))
+    }
+}
@@ -1,2 +1,7 @@
 pub mod actions;
+pub mod auth_handler;
+pub mod delegation_handler;
+pub mod grpc;
+pub mod organization_handler;
+pub mod purge_job;
 pub mod user_handler;
@@ -0,0 +1,265 @@
+use crate::application::{
+    access::{
+        app::{
+            authorize, authorize_organization, authorize_user, AccessAppInterface, Actor, Resource,
+        },
+        permission::{Permission, PermissionError},
+    },
+    organization::{
+        app::OrganizationAppInterface,
+        request::{
+            RequestAcceptInvitation, RequestAddOrganizationMember, RequestCreateInvitation,
+            RequestCreateOrganization, RequestDeleteOrganization, RequestGetOrganization,
+            RequestListInvitations, RequestListOrganizationMembers, RequestListUserOrganizations,
+            RequestRemoveOrganizationMember, RequestRevokeInvitation,
+            RequestSwitchActiveOrganization, RequestUpdateOrganization,
+        },
+        response::{
+            PublicOrganization, PublicOrganizationInvitation, PublicOrganizationMember,
+            PublicOrganizationMemberPage,
+        },
+    },
+    topic::response::PublicUser,
+};
+use anyhow::bail;
+use std::sync::Arc;
+use uptop_core::common::result::AppResult;
+
+/// Every call needs a caller. Calls on an organization need the matching
+/// `org.*` permission in it. Creating an organization and switching the
+/// active one act for the caller, or for another user with a global role
+/// that may update every user.
+#[derive(Clone, Debug)]
+pub struct OrganizationHandler<OA: OrganizationAppInterface, AA: AccessAppInterface> {
+    pub organization_app: Arc<OA>,
+    pub access_app: Arc<AA>,
+}
+
+impl<OA: OrganizationAppInterface, AA: AccessAppInterface> OrganizationHandler<OA, AA> {
+    /// Fails unless `caller` may do `action` in the organization.
+    async fn authorize_organization(
+        &self,
+        caller: Option<&Actor>,
+        action: Permission,
+        organization_id: &str,
+    ) -> AppResult<Actor> {
+        authorize_organization(self.access_app.as_ref(), caller, action, organization_id).await
+    }
+
+    /// The user a call acts for: `user_id` when set, else the caller.
+    async fn acting_user(&self, caller: Option<&Actor>, user_id: &str) -> AppResult<String> {
+        let Some(actor) = caller else {
+            bail!(PermissionError::Unauthenticated)
+        };
+        if user_id.is_empty() || actor.is_user(user_id) {
+            return Ok(actor.user_id.to_string());
+        }
+        authorize(
+            self.access_app.as_ref(),
+            caller,
+            Permission::UserUpdate,
+            Resource::AllUsers,
+        )
+        .await?;
+        Ok(user_id.to_owned())
+    }
+
+    /// Fails unless `caller` may do `action` on the user `user_id`.
+    async fn authorize_user(
+        &self,
+        caller: Option<&Actor>,
+        action: Permission,
+        user_id: &str,
+    ) -> AppResult<Actor> {
+        authorize_user(self.access_app.as_ref(), caller, action, user_id).await
+    }
+}
+
+pub async fn on_create_organization<OA: OrganizationAppInterface, AA: AccessAppInterface>(
+    handler: OrganizationHandler<OA, AA>,
+    caller: Option<Actor>,
+    body: RequestCreateOrganization,
+) -> AppResult<PublicOrganization> {
+    let owner_id = handler.acting_user(caller.as_ref(), &body.owner_id).await?;
+    let req = RequestCreateOrganization { owner_id, ..body }.try_into_domain()?;
+    handler.organization_app.create_organization(req).await
+}
+
+pub async fn on_get_organization<OA: OrganizationAppInterface, AA: AccessAppInterface>(
+    handler: OrganizationHandler<OA, AA>,
+    caller: Option<Actor>,
+    query: RequestGetOrganization,
+) -> AppResult<PublicOrganization> {
+    handler
+        .authorize_organization(caller.as_ref(), Permission::OrgRead, &query.organization_id)
+        .await?;
+    let query = query.try_into_domain()?;
+    handler.organization_app.get_organization(query).await
+}
+
+pub async fn on_update_organization<OA: OrganizationAppInterface, AA: AccessAppInterface>(
+    handler: OrganizationHandler<OA, AA>,
+    caller: Option<Actor>,
+    body: RequestUpdateOrganization,
+) -> AppResult<PublicOrganization> {
+    handler
+        .authorize_organization(
+            caller.as_ref(),
+            Permission::OrgUpdate,
+            &body.organization_id,
+        )
+        .await?;
+    let req = body.try_into_domain()?;
+    handler.organization_app.update_organization(req).await
+}
+
+pub async fn on_delete_organization<OA: OrganizationAppInterface, AA: AccessAppInterface>(
+    handler: OrganizationHandler<OA, AA>,
+    caller: Option<Actor>,
+    body: RequestDeleteOrganization,
+) -> AppResult<bool> {
+    handler
+        .authorize_organization(
+            caller.as_ref(),
+            Permission::OrgDelete,
+            &body.organization_id,
+        )
+        .await?;
+    let req = body.try_into_domain()?;
+    handler.organization_app.delete_organization(req).await
+}
+
+pub async fn on_add_organization_member<OA: OrganizationAppInterface, AA: AccessAppInterface>(
+    handler: OrganizationHandler<OA, AA>,
+    caller: Option<Actor>,
+    body: RequestAddOrganizationMember,
+) -> AppResult<PublicOrganizationMember> {
+    handler
+        .authorize_organization(
+            caller.as_ref(),
+            Permission::OrgManageMembers,
+            &body.organization_id,
+        )
+        .await?;
+    let req = body.try_into_domain()?;
+    handler.organization_app.add_member(req).await
+}
+
+pub async fn on_remove_organization_member<OA: OrganizationAppInterface, AA: AccessAppInterface>(
+    handler: OrganizationHandler<OA, AA>,
+    caller: Option<Actor>,
+    body: RequestRemoveOrganizationMember,
+) -> AppResult<bool> {
+    handler
+        .authorize_organization(
+            caller.as_ref(),
+            Permission::OrgManageMembers,
+            &body.organization_id,
+        )
+        .await?;
+    let req = body.try_into_domain()?;
+    handler.organization_app.remove_member(req).await
+}
+
+pub async fn on_list_organization_members<OA: OrganizationAppInterface, AA: AccessAppInterface>(
+    handler: OrganizationHandler<OA, AA>,
+    caller: Option<Actor>,
+    query: RequestListOrganizationMembers,
+) -> AppResult<PublicOrganizationMemberPage> {
+    handler
+        .authorize_organization(caller.as_ref(), Permission::OrgRead, &query.organization_id)
+        .await?;
+    let query = query.try_into_domain()?;
+    handler.organization_app.list_members(query).await
+}
+
+pub async fn on_list_user_organizations<OA: OrganizationAppInterface, AA: AccessAppInterface>(
+    handler: OrganizationHandler<OA, AA>,
+    caller: Option<Actor>,
+    query: RequestListUserOrganizations,
+) -> AppResult<Vec<PublicOrganization>> {
+    handler
+        .authorize_user(caller.as_ref(), Permission::UserRead, &query.user_id)
+        .await?;
+    let query = query.try_into_domain()?;
+    handler
+        .organization_app
+        .list_user_organizations(query)
+        .await
+}
+
+pub async fn on_switch_active_organization<OA: OrganizationAppInterface, AA: AccessAppInterface>(
+    handler: OrganizationHandler<OA, AA>,
+    caller: Option<Actor>,
+    body: RequestSwitchActiveOrganization,
+) -> AppResult<PublicUser> {
+    let user_id = handler.acting_user(caller.as_ref(), &body.user_id).await?;
+    let req = RequestSwitchActiveOrganization { user_id, ..body }.try_into_domain()?;
+    handler
+        .organization_app
+        .switch_active_organization(req)
+        .await
+}
+
+pub async fn on_create_invitation<OA: OrganizationAppInterface, AA: AccessAppInterface>(
+    handler: OrganizationHandler<OA, AA>,
+    caller: Option<Actor>,
+    body: RequestCreateInvitation,
+) -> AppResult<PublicOrganizationInvitation> {
+    let inviter = handler
+        .authorize_organization(
+            caller.as_ref(),
+            Permission::OrgInvite,
+            &body.organization_id,
+        )
+        .await?;
+    let req = body.try_into_domain()?;
+    handler
+        .organization_app
+        .create_invitation(&inviter, req)
+        .await
+}
+
+pub async fn on_list_invitations<OA: OrganizationAppInterface, AA: AccessAppInterface>(
+    handler: OrganizationHandler<OA, AA>,
+    caller: Option<Actor>,
+    query: RequestListInvitations,
+) -> AppResult<Vec<PublicOrganizationInvitation>> {
+    handler
+        .authorize_organization(
+            caller.as_ref(),
+            Permission::OrgInvite,
+            &query.organization_id,
+        )
+        .await?;
+    let query = query.try_into_domain()?;
+    handler.organization_app.list_invitations(query).await
+}
+
+pub async fn on_revoke_invitation<OA: OrganizationAppInterface, AA: AccessAppInterface>(
+    handler: OrganizationHandler<OA, AA>,
+    caller: Option<Actor>,
+    body: RequestRevokeInvitation,
+) -> AppResult<bool> {
+    handler
+        .authorize_organization(
+            caller.as_ref(),
+            Permission::OrgInvite,
+            &body.organization_id,
+        )
+        .await?;
+    let req = body.try_into_domain()?;
+    handler.organization_app.revoke_invitation(req).await
+}
+
+pub async fn on_accept_invitation<OA: OrganizationAppInterface, AA: AccessAppInterface>(
+    handler: OrganizationHandler<OA, AA>,
+    caller: Option<Actor>,
+    body: RequestAcceptInvitation,
+) -> AppResult<PublicOrganizationMember> {
+    handler
+        .authorize_user(caller.as_ref(), Permission::UserUpdate, &body.user_id)
+        .await?;
+    let req = body.try_into_domain()?;
+    handler.organization_app.accept_invitation(req).await
+}
@@ -0,0 +1,18 @@
+use crate::application::topic::app::UserAppInterface;
+use std::{sync::Arc, time::Duration};
+
+pub const PURGE_INTERVAL: Duration = Duration::from_secs(60 * 60);
+
+/// Purges soft deleted users once their grace period ended, every `every`.
+/// Runs until the process exits.
+pub async fn run_purge_job<UA: UserAppInterface>(user_app: Arc<UA>, every: Duration) {
+    let mut ticker = tokio::time::interval(every);
+    loop {
+        ticker.tick().await;
+        match user_app.purge_expired_users().await {
+            Ok(0) => {}
+            Ok(purged) => tracing::info!("Purged {purged} deleted users"),
+            Err(err) => tracing::error!("Can not purge deleted users: {err:?}"),
+        }
+    }
+}
@@ -1,60 +1,244 @@
-use crate::application::topic::{
-    app::UserAppInterface,
-    request::{RequestCreateUser, RequestCreateUserError, RequestGetUser},
+use crate::application::{
+    access::{
+        app::{
+            authorize, authorize_organization, authorize_user, AccessAppInterface, Actor, Resource,
+        },
+        permission::{Permission, PermissionError},
+    },
+    topic::{
+        app::UserAppInterface,
+        request::{
+            RequestChangePassword, RequestCreateUser, RequestDeleteUser, RequestFindUserError,
+            RequestGetUser, RequestGetUserByPrimaryKey, RequestGetUserStatusHistory,
+            RequestListUsers, RequestMoveUserLocation, RequestPurgeUser, RequestRestoreUser,
+            RequestUpdateUser, RequestUpdateUserStatus, RequestVerifyEmail,
+        },
+        response::{PublicUser, PublicUserPage, PublicUserStatusPage},
+    },
 };
 use anyhow::bail;
 use std::sync::Arc;
 use uptop_core::common::result::AppResult;
 
+/// Every `on_*` function gets the caller resolved from the access token, if
+/// any. Sign up and email verification are public, everything else needs a
+/// caller with the matching [`Permission`].
 #[derive(Clone, Debug)]
-pub struct UserHandler<UA: UserAppInterface> {
+pub struct UserHandler<UA: UserAppInterface, AA: AccessAppInterface> {
     pub user_app: Arc<UA>,
+    pub access_app: Arc<AA>,
 }
 
-pub async fn on_create_new_user<UA: UserAppInterface>(
-    handler: UserHandler<UA>,
-    payload: String,
-) -> AppResult<String> {
-    let body: RequestCreateUser = serde_json::from_str(&payload)?;
-    let req = body.try_into_domain()?;
+impl<UA: UserAppInterface, AA: AccessAppInterface> UserHandler<UA, AA> {
+    /// Fails unless `caller` may do `action` on `resource`.
+    async fn authorize(
+        &self,
+        caller: Option<&Actor>,
+        action: Permission,
+        resource: Resource,
+    ) -> AppResult<Actor> {
+        authorize(self.access_app.as_ref(), caller, action, resource).await
+    }
 
-    let is_email_existed = handler
-        .user_app
-        .find_user(&RequestGetUser {
-            email: Some((*req.email).to_string()),
-            ..Default::default()
-        })
-        .await;
+    /// Like [`Self::authorize`] on the user `user_id`.
+    async fn authorize_user(
+        &self,
+        caller: Option<&Actor>,
+        action: Permission,
+        user_id: &str,
+    ) -> AppResult<Actor> {
+        authorize_user(self.access_app.as_ref(), caller, action, user_id).await
+    }
+}
 
-    if is_email_existed.is_ok() {
-        bail!(RequestCreateUserError::EmailExisted {
-            email: (*req.email).to_string()
-        })
+pub async fn on_create_new_user<UA: UserAppInterface, AA: AccessAppInterface>(
+    handler: UserHandler<UA, AA>,
+    caller: Option<Actor>,
+    body: RequestCreateUser,
+) -> AppResult<PublicUser> {
+    // Signing up is public, choosing the role or status of the account is not.
+    if body.role.is_some() || body.status.is_some() {
+        handler
+            .authorize(caller.as_ref(), Permission::UserUpdate, Resource::AllUsers)
+            .await?;
     }
+    // Joining an organization takes an invitation to it, checked by the app,
+    // or a caller managing its members.
+    if body.invitation_token.as_deref().is_none_or(str::is_empty) {
+        for company_id in body.company_id.iter().flatten() {
+            authorize_organization(
+                handler.access_app.as_ref(),
+                caller.as_ref(),
+                Permission::OrgManageMembers,
+                company_id,
+            )
+            .await?;
+        }
+    }
+    let req = body.try_into_domain()?;
+    handler.user_app.create_user(req).await
+}
 
-    let is_user_name_existed = handler
-        .user_app
-        .find_user(&RequestGetUser {
-            user_name: (*req.user_name).to_string(),
-            ..Default::default()
-        })
+pub async fn on_find_user<UA: UserAppInterface, AA: AccessAppInterface>(
+    handler: UserHandler<UA, AA>,
+    caller: Option<Actor>,
+    query: RequestGetUser,
+) -> AppResult<PublicUser> {
+    if caller.is_none() {
+        bail!(PermissionError::Unauthenticated)
+    }
+    let user = handler.user_app.find_user(&query).await?;
+    // A user the caller may not read looks missing, so emails and user names
+    // can not be probed for accounts.
+    let authorized = handler
+        .authorize_user(caller.as_ref(), Permission::UserRead, &user.user_id)
         .await;
+    match authorized {
+        Ok(_) => Ok(user),
+        Err(err) if err.is::<PermissionError>() => bail!(RequestFindUserError::UserNotFound),
+        Err(err) => Err(err),
+    }
+}
+
+pub async fn on_find_user_by_id<UA: UserAppInterface, AA: AccessAppInterface>(
+    handler: UserHandler<UA, AA>,
+    caller: Option<Actor>,
+    query: RequestGetUserByPrimaryKey,
+) -> AppResult<PublicUser> {
+    handler
+        .authorize_user(caller.as_ref(), Permission::UserRead, &query.user_id)
+        .await?;
+    let query = query.try_into_domain()?;
+    handler.user_app.find_user_by_id(&query).await
+}
+
+pub async fn on_find_users<UA: UserAppInterface, AA: AccessAppInterface>(
+    handler: UserHandler<UA, AA>,
+    caller: Option<Actor>,
+    query: RequestListUsers,
+) -> AppResult<PublicUserPage> {
+    handler
+        .authorize(caller.as_ref(), Permission::UserRead, Resource::AllUsers)
+        .await?;
+    let query = query.try_into_domain()?;
+    handler.user_app.find_users(&query).await
+}
+
+pub async fn on_update_user<UA: UserAppInterface, AA: AccessAppInterface>(
+    handler: UserHandler<UA, AA>,
+    caller: Option<Actor>,
+    body: RequestUpdateUser,
+) -> AppResult<PublicUser> {
+    handler
+        .authorize_user(caller.as_ref(), Permission::UserUpdate, &body.user_id)
+        .await?;
+    // Users can not promote themselves.
+    if body.role.is_some() {
+        handler
+            .authorize(caller.as_ref(), Permission::UserUpdate, Resource::AllUsers)
+            .await?;
+    }
+    let req = body.try_into_domain()?;
+    handler.user_app.update_user(req).await
+}
 
-    if is_user_name_existed.is_ok() {
-        bail!(RequestCreateUserError::UserNameExisted {
-            name: (*req.user_name).to_string()
-        })
+pub async fn on_update_user_status<UA: UserAppInterface, AA: AccessAppInterface>(
+    handler: UserHandler<UA, AA>,
+    caller: Option<Actor>,
+    payload: RequestUpdateUserStatus,
+) -> AppResult<bool> {
+    let actor = handler
+        .authorize_user(
+            caller.as_ref(),
+            Permission::UserUpdateStatus,
+            &payload.user_id,
+        )
+        .await?;
+    // The change is recorded as made by the caller, whatever the payload says.
+    let payload = RequestUpdateUserStatus {
+        actor_id: Some(actor.user_id.to_string()),
+        ..payload
     }
+    .try_into_domain()?;
+    handler.user_app.push_new_user_status(&payload).await
+}
+
+pub async fn on_verify_email<UA: UserAppInterface, AA: AccessAppInterface>(
+    handler: UserHandler<UA, AA>,
+    body: RequestVerifyEmail,
+) -> AppResult<PublicUser> {
+    let req = body.try_into_domain()?;
+    handler.user_app.verify_email(req).await
+}
+
+pub async fn on_change_password<UA: UserAppInterface, AA: AccessAppInterface>(
+    handler: UserHandler<UA, AA>,
+    caller: Option<Actor>,
+    body: RequestChangePassword,
+) -> AppResult<PublicUser> {
+    handler
+        .authorize_user(caller.as_ref(), Permission::UserUpdate, &body.user_id)
+        .await?;
+    let req = body.try_into_domain()?;
+    handler.user_app.change_password(req).await
+}
+
+pub async fn on_move_user_location<UA: UserAppInterface, AA: AccessAppInterface>(
+    handler: UserHandler<UA, AA>,
+    caller: Option<Actor>,
+    body: RequestMoveUserLocation,
+) -> AppResult<PublicUser> {
+    handler
+        .authorize_user(caller.as_ref(), Permission::UserUpdate, &body.user_id)
+        .await?;
+    let req = body.try_into_domain()?;
+    handler.user_app.move_user_location(req).await
+}
+
+pub async fn on_delete_user<UA: UserAppInterface, AA: AccessAppInterface>(
+    handler: UserHandler<UA, AA>,
+    caller: Option<Actor>,
+    body: RequestDeleteUser,
+) -> AppResult<PublicUser> {
+    handler
+        .authorize_user(caller.as_ref(), Permission::UserDelete, &body.user_id)
+        .await?;
+    let req = body.try_into_domain()?;
+    handler.user_app.delete_user(req).await
+}
 
-    let result = handler.user_app.create_user(req).await?;
-    Ok(serde_json::to_string(&result)?)
+pub async fn on_restore_user<UA: UserAppInterface, AA: AccessAppInterface>(
+    handler: UserHandler<UA, AA>,
+    caller: Option<Actor>,
+    body: RequestRestoreUser,
+) -> AppResult<PublicUser> {
+    handler
+        .authorize_user(caller.as_ref(), Permission::UserDelete, &body.user_id)
+        .await?;
+    let req = body.try_into_domain()?;
+    handler.user_app.restore_user(req).await
+}
+
+pub async fn on_purge_user<UA: UserAppInterface, AA: AccessAppInterface>(
+    handler: UserHandler<UA, AA>,
+    caller: Option<Actor>,
+    body: RequestPurgeUser,
+) -> AppResult<PublicUser> {
+    handler
+        .authorize_user(caller.as_ref(), Permission::UserPurge, &body.user_id)
+        .await?;
+    let req = body.try_into_domain()?;
+    handler.user_app.purge_user(req).await
 }
 
-pub async fn on_find_user<UA: UserAppInterface>(
-    handler: UserHandler<UA>,
-    payload: String,
-) -> AppResult<String> {
-    let query: RequestGetUser = serde_json::from_str(&payload)?;
-    let result = handler.user_app.find_user(&query).await?;
-    Ok(serde_json::to_string(&result)?)
+pub async fn on_get_user_status_history<UA: UserAppInterface, AA: AccessAppInterface>(
+    handler: UserHandler<UA, AA>,
+    caller: Option<Actor>,
+    query: RequestGetUserStatusHistory,
+) -> AppResult<PublicUserStatusPage> {
+    handler
+        .authorize_user(caller.as_ref(), Permission::UserRead, &query.user_id)
+        .await?;
+    let query = query.try_into_domain()?;
+    handler.user_app.get_user_status_history(query).await
 }